@@ -0,0 +1,39 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Benchmarks of the encryption of Pokémon data, done for every deposit and reception, and for
+//! every Pokémon read from a save file.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pkm_utils::pokemon::Pokemon;
+
+const GEN4_PARTY: &[u8] = include_bytes!("../fuzz/seeds/pokemon_deserialize/gen4_party");
+
+fn encryption(c: &mut Criterion) {
+    let encrypted = Pokemon::to_encrypted_data(GEN4_PARTY);
+
+    c.bench_function("encrypt gen 4 party", |b| {
+        b.iter(|| Pokemon::to_encrypted_data(black_box(GEN4_PARTY)))
+    });
+    c.bench_function("decrypt gen 4 party", |b| {
+        b.iter(|| Pokemon::to_decrypted_data(black_box(&encrypted)))
+    });
+}
+
+criterion_group!(benches, encryption);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Fuzz target for `GTSDeposit::from_base64`.
+//!
+//! The raw input is base64-encoded before being parsed, so that the fuzzer explores the deposit
+//! format itself rather than the base64 decoder. Any input must either be parsed or return an
+//! error, but never panic.
+#![no_main]
+
+use base64::{Engine, engine::general_purpose::URL_SAFE};
+use libfuzzer_sys::fuzz_target;
+use pkm_utils::gts::GTSDeposit;
+
+fuzz_target!(|data: &[u8]| {
+    let encoded = URL_SAFE.encode(data);
+    let _ = GTSDeposit::from_base64(&encoded, false);
+    let _ = GTSDeposit::from_base64(&encoded, true);
+});
@@ -0,0 +1,29 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Fuzz target for `Pokemon::try_deserialize`.
+//!
+//! Any input must either deserialize into a Pokémon or return an error, but never panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pkm_utils::pokemon::Pokemon;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Pokemon::try_deserialize(data);
+});
@@ -0,0 +1,340 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::{
+    env,
+    io::{Error, ErrorKind, Result},
+    net::Ipv4Addr,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use crate::{
+    data_dump::{DataTable, DumpFormat},
+    http_server::Generations,
+    pokemon_source::{unquote_path, SourceOrder},
+};
+use pkm_utils::pokemon::SaveFormat;
+
+/// Command-line options of the application.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Configuration file to load, instead of the default one.
+    pub config: Option<PathBuf>,
+    /// IP address to advertise to the consoles, instead of the auto-detected one.
+    pub advertise_ip: Option<Ipv4Addr>,
+    /// Longest time, in seconds, to cache the answers of the real DNS server for.
+    pub dns_cache_max_ttl: Option<u64>,
+    /// File to record the session to, for debugging.
+    pub record_session: Option<PathBuf>,
+    /// File to write the DNS activity to, apart from the rest of the log.
+    pub dns_log: Option<PathBuf>,
+    /// File with a recorded session to replay, instead of running the servers.
+    pub replay_session: Option<PathBuf>,
+    /// Whether to hex-dump the payloads of all GTS requests and responses.
+    pub verbose_protocol: bool,
+    /// Directory to save the deposited Pokémon to.
+    pub save_dir: Option<PathBuf>,
+    /// Format to save the deposited Pokémon in.
+    pub save_format: Option<SaveFormat>,
+    /// Pokémon file or directory to send to Gen 4 games, instead of prompting for it.
+    pub gen4_source: Option<PathBuf>,
+    /// Pokémon file or directory to send to Gen 5 games, instead of prompting for it.
+    pub gen5_source: Option<PathBuf>,
+    /// Pokémon file to send to the games of both generations, instead of prompting for it.
+    pub pokemon_file: Option<PathBuf>,
+    /// Order the Pokémon files of a directory source are sent in.
+    pub source_order: Option<SourceOrder>,
+    /// Whether to move each Pokémon file sent from a directory source to its `dispensed`
+    /// subdirectory.
+    pub dispense: bool,
+    /// Whether to move the dispensed Pokémon files back once all of them were sent.
+    pub dispense_replenish: bool,
+    /// Pokémon files to send, in order, to the next games receiving a Pokémon.
+    pub queue: Vec<PathBuf>,
+    /// Playlist of Pokémon files to queue.
+    pub queue_file: Option<PathBuf>,
+    /// Pokémon file or directory to list in the GTS search results.
+    pub search_pool: Option<PathBuf>,
+    /// Seed of the random GTS data of the receptions, to make it repeatable.
+    pub reception_seed: Option<u64>,
+    /// Whether to make the Pokémon sent appear as received through a link trade.
+    pub link_trade_origin: bool,
+    /// Token sent to the games in the token exchange, instead of the default one.
+    pub gts_token: Option<String>,
+    /// Status code answered to `info.asp`.
+    pub info_status: Option<u16>,
+    /// Headers overriding the default ones of the GTS responses, as (name, value) pairs.
+    pub headers: Vec<(String, String)>,
+    /// Whether to randomize the session ID set by the GTS responses.
+    pub random_session_id: bool,
+    /// Whether to apply safe legality fixes to the deposited Pokémon, before saving them.
+    pub auto_legalize: bool,
+    /// Whether to never send Pokémon to the games, only collecting their deposits.
+    pub deposit_only: bool,
+    /// Whether to accept the deposits, sending each deposited Pokémon back to its game.
+    pub accept_deposits: bool,
+    /// Whether to exit after a single Pokémon is sent to a game.
+    pub once: bool,
+    /// Whether to send each deposited Pokémon back to the next game receiving one.
+    pub relay: bool,
+    /// Whether to evolve the relayed Pokémon that evolve by trade.
+    pub relay_trade_evolution: bool,
+    /// Seconds between two summaries of the usage counters in the log, or 0 to never log them.
+    pub metrics_log_interval: Option<u64>,
+    /// Number of worker threads of the HTTP server.
+    pub http_workers: Option<usize>,
+    /// Generations of games to serve.
+    pub generations: Option<Generations>,
+    /// Data table to print, instead of running the servers.
+    pub dump_table: Option<DataTable>,
+    /// Format to print the data table in.
+    pub dump_format: DumpFormat,
+    /// Pokémon file to convert to the other generation, and file to write the result to, instead
+    /// of running the servers.
+    pub migrate: Option<(PathBuf, PathBuf)>,
+    /// Gen 4 or Gen 5 save file to extract the PC box Pokémon of, and directory to extract them
+    /// to, instead of running the servers.
+    pub extract_save: Option<(PathBuf, PathBuf)>,
+}
+
+impl Args {
+    /// Parses the command-line arguments the application was run with.
+    ///
+    /// Returns an error of kind `InvalidInput` if an unknown option is found, or if an option's
+    /// value is missing or invalid.
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(env::args().skip(1))
+    }
+
+    /// Parses the given command-line arguments (without the program name).
+    ///
+    /// # Arguments
+    /// * `args` - The command-line arguments to parse.
+    fn parse_from(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut parsed = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    parsed.config = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--advertise-ip" => {
+                    parsed.advertise_ip = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--dns-cache-max-ttl" => {
+                    parsed.dns_cache_max_ttl = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--record-session" => {
+                    parsed.record_session = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--dns-log" => {
+                    parsed.dns_log = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--replay-session" => {
+                    parsed.replay_session = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--verbose-protocol" => parsed.verbose_protocol = true,
+                "--save-dir" => {
+                    parsed.save_dir = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--save-format" => {
+                    parsed.save_format = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--gen4-source" => {
+                    parsed.gen4_source = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--gen5-source" => {
+                    parsed.gen5_source = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--pokemon-file" => {
+                    parsed.pokemon_file = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--source-order" => {
+                    parsed.source_order = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--dispense" => parsed.dispense = true,
+                "--dispense-replenish" => parsed.dispense_replenish = true,
+                "--queue" => {
+                    let files: String = Self::parse_value(&arg, args.next())?;
+                    parsed.queue.extend(
+                        files
+                            .split(',')
+                            .filter(|file| !file.is_empty())
+                            .map(|file| PathBuf::from(unquote_path(file))),
+                    );
+                }
+                "--pkm" => {
+                    let file: String = Self::parse_value(&arg, args.next())?;
+                    parsed.queue.push(PathBuf::from(unquote_path(&file)));
+                }
+                "--queue-file" => {
+                    parsed.queue_file = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--search-pool" => {
+                    parsed.search_pool = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--reception-seed" => {
+                    parsed.reception_seed = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--gts-token" => {
+                    parsed.gts_token = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--info-status" => {
+                    parsed.info_status = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--header" => {
+                    let header: String = Self::parse_value(&arg, args.next())?;
+                    let (name, value) = header.split_once(':').ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Invalid value for option {}: {}", arg, header),
+                        )
+                    })?;
+                    parsed
+                        .headers
+                        .push((name.trim().to_string(), value.trim().to_string()));
+                }
+                "--random-session-id" => parsed.random_session_id = true,
+                "--auto-legalize" => parsed.auto_legalize = true,
+                "--link-trade-origin" => parsed.link_trade_origin = true,
+                "--deposit-only" => parsed.deposit_only = true,
+                "--accept-deposits" => parsed.accept_deposits = true,
+                "--once" => parsed.once = true,
+                "--relay" => parsed.relay = true,
+                "--relay-trade-evolution" => parsed.relay_trade_evolution = true,
+                "--metrics-log-interval" => {
+                    parsed.metrics_log_interval = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--http-workers" => {
+                    let workers = Self::parse_value(&arg, args.next())?;
+                    if workers == 0 {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Invalid value for option {}: 0", arg),
+                        ));
+                    }
+                    parsed.http_workers = Some(workers);
+                }
+                "--generations" => {
+                    parsed.generations = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--dump-table" => {
+                    parsed.dump_table = Some(Self::parse_value(&arg, args.next())?);
+                }
+                "--dump-format" => parsed.dump_format = Self::parse_value(&arg, args.next())?,
+                "--migrate" => {
+                    let input = Self::parse_value(&arg, args.next())?;
+                    let output = Self::parse_value(&arg, args.next())?;
+                    parsed.migrate = Some((input, output));
+                }
+                "--extract-save" => {
+                    let save = Self::parse_value(&arg, args.next())?;
+                    let dir = Self::parse_value(&arg, args.next())?;
+                    parsed.extract_save = Some((save, dir));
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown option: {}\n{}", arg, Self::usage()),
+                    ));
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Parses the value given to a command-line option.
+    ///
+    /// # Arguments
+    /// * `option` - The name of the option, for error reporting.
+    /// * `value` - The value given to the option, if any.
+    fn parse_value<T: FromStr>(option: &str, value: Option<String>) -> Result<T> {
+        let value = value.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Missing value for option {}", option),
+            )
+        })?;
+
+        value.parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid value for option {}: {}", option, value),
+            )
+        })
+    }
+
+    /// Returns the usage message of the application.
+    fn usage() -> &'static str {
+        "Usage: gts-rs [OPTIONS]
+
+Options:
+  --config <FILE>           Load the settings from a TOML file (gts-rs.toml by default)
+  --advertise-ip <IP>       IP address the consoles should connect to (auto-detected by default)
+  --dns-cache-max-ttl <SECS>
+                            Cache the answers of the real DNS server for up to SECS seconds (300
+                            by default; 0 disables the cache)
+  --record-session <FILE>   Record the HTTP requests and DNS queries received to a file
+  --dns-log <FILE>          Write the DNS activity (the dns::rewrite log target) to a file, apart
+                            from the rest of the log
+  --replay-session <FILE>   Replay a recorded session through the GTS endpoints, and exit
+  --verbose-protocol        Hex-dump the payloads of all GTS requests and responses
+  --save-dir <DIR>          Directory to save the deposited Pokémon to (pokemon/ by default)
+  --save-format <FORMAT>    Format to save the deposited Pokémon in: pk (.pk4/.pk5 files, by
+                            default), ek (encrypted .ek4/.ek5 files), or json
+  --gen4-source <PATH>      Pokémon file, or directory of files, to send to Gen 4 games
+  --gen5-source <PATH>      Pokémon file, or directory of files, to send to Gen 5 games
+  --pokemon-file <FILE>     Pokémon file to send to games of both generations, re-read on every
+                            request (the per-generation sources take precedence)
+  --source-order <ORDER>    Order to send the files of a source directory in: sequential
+                            (alphabetical, by default), random, or shuffle
+  --dispense                Send each file of a source directory once, moving it to its
+                            dispensed/ subdirectory
+  --dispense-replenish      Like --dispense, moving the files back once all were sent
+  --queue <FILES>           Comma-separated Pokémon files to send, in order, to the next games
+                            receiving a Pokémon, before the sources
+  --queue-file <FILE>       Like --queue, with the files listed in a playlist, one per line
+  --pkm <FILE>              Queue a Pokémon file, like --queue; can be given several times
+  --search-pool <PATH>      Pokémon file, or directory of files, to list in GTS searches
+  --reception-seed <N>      Seed the random trainer data sent with each Pokémon, to make it repeatable
+  --link-trade-origin       Make the Pokémon sent appear as received through a link trade
+  --gts-token <TOKEN>       Token sent to the games when they request one (32 letters and digits)
+  --info-status <CODE>      Status code answered to info.asp (1, the GTS is available, by default)
+  --header <NAME:VALUE>     Override a header of the GTS responses (an empty value removes it)
+  --random-session-id       Give each profile a random session ID cookie
+  --auto-legalize           Apply safe legality fixes to deposited Pokémon before saving them
+  --deposit-only            Never send Pokémon to the games, only collect their deposits
+  --accept-deposits         Keep the deposited Pokémon, until they are taken back by their games
+  --once                    Exit after a single Pokémon is sent to a game
+  --relay                   Send each deposited Pokémon back to the next game receiving one
+  --relay-trade-evolution   Like --relay, evolving the Pokémon that evolve by trade
+  --metrics-log-interval <SECS>
+                            Log a summary of the usage counters every SECS seconds (3600 by
+                            default; 0 disables it)
+  --http-workers <N>        Number of worker threads of the HTTP server (1 by default)
+  --generations <LIST>      Generations of games to serve, e.g. 5 or 4,5 (both by default)
+  --dump-table <TABLE>      Print a data table and exit: species, moves, items-gen4, items-gen5,
+                            abilities, or natures
+  --dump-format <FORMAT>    Format of the printed data table: columns (default) or json
+  --migrate <IN> <OUT>      Convert a .pk4 file to a .pk5 one, or vice versa, and exit
+  --extract-save <SAV> <DIR>
+                            Save the PC box Pokémon of a Gen 4 or Gen 5 save file to DIR, and exit"
+    }
+}
@@ -0,0 +1,506 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Configuration of the application, loaded at startup.
+//!
+//! The settings are taken, from lowest to highest priority, from: their default values, the
+//! configuration file (`gts-rs.toml` by default), the `GTS_RS_*` environment variables, and the
+//! command-line options.
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{Error, ErrorKind, Result},
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{cli::Args, http_server::Generations, pokemon_source::SourceOrder};
+use pkm_utils::pokemon::SaveFormat;
+
+/// Configuration file loaded by default, if present in the working directory.
+pub const DEFAULT_CONFIG_FILE: &str = "gts-rs.toml";
+/// Prefix of the environment variables overriding the settings of the configuration file.
+const ENV_PREFIX: &str = "GTS_RS_";
+
+/// Settings of the application.
+///
+/// Every field can be set in the configuration file with its name, and overridden with the
+/// environment variable of its name in uppercase, prefixed by `GTS_RS_` (e.g. `GTS_RS_HTTP_PORT`),
+/// except `headers` and `queue`, which can only be set in the configuration file and the command
+/// line.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// IP address of the real DNS server, to proxy the DNS queries of the consoles to.
+    pub upstream_dns: Ipv4Addr,
+    /// IP address to advertise to the consoles, instead of the auto-detected one.
+    pub advertise_ip: Option<Ipv4Addr>,
+    /// Longest time, in seconds, to cache the answers of the real DNS server for, or 0 to not
+    /// cache them.
+    pub dns_cache_max_ttl: u64,
+    /// Port to listen to the GTS requests on. The consoles always connect to port 80, so it
+    /// should only be changed behind a proxy or port forwarding.
+    pub http_port: u16,
+    /// Number of worker threads of the HTTP server.
+    pub http_workers: usize,
+    /// Generations of games to serve, e.g. `[5]` to not mount the Gen 4 endpoints.
+    pub generations: Generations,
+    /// Directory to save the deposited Pokémon to.
+    pub save_dir: PathBuf,
+    /// Format to save the deposited Pokémon in: `pk` (`.pk4`/`.pk5` files), `ek` (encrypted
+    /// `.ek4`/`.ek5` files), or `json`.
+    pub save_format: SaveFormat,
+    /// Pokémon file or directory to send to Gen 4 games, instead of prompting for it.
+    pub gen4_source: Option<PathBuf>,
+    /// Pokémon file or directory to send to Gen 5 games, instead of prompting for it.
+    pub gen5_source: Option<PathBuf>,
+    /// Pokémon file to send to the games of both generations, instead of prompting for it. It is
+    /// read again on every request, so it can be replaced while running. The sources of each
+    /// generation take precedence over it.
+    pub pokemon_file: Option<PathBuf>,
+    /// Pokémon files to send, in order, to the next games receiving a Pokémon, before the sources
+    /// above.
+    pub queue: Vec<PathBuf>,
+    /// Playlist of Pokémon files to queue after those of `queue`: a text file with a path on each
+    /// line.
+    pub queue_file: Option<PathBuf>,
+    /// Order the Pokémon files of a directory source are sent in.
+    pub source_order: SourceOrder,
+    /// Whether to move each Pokémon file sent from a directory source to its `dispensed`
+    /// subdirectory, so that it is only sent once.
+    pub dispense: bool,
+    /// Whether to move the dispensed Pokémon files back once all of them were sent. Implies
+    /// `dispense`.
+    pub dispense_replenish: bool,
+    /// Pokémon file or directory to list in the GTS search results.
+    pub search_pool: Option<PathBuf>,
+    /// Seed of the random GTS data of the receptions (the trainer's profile ID and the requested
+    /// species), to make it repeatable. Random on every reception if not set.
+    pub reception_seed: Option<u64>,
+    /// Whether to make the Pokémon sent appear as received through a link trade, rewriting their
+    /// met location and date.
+    pub link_trade_origin: bool,
+    /// Whether to apply safe legality fixes to the deposited Pokémon, before saving them.
+    pub auto_legalize: bool,
+    /// Whether to never send Pokémon to the games, only collecting their deposits.
+    pub deposit_only: bool,
+    /// Whether to accept the deposits, sending each deposited Pokémon back to its game.
+    pub accept_deposits: bool,
+    /// Whether to exit after a single Pokémon is sent to a game.
+    pub once: bool,
+    /// Whether to send each deposited Pokémon back to the next game receiving one.
+    pub relay: bool,
+    /// Whether to evolve the relayed Pokémon that evolve by trade. Implies `relay`.
+    pub relay_trade_evolution: bool,
+    /// Token sent to the games in the token exchange, of 32 ASCII letters and digits, instead of
+    /// the default one.
+    pub gts_token: Option<String>,
+    /// Status code answered to `info.asp`; 1 means the GTS is available.
+    pub info_status: u16,
+    /// Headers overriding the default ones of the GTS responses. An empty value removes a header.
+    pub headers: BTreeMap<String, String>,
+    /// Whether to randomize the session ID set by the GTS responses.
+    pub random_session_id: bool,
+    /// Seconds between two summaries of the usage counters in the log, or 0 to never log them.
+    pub metrics_log_interval: u64,
+    /// Whether to hex-dump the payloads of all GTS requests and responses.
+    pub verbose_protocol: bool,
+    /// File to record the session to, for debugging.
+    pub record_session: Option<PathBuf>,
+    /// File to write the DNS activity to (the `dns::rewrite` log target), apart from the rest of
+    /// the log.
+    pub dns_log: Option<PathBuf>,
+    /// Log filter, in the `RUST_LOG` syntax (e.g. `info,dns::rewrite=debug`). The `RUST_LOG`
+    /// environment variable takes precedence over it. Defaults to the build's log level.
+    pub log: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            upstream_dns: Ipv4Addr::new(178, 62, 43, 212),
+            advertise_ip: None,
+            dns_cache_max_ttl: 300,
+            http_port: 80,
+            http_workers: 1,
+            generations: Generations::default(),
+            save_dir: PathBuf::from("pokemon"),
+            save_format: SaveFormat::default(),
+            gen4_source: None,
+            gen5_source: None,
+            pokemon_file: None,
+            queue: Vec::new(),
+            queue_file: None,
+            source_order: SourceOrder::default(),
+            dispense: false,
+            dispense_replenish: false,
+            search_pool: None,
+            reception_seed: None,
+            auto_legalize: false,
+            link_trade_origin: false,
+            deposit_only: false,
+            accept_deposits: false,
+            once: false,
+            relay: false,
+            relay_trade_evolution: false,
+            gts_token: None,
+            info_status: 1,
+            headers: BTreeMap::new(),
+            random_session_id: false,
+            metrics_log_interval: 3600,
+            verbose_protocol: false,
+            record_session: None,
+            dns_log: None,
+            log: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration, combining the configuration file, the environment variables, and
+    /// the command-line options.
+    ///
+    /// # Arguments
+    /// * `args` - The command-line options. If they include a configuration file, it is loaded
+    ///   instead of the default one.
+    ///
+    /// Returns an error of kind `NotFound` if the configuration file given in the command line
+    /// does not exist, or of kind `InvalidInput` if the configuration file or an environment
+    /// variable has an invalid value.
+    pub fn load(args: &Args) -> Result<Self> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        config.apply_args(args);
+
+        if config.http_workers == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The HTTP server needs at least one worker",
+            ));
+        }
+        // These modes rely on Pokémon being sent to the games, which never happens:
+        if config.deposit_only && (config.once || config.relay || config.relay_trade_evolution) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The deposit-only mode cannot be combined with the single-reception or relay modes",
+            ));
+        }
+        // The deposits are sent back to their games, which neither mode would let happen:
+        if config.accept_deposits
+            && (config.deposit_only || config.relay || config.relay_trade_evolution)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Accepting deposits cannot be combined with the deposit-only or relay modes",
+            ));
+        }
+
+        Ok(config)
+    }
+
+    /// Loads the configuration from a TOML file. Missing fields take their default values.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the configuration file.
+    fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!(
+                    "Could not read configuration file {}: {}",
+                    path.display(),
+                    e
+                ),
+            )
+        })?;
+
+        toml::from_str(&contents).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid configuration file {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Overrides the settings with the `GTS_RS_*` environment variables that are set.
+    fn apply_env(&mut self) -> Result<()> {
+        /// Overrides each given field with its environment variable, if set.
+        macro_rules! override_from_env {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = env_value(stringify!($field))? {
+                        self.$field = value;
+                    }
+                )*
+            };
+        }
+        /// Overrides each given optional field with its environment variable, if set.
+        macro_rules! override_optional_from_env {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = env_value(stringify!($field))? {
+                        self.$field = Some(value);
+                    }
+                )*
+            };
+        }
+
+        override_from_env!(
+            upstream_dns,
+            dns_cache_max_ttl,
+            http_port,
+            http_workers,
+            generations,
+            save_dir,
+            save_format,
+            source_order,
+            dispense,
+            dispense_replenish,
+            auto_legalize,
+            link_trade_origin,
+            deposit_only,
+            accept_deposits,
+            once,
+            relay,
+            relay_trade_evolution,
+            info_status,
+            random_session_id,
+            metrics_log_interval,
+            verbose_protocol,
+        );
+        override_optional_from_env!(
+            advertise_ip,
+            gen4_source,
+            gen5_source,
+            pokemon_file,
+            queue_file,
+            search_pool,
+            reception_seed,
+            gts_token,
+            record_session,
+            dns_log,
+            log,
+        );
+
+        Ok(())
+    }
+
+    /// Overrides the settings with the command-line options that were given.
+    ///
+    /// # Arguments
+    /// * `args` - The command-line options.
+    fn apply_args(&mut self, args: &Args) {
+        if args.advertise_ip.is_some() {
+            self.advertise_ip = args.advertise_ip;
+        }
+        if let Some(max_ttl) = args.dns_cache_max_ttl {
+            self.dns_cache_max_ttl = max_ttl;
+        }
+        if let Some(workers) = args.http_workers {
+            self.http_workers = workers;
+        }
+        if let Some(generations) = args.generations {
+            self.generations = generations;
+        }
+        if let Some(interval) = args.metrics_log_interval {
+            self.metrics_log_interval = interval;
+        }
+        if let Some(dir) = &args.save_dir {
+            self.save_dir.clone_from(dir);
+        }
+        if let Some(format) = args.save_format {
+            self.save_format = format;
+        }
+        if let Some(order) = args.source_order {
+            self.source_order = order;
+        }
+        if args.gts_token.is_some() {
+            self.gts_token.clone_from(&args.gts_token);
+        }
+        if let Some(status) = args.info_status {
+            self.info_status = status;
+        }
+        if args.reception_seed.is_some() {
+            self.reception_seed = args.reception_seed;
+        }
+        for (field, arg) in [
+            (&mut self.gen4_source, &args.gen4_source),
+            (&mut self.gen5_source, &args.gen5_source),
+            (&mut self.pokemon_file, &args.pokemon_file),
+            (&mut self.queue_file, &args.queue_file),
+            (&mut self.search_pool, &args.search_pool),
+            (&mut self.record_session, &args.record_session),
+            (&mut self.dns_log, &args.dns_log),
+        ] {
+            if arg.is_some() {
+                field.clone_from(arg);
+            }
+        }
+        if !args.queue.is_empty() {
+            self.queue.clone_from(&args.queue);
+        }
+        for (name, value) in &args.headers {
+            self.headers.insert(name.clone(), value.clone());
+        }
+        self.dispense |= args.dispense;
+        self.dispense_replenish |= args.dispense_replenish;
+        self.auto_legalize |= args.auto_legalize;
+        self.link_trade_origin |= args.link_trade_origin;
+        self.deposit_only |= args.deposit_only;
+        self.accept_deposits |= args.accept_deposits;
+        self.once |= args.once;
+        self.relay |= args.relay;
+        self.relay_trade_evolution |= args.relay_trade_evolution;
+        self.random_session_id |= args.random_session_id;
+        self.verbose_protocol |= args.verbose_protocol;
+    }
+}
+
+/// Reads and parses the environment variable overriding a setting.
+///
+/// # Arguments
+/// * `field` - The name of the setting, as in the configuration file.
+///
+/// Returns the parsed value, `None` if the variable is not set, or an error of kind
+/// `InvalidInput` if its value is invalid.
+fn env_value<T: FromStr>(field: &str) -> Result<Option<T>> {
+    let name = format!("{}{}", ENV_PREFIX, field.to_uppercase());
+    let Ok(value) = env::var(&name) else {
+        return Ok(None);
+    };
+
+    value.parse().map(Some).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid value for environment variable {}: {}", name, value),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Lock held by the tests loading the configuration, as they read the environment variables
+    /// that some of them set.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Writes a configuration file for a test, and gets command-line options that load it.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the test, to keep the files of concurrent tests apart.
+    /// * `contents` - The contents of the configuration file.
+    fn args_with_config(name: &str, contents: &str) -> Args {
+        let path = env::temp_dir().join(format!("gts-rs-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+
+        Args {
+            config: Some(path),
+            ..Args::default()
+        }
+    }
+
+    #[test]
+    fn empty_config_files_take_the_defaults() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let config = Config::load(&args_with_config("defaults", "")).unwrap();
+
+        assert_eq!(config.upstream_dns, Ipv4Addr::new(178, 62, 43, 212));
+        assert_eq!(config.dns_cache_max_ttl, 300);
+        assert_eq!(config.http_port, 80);
+        assert_eq!(config.http_workers, 1);
+        assert_eq!(config.save_dir, PathBuf::from("pokemon"));
+        assert_eq!(config.info_status, 1);
+        assert_eq!(config.metrics_log_interval, 3600);
+        assert!(config.gen4_source.is_none() && config.queue.is_empty());
+        assert!(!config.dispense && !config.deposit_only && !config.relay);
+    }
+
+    #[test]
+    fn settings_are_overridden_by_env_vars_then_by_args() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let mut args = args_with_config(
+            "precedence",
+            "http_port = 8080\ninfo_status = 2\nsave_dir = \"from-file\"\n",
+        );
+        args.info_status = Some(4);
+        // SAFETY: the tests reading the environment variables hold `ENV_LOCK`.
+        unsafe {
+            env::set_var("GTS_RS_HTTP_PORT", "8081");
+            env::set_var("GTS_RS_INFO_STATUS", "3");
+            env::set_var("GTS_RS_DISPENSE", "true");
+        }
+        let config = Config::load(&args);
+        unsafe {
+            env::remove_var("GTS_RS_HTTP_PORT");
+            env::remove_var("GTS_RS_INFO_STATUS");
+            env::remove_var("GTS_RS_DISPENSE");
+        }
+
+        let config = config.unwrap();
+        assert_eq!(config.http_port, 8081);
+        assert_eq!(config.info_status, 4);
+        assert_eq!(config.save_dir, PathBuf::from("from-file"));
+        assert!(config.dispense);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        for (name, contents) in [
+            ("unknown-field", "http_prot = 8080\n"),
+            ("wrong-type", "http_port = \"eighty\"\n"),
+            ("out-of-range", "http_port = 65536\n"),
+            ("no-workers", "http_workers = 0\n"),
+            ("deposit-only-relay", "deposit_only = true\nrelay = true\n"),
+            (
+                "accept-deposits-relay",
+                "accept_deposits = true\nrelay = true\n",
+            ),
+        ] {
+            let result = Config::load(&args_with_config(name, contents));
+            assert_eq!(
+                result.unwrap_err().kind(),
+                ErrorKind::InvalidInput,
+                "{}",
+                contents
+            );
+        }
+
+        // SAFETY: the tests reading the environment variables hold `ENV_LOCK`.
+        unsafe { env::set_var("GTS_RS_HTTP_WORKERS", "many") };
+        let result = Config::load(&args_with_config("invalid-env", ""));
+        unsafe { env::remove_var("GTS_RS_HTTP_WORKERS") };
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let args = Args {
+            config: Some(PathBuf::from("does/not/exist.toml")),
+            ..Args::default()
+        };
+        assert_eq!(Config::load(&args).unwrap_err().kind(), ErrorKind::NotFound);
+    }
+}
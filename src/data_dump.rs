@@ -0,0 +1,160 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Dumping of the data tables loaded by the library, for scripting purposes.
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use pkm_utils::data_maps::{ABILITIES, ITEMS_GEN4, ITEMS_GEN5, MOVES, NATURES, SPECIES};
+
+/// Enum that identifies the data tables that can be dumped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataTable {
+    /// Pokémon species, by National Pokédex number.
+    Species,
+    /// Moves.
+    Moves,
+    /// Items of the Gen 4 games.
+    ItemsGen4,
+    /// Items of the Gen 5 games.
+    ItemsGen5,
+    /// Abilities.
+    Abilities,
+    /// Natures.
+    Natures,
+}
+
+impl DataTable {
+    /// Names of the tables, as given in the command line.
+    pub const NAMES: [&str; 6] = [
+        "species",
+        "moves",
+        "items-gen4",
+        "items-gen5",
+        "abilities",
+        "natures",
+    ];
+
+    /// Gets the entries of the table, as (ID, name) pairs sorted by ID.
+    pub fn entries(&self) -> Vec<(u16, &'static str)> {
+        let mut entries: Vec<(u16, &str)> = match self {
+            DataTable::Species => SPECIES
+                .iter()
+                .map(|(id, name)| (*id, name.as_str()))
+                .collect(),
+            DataTable::Moves => MOVES
+                .iter()
+                .enumerate()
+                .map(|(id, name)| (id as u16, name.as_str()))
+                .collect(),
+            DataTable::ItemsGen4 => ITEMS_GEN4
+                .iter()
+                .map(|(id, name)| (*id, name.as_str()))
+                .collect(),
+            DataTable::ItemsGen5 => ITEMS_GEN5
+                .iter()
+                .map(|(id, name)| (*id, name.as_str()))
+                .collect(),
+            DataTable::Abilities => ABILITIES
+                .iter()
+                .map(|(id, name)| (*id, name.as_str()))
+                .collect(),
+            DataTable::Natures => NATURES
+                .iter()
+                .map(|(id, name)| (*id, name.as_str()))
+                .collect(),
+        };
+        entries.sort_unstable();
+
+        entries
+    }
+}
+
+impl FromStr for DataTable {
+    type Err = Error;
+
+    /// Parses the name of a table, as listed in `DataTable::NAMES`.
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "species" => Ok(DataTable::Species),
+            "moves" => Ok(DataTable::Moves),
+            "items-gen4" => Ok(DataTable::ItemsGen4),
+            "items-gen5" => Ok(DataTable::ItemsGen5),
+            "abilities" => Ok(DataTable::Abilities),
+            "natures" => Ok(DataTable::Natures),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unknown table: {} (expected one of: {})",
+                    name,
+                    DataTable::NAMES.join(", ")
+                ),
+            )),
+        }
+    }
+}
+
+/// Enum that identifies the formats a data table can be dumped in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// One entry per line: the ID, right-aligned, followed by the name.
+    #[default]
+    Columns,
+    /// A JSON object mapping each ID to its name.
+    Json,
+}
+
+impl FromStr for DumpFormat {
+    type Err = Error;
+
+    /// Parses the name of a format: `columns` or `json`.
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "columns" => Ok(DumpFormat::Columns),
+            "json" => Ok(DumpFormat::Json),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown format: {} (expected columns or json)", name),
+            )),
+        }
+    }
+}
+
+/// Formats a data table for printing.
+///
+/// # Arguments
+/// * `table` - The table to format.
+/// * `format` - The format to use.
+pub fn dump(table: DataTable, format: DumpFormat) -> String {
+    let entries = table.entries();
+
+    match format {
+        DumpFormat::Columns => entries
+            .iter()
+            .map(|(id, name)| format!("{:>4}  {}", id, name))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        DumpFormat::Json => {
+            let map: BTreeMap<u16, &str> = entries.into_iter().collect();
+            serde_json::to_string_pretty(&map).expect("A table of names is always valid JSON")
+        }
+    }
+}
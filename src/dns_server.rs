@@ -16,42 +16,93 @@
  * You should have received a copy of the GNU General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use futures::StreamExt;
+use crate::session::{self, SessionEntry};
+use futures::{stream::BoxStream, StreamExt};
 use hickory_client::{
     client::Client as DNSClient,
     proto::{
         op::message::Message,
-        rr::{rdata::A, record_data::RData, RecordType},
+        rr::{rdata::A, record_data::RData, Name, RecordType},
         runtime::TokioRuntimeProvider,
         udp::UdpClientStream,
         xfer::{DnsHandle, DnsResponse},
+        ProtoError,
     },
 };
 use std::{
+    collections::HashMap,
     fmt,
-    io::{Error, ErrorKind, Result},
+    io::{Error, Result},
     net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
     str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
 };
 
 /// Port to listen to DNS requests on.
-const LISTENING_PORT: u16 = 53;
+pub const LISTENING_PORT: u16 = 53;
 /// Wildcard IP address to listen to all IPv4 interfaces on this system.
 const ALL_V4_INTERFACES: Ipv4Addr = Ipv4Addr::new(0, 0, 0, 0);
 /// Wildcard port to make the OS assign an arbitrary port automatically.
 const ANY_PORT: u16 = 0;
+/// Log target of the DNS activity: queries received, answers, and rewrites of the GTS host.
+///
+/// It can be enabled on its own with `RUST_LOG=dns::rewrite=debug`, or written to its own file with
+/// the `dns_log` setting.
+pub const REWRITE_LOG_TARGET: &str = "dns::rewrite";
+/// Host name of Pokémon's GTS servers, whose DNS answers are rewritten.
+const GTS_HOST: &str = "gamestats2.gs.nintendowifi.net.";
+
+/// Upstream response cached by the `DNSServer`, to answer repeated queries locally.
+struct CachedResponse {
+    /// The response, as received from the upstream server (i.e., before being modified).
+    response: DnsResponse,
+    /// When the response was cached.
+    cached_at: Instant,
+    /// How long the response is valid for: its lowest TTL, capped by the maximum of the cache.
+    ttl: Duration,
+}
+
+/// Upstream DNS server that the `DNSServer` proxies the requests to.
+///
+/// Implemented by the real DNS client, and by mocks to test the DNS server without a network.
+pub trait DnsUpstream {
+    /// Sends a DNS request to the upstream server.
+    ///
+    /// # Arguments
+    /// * `message` - The DNS request to send.
+    ///
+    /// Returns a stream of the responses received from the upstream server.
+    fn query(
+        &self,
+        message: Message,
+    ) -> BoxStream<'static, std::result::Result<DnsResponse, ProtoError>>;
+}
+
+impl DnsUpstream for DNSClient {
+    fn query(
+        &self,
+        message: Message,
+    ) -> BoxStream<'static, std::result::Result<DnsResponse, ProtoError>> {
+        self.send(message).boxed()
+    }
+}
 
 /// A DNS server that proxies requests to a real DNS server, and modifes certain responses to
 /// impersonate Pokémon's GTS servers.
-pub struct DNSServer {
-    real_dns: DNSClient,
+pub struct DNSServer<U: DnsUpstream = DNSClient> {
+    real_dns: U,
     real_dns_ip: Ipv4Addr, // Stored only to display on print.
     proxy_ip: Ipv4Addr,
     listening_socket: UdpSocket,
+    /// Longest time to cache the upstream responses for, or zero to not cache them.
+    cache_max_ttl: Duration,
+    /// Upstream responses, by the name and type of their query.
+    cache: Mutex<HashMap<(Name, RecordType), CachedResponse>>,
 }
 
 /// Implements the Display trait for DNSServer to provide a string representation for printing.
-impl fmt::Display for DNSServer {
+impl<U: DnsUpstream> fmt::Display for DNSServer<U> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -67,7 +118,7 @@ impl fmt::Display for DNSServer {
 
 /// Implements the Debug trait for DNSServer to provide a debug string representation.
 #[cfg(debug_assertions)]
-impl fmt::Debug for DNSServer {
+impl<U: DnsUpstream> fmt::Debug for DNSServer<U> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -83,14 +134,17 @@ impl DNSServer {
     /// # Arguments
     /// * `ip_to_proxy` - \[Optional\] The IP address of the real DNS server to proxy requests to. If
     ///   `None`, it defaults to `178.62.43.212`.
-    pub async fn new(ip_to_proxy: Option<String>) -> Result<Self> {
+    /// * `advertise_ip` - \[Optional\] The IP address to redirect the GTS requests to. If `None`,
+    ///   it is auto-detected as the local IP used for external connections. Setting it is useful
+    ///   when the auto-detected IP is not reachable from the console (e.g., Docker or WSL setups).
+    pub async fn new(ip_to_proxy: Option<String>, advertise_ip: Option<Ipv4Addr>) -> Result<Self> {
         // Unpack the IP of the real DNS server to query:
         let ip_to_proxy = match ip_to_proxy {
             Some(ip) => ip,
             None => "178.62.43.212".to_string(), // Default DNS server.
         };
         let ip_to_proxy = Ipv4Addr::from_str(&ip_to_proxy)
-            .expect(format!("[DNS resolver] Invalid IP address: {}", ip_to_proxy).as_str());
+            .unwrap_or_else(|_| panic!("[DNS resolver] Invalid IP address: {}", ip_to_proxy));
         let addr_to_proxy = SocketAddr::new(ip_to_proxy.into(), 53);
 
         // Create a DNS client to query the real DNS server (in a background thread):
@@ -99,18 +153,23 @@ impl DNSServer {
         let (client, bg) = DNSClient::connect(udp_connection).await?;
         tokio::spawn(bg);
 
-        // Get the local IP address for external connections of this DNS proxy:
-        let proxy_ip = Self::get_proxy_ip(ip_to_proxy).await?;
+        // Get the IP address to advertise for the GTS servers; either the one provided, or the
+        // local IP address for external connections of this DNS proxy:
+        let proxy_ip = match advertise_ip {
+            Some(ip) => ip,
+            None => Self::get_proxy_ip(ip_to_proxy).await?,
+        };
 
         // Create and start the socket for the DNS connection with the client:
-        let listening_socket = UdpSocket::bind((ALL_V4_INTERFACES, LISTENING_PORT))?;
+        let listening_socket = UdpSocket::bind((ALL_V4_INTERFACES, LISTENING_PORT))
+            .map_err(|e| crate::port_bind_error(e, "DNS", LISTENING_PORT))?;
 
-        Ok(Self {
-            real_dns: client,
-            real_dns_ip: ip_to_proxy,
+        Ok(Self::with_upstream(
+            client,
+            ip_to_proxy,
             proxy_ip,
             listening_socket,
-        })
+        ))
     }
 
     /// Gets the local IP for external connections of this DNS server.
@@ -129,12 +188,50 @@ impl DNSServer {
         match local_addr.ip() {
             IpAddr::V4(ip) => Ok(ip),
             // An IPv6 should never occur, as an IPv4 address was requested above.
-            IpAddr::V6(_) => Err(Error::new(
-                ErrorKind::Other,
+            IpAddr::V6(_) => Err(Error::other(
                 "OS assigned an IPv6 address to the proxy, but only IPv4 is supported",
             )),
         }
     }
+}
+
+impl<U: DnsUpstream> DNSServer<U> {
+    /// Creates a new instance of the DNSServer, proxying requests to the given upstream server.
+    ///
+    /// # Arguments
+    /// * `upstream` - The upstream DNS server to proxy requests to.
+    /// * `upstream_ip` - The IP address of the upstream DNS server, only used for display.
+    /// * `proxy_ip` - The IP address to redirect the GTS requests to.
+    /// * `listening_socket` - The socket to listen to the clients' DNS requests on.
+    pub fn with_upstream(
+        upstream: U,
+        upstream_ip: Ipv4Addr,
+        proxy_ip: Ipv4Addr,
+        listening_socket: UdpSocket,
+    ) -> Self {
+        Self {
+            real_dns: upstream,
+            real_dns_ip: upstream_ip,
+            proxy_ip,
+            listening_socket,
+            cache_max_ttl: Duration::ZERO,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the longest time to cache the upstream responses for, so that repeated queries (as
+    /// the consoles make several when connecting) are answered without querying upstream again.
+    ///
+    /// Responses are cached for their lowest TTL, capped by `max_ttl`. Responses with no answers
+    /// are never cached. The GTS host is rewritten in the cached responses as well.
+    ///
+    /// # Arguments
+    /// * `max_ttl` - The longest time to cache a response for. Zero, the default, disables the
+    ///   cache.
+    pub fn with_cache_max_ttl(mut self, max_ttl: Duration) -> Self {
+        self.cache_max_ttl = max_ttl;
+        self
+    }
 
     /// Runs the DNS server, listening for requests.
     ///
@@ -151,47 +248,130 @@ impl DNSServer {
         loop {
             // Wait for a DNS request from a client:
             let (_, client_address) = self.listening_socket.recv_from(&mut listening_buf)?;
-            log::debug!("New DNS request received from {}", client_address);
-
-            // Parse the received buffer into a DNS message and log the query:
-            let Ok(dns_msg) = Message::from_vec(&listening_buf) else {
-                log::debug!("Received message is not a DNS message.");
-                continue;
-            };
-            let Some(dns_query) = dns_msg.query() else {
-                log::debug!("Received DNS request with no query.");
-                continue;
-            };
-            log::debug!("DNS Query: {}", dns_query);
-
-            // Get the DNS message ID for the client, required to send the respone:
-            let client_id = dns_msg.id();
-
-            // Send the DNS request to the real server:
-            'retry_dns_sending: loop {
-                let mut exchange = self.real_dns.send(dns_msg.clone());
-                // Handle all received responses:
-                while let Some(response) = exchange.next().await {
-                    // Retry sending the whole query if there was an error with the DNS query redirection:
-                    if response.is_err() {
-                        log::warn!(
-                            "Error when querying the real DNS server ({}). Retrying...",
-                            response
-                                .err()
-                                .expect("Error message missing for response matched as error")
-                        );
-                        continue 'retry_dns_sending;
-                    }
-
-                    // Modify respone to impersonate Pokémon's servers, and send back:
-                    let modified_response = self.modify_response(response?, client_id);
-                    self.listening_socket
-                        .send_to(&modified_response.to_vec()?, client_address)?;
+            log::debug!(target: REWRITE_LOG_TARGET, "New DNS request received from {}", client_address);
+
+            self.handle_request(&listening_buf, client_address).await?;
+        }
+    }
+
+    /// Handles a DNS request from a client: proxies it to the upstream server, and sends the
+    /// modified responses back to the client.
+    ///
+    /// Requests that are not valid DNS messages, or have no query, are ignored.
+    ///
+    /// # Arguments
+    /// * `request` - The raw DNS request received from the client.
+    /// * `client_address` - The address of the client, to send the responses to.
+    async fn handle_request(&self, request: &[u8], client_address: SocketAddr) -> Result<()> {
+        // Parse the received buffer into a DNS message and log the query:
+        let Ok(dns_msg) = Message::from_vec(request) else {
+            log::debug!(target: REWRITE_LOG_TARGET, "Received message is not a DNS message.");
+            return Ok(());
+        };
+        let Some(dns_query) = dns_msg.query() else {
+            log::debug!(target: REWRITE_LOG_TARGET, "Received DNS request with no query.");
+            return Ok(());
+        };
+        log::debug!(target: REWRITE_LOG_TARGET, "DNS Query: {}", dns_query);
+        session::record(SessionEntry::Dns {
+            query: dns_query.to_string(),
+        });
+
+        // Get the DNS message ID for the client, required to send the respone:
+        let client_id = dns_msg.id();
+
+        // Answer from the cache, if the same query was answered recently:
+        let cache_key = (dns_query.name().to_lowercase(), dns_query.query_type());
+        if let Some(cached) = self.cached_response(&cache_key) {
+            log::debug!(target: REWRITE_LOG_TARGET, "Answering from the cache");
+            let modified_response = self.modify_response(cached, client_id);
+            self.listening_socket
+                .send_to(&modified_response.to_vec()?, client_address)?;
+            return Ok(());
+        }
+
+        // Send the DNS request to the real server:
+        'retry_dns_sending: loop {
+            let mut exchange = self.real_dns.query(dns_msg.clone());
+            // Handle all received responses:
+            while let Some(response) = exchange.next().await {
+                // Retry sending the whole query if there was an error with the DNS query redirection:
+                if response.is_err() {
+                    log::warn!(
+                        "Error when querying the real DNS server ({}). Retrying...",
+                        response.expect_err("Error message missing for response matched as error")
+                    );
+                    continue 'retry_dns_sending;
                 }
 
-                break 'retry_dns_sending;
+                // Modify respone to impersonate Pokémon's servers, and send back:
+                let response = response?;
+                self.cache_response(cache_key.clone(), &response);
+                let modified_response = self.modify_response(response, client_id);
+                self.listening_socket
+                    .send_to(&modified_response.to_vec()?, client_address)?;
             }
+
+            break 'retry_dns_sending;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the cached upstream response to a query, if it has not expired.
+    ///
+    /// The TTLs of the answers are lowered by the time the response has been cached for, as a
+    /// caching DNS server would do. Expired responses are removed from the cache.
+    ///
+    /// # Arguments
+    /// * `key` - The name, in lowercase, and the type of the query.
+    fn cached_response(&self, key: &(Name, RecordType)) -> Option<DnsResponse> {
+        let mut cache = self
+            .cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let cached = cache.get(key)?;
+        let elapsed = cached.cached_at.elapsed();
+        if elapsed >= cached.ttl {
+            cache.remove(key);
+            return None;
+        }
+
+        let mut response = cached.response.clone();
+        let elapsed_secs = elapsed.as_secs().min(u32::MAX as u64) as u32;
+        for answer in response.answers_mut().iter_mut() {
+            let ttl = answer.ttl().saturating_sub(elapsed_secs);
+            answer.set_ttl(ttl);
         }
+
+        Some(response)
+    }
+
+    /// Caches an upstream response, if the cache is enabled and the response has answers.
+    ///
+    /// # Arguments
+    /// * `key` - The name, in lowercase, and the type of the query.
+    /// * `response` - The response received from the upstream server, before modifying it.
+    fn cache_response(&self, key: (Name, RecordType), response: &DnsResponse) {
+        let Some(lowest_ttl) = response.answers().iter().map(|answer| answer.ttl()).min() else {
+            return;
+        };
+        let ttl = Duration::from_secs(lowest_ttl.into()).min(self.cache_max_ttl);
+        if ttl.is_zero() {
+            return;
+        }
+
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                key,
+                CachedResponse {
+                    response: response.clone(),
+                    cached_at: Instant::now(),
+                    ttl,
+                },
+            );
     }
 
     /// Modifies the DNS responses from the real DNS server, to send back to the client.
@@ -216,12 +396,27 @@ impl DNSServer {
         // Modify the response to change Nintendo's servers' IP to our IP:
         for answer in response.answers_mut().iter_mut() {
             if answer.record_type() == RecordType::A {
-                log::debug!("DNS returns IP {} for {}", answer.data(), answer.name());
+                log::debug!(
+                    target: REWRITE_LOG_TARGET,
+                    "DNS returns IP {} for {}",
+                    answer.data(),
+                    answer.name()
+                );
 
                 // Check if the A record matches Pokémon's GTS servers, and modify it:
-                if answer.name().to_string() == "gamestats2.gs.nintendowifi.net.".to_string() {
+                if answer.name().to_string() == GTS_HOST {
                     answer.set_data(RData::A(A(self.proxy_ip)));
-                    log::debug!("Modified answer: {}", answer);
+                    log::debug!(
+                        target: REWRITE_LOG_TARGET,
+                        "Matched GTS host; substituted IP {}: {}",
+                        self.proxy_ip,
+                        answer
+                    );
+                } else {
+                    log::debug!(
+                        target: REWRITE_LOG_TARGET,
+                        "Not the GTS host; answer passed through"
+                    );
                 }
             }
         }
@@ -234,3 +429,202 @@ impl DNSServer {
         self.proxy_ip
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use hickory_client::proto::{
+        op::{MessageType, Query},
+        rr::{Name, Record},
+    };
+
+    /// IP address the GTS host resolves to upstream.
+    const UPSTREAM_GTS_IP: Ipv4Addr = Ipv4Addr::new(1, 2, 3, 4);
+    /// IP address the GTS requests are redirected to.
+    const PROXY_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 10);
+
+    /// Upstream DNS server that answers every query with a fixed A record for the queried name.
+    struct MockUpstream {
+        ip: Ipv4Addr,
+        /// Number of queries received.
+        queries: std::sync::atomic::AtomicUsize,
+    }
+
+    impl DnsUpstream for MockUpstream {
+        fn query(
+            &self,
+            message: Message,
+        ) -> BoxStream<'static, std::result::Result<DnsResponse, ProtoError>> {
+            self.queries
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let query = message.query().expect("Request has no query").clone();
+            let mut response = Message::new();
+            response
+                .set_id(message.id().wrapping_add(1)) // Differs from the request's, as upstream.
+                .set_message_type(MessageType::Response)
+                .add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    300,
+                    RData::A(A(self.ip)),
+                ))
+                .add_query(query);
+
+            stream::once(async move { DnsResponse::from_message(response) }).boxed()
+        }
+    }
+
+    /// Creates a DNS server with a mock upstream, listening on an arbitrary local port.
+    fn mock_server() -> DNSServer<MockUpstream> {
+        let listening_socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, ANY_PORT))
+            .expect("Failed to bind the listening socket");
+        DNSServer::with_upstream(
+            MockUpstream {
+                ip: UPSTREAM_GTS_IP,
+                queries: Default::default(),
+            },
+            Ipv4Addr::LOCALHOST,
+            PROXY_IP,
+            listening_socket,
+        )
+    }
+
+    /// Builds a DNS request for the A record of the given host.
+    fn request(host: &str, id: u16) -> Message {
+        let mut request = Message::new();
+        request
+            .set_id(id)
+            .add_query(Query::query(Name::from_str(host).unwrap(), RecordType::A));
+        request
+    }
+
+    /// Returns the IPs of the A records answered in the given response.
+    fn answered_ips(response: &Message) -> Vec<Ipv4Addr> {
+        response
+            .answers()
+            .iter()
+            .filter_map(|answer| match answer.data() {
+                RData::A(A(ip)) => Some(*ip),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Sends a request through the server's request handling, and returns the client's response.
+    async fn exchange(server: &DNSServer<MockUpstream>, request: Message) -> Message {
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, ANY_PORT)).unwrap();
+        server
+            .handle_request(&request.to_vec().unwrap(), client.local_addr().unwrap())
+            .await
+            .expect("Failed to handle the request");
+
+        let mut buf = [0u8; 512];
+        let (len, from) = client.recv_from(&mut buf).unwrap();
+        assert_eq!(from, server.listening_socket.local_addr().unwrap());
+        Message::from_vec(&buf[..len]).expect("Response is not a DNS message")
+    }
+
+    #[tokio::test]
+    async fn gts_a_record_is_rewritten_to_proxy_ip() {
+        let server = mock_server();
+        let response = exchange(&server, request(GTS_HOST, 0x1234)).await;
+
+        assert_eq!(response.id(), 0x1234);
+        assert_eq!(answered_ips(&response), vec![PROXY_IP]);
+    }
+
+    #[tokio::test]
+    async fn other_records_pass_through_untouched() {
+        let server = mock_server();
+        let response = exchange(&server, request("example.com.", 0x4321)).await;
+
+        assert_eq!(response.id(), 0x4321);
+        assert_eq!(answered_ips(&response), vec![UPSTREAM_GTS_IP]);
+        assert_eq!(
+            response.answers()[0].name(),
+            &Name::from_str("example.com.").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_responses_are_answered_locally() {
+        let server = mock_server().with_cache_max_ttl(Duration::from_secs(60));
+        let queries = || {
+            server
+                .real_dns
+                .queries
+                .load(std::sync::atomic::Ordering::Relaxed)
+        };
+
+        let response = exchange(&server, request(GTS_HOST, 1)).await;
+        assert_eq!(answered_ips(&response), vec![PROXY_IP]);
+        assert_eq!(queries(), 1);
+
+        // The GTS host is still rewritten, and the ID is the client's:
+        let response = exchange(&server, request(GTS_HOST, 2)).await;
+        assert_eq!(response.id(), 2);
+        assert_eq!(answered_ips(&response), vec![PROXY_IP]);
+        assert!(response.answers()[0].ttl() <= 300);
+        assert_eq!(queries(), 1);
+
+        exchange(&server, request("example.com.", 3)).await;
+        assert_eq!(queries(), 2);
+    }
+
+    #[tokio::test]
+    async fn cache_is_disabled_by_default() {
+        let server = mock_server();
+        exchange(&server, request(GTS_HOST, 1)).await;
+        exchange(&server, request(GTS_HOST, 2)).await;
+
+        assert_eq!(
+            server
+                .real_dns
+                .queries
+                .load(std::sync::atomic::Ordering::Relaxed),
+            2
+        );
+    }
+
+    #[test]
+    fn modify_response_only_rewrites_gts_host() {
+        let server = mock_server();
+        let mut message = Message::new();
+        message
+            .set_message_type(MessageType::Response)
+            .add_answer(Record::from_rdata(
+                Name::from_str(GTS_HOST).unwrap(),
+                300,
+                RData::A(A(UPSTREAM_GTS_IP)),
+            ))
+            .add_answer(Record::from_rdata(
+                Name::from_str("conntest.nintendowifi.net.").unwrap(),
+                300,
+                RData::A(A(UPSTREAM_GTS_IP)),
+            ));
+        let response = DnsResponse::from_message(message).unwrap();
+
+        let modified = server.modify_response(response, 7);
+
+        assert_eq!(modified.id(), 7);
+        assert_eq!(answered_ips(&modified), vec![PROXY_IP, UPSTREAM_GTS_IP]);
+    }
+
+    #[tokio::test]
+    async fn invalid_requests_are_ignored() {
+        let server = mock_server();
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, ANY_PORT)).unwrap();
+
+        server
+            .handle_request(b"not a DNS message", client.local_addr().unwrap())
+            .await
+            .expect("Invalid requests should not be an error");
+        server
+            .handle_request(
+                &Message::new().to_vec().unwrap(),
+                client.local_addr().unwrap(),
+            )
+            .await
+            .expect("Requests with no query should not be an error");
+    }
+}
@@ -18,48 +18,262 @@
  */
 use actix_web::{
     body::{BoxBody, MessageBody},
-    dev::{Server, ServiceRequest, ServiceResponse},
+    dev::{Server, ServerHandle, ServiceFactory, ServiceRequest, ServiceResponse},
     error::Error as ActixError,
     get,
     http::StatusCode,
     middleware::{from_fn, Logger, Next},
-    web::{scope, Query},
-    App, HttpResponse, HttpResponseBuilder, HttpServer, Result as ActixResult,
+    rt::{self, time::timeout},
+    web::{self, scope, Query},
+    App, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer, Result as ActixResult,
 };
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 use paste::paste;
-use serde::Deserialize;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::{
-    collections::HashMap,
-    fs,
-    io::{stdin, Result},
+    collections::{BTreeSet, HashMap, VecDeque},
+    fmt, fs,
+    io::{stdin, Error, ErrorKind, Result},
     net::Ipv4Addr,
-    path::Path,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering},
+        LazyLock, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
+use tokio::sync::Notify;
 
+use crate::{
+    pokemon_source::{unquote_path, PokemonSource},
+    session::{self, SessionEntry},
+};
 use pkm_utils::{
-    gts::{GTSDeposit, GTSReception},
-    pokemon::Pokemon,
+    gts::{GTSDeposit, GTSReception, GTSSearch, GTSSearchResult},
+    pokemon::{Pokemon, SaveFormat},
 };
 
-/// Token used for some specific GTS response:
-const GTS_TOKEN: &str = "c9KcX1Cry3QKS2Ai7yxL6QiQGeBGeQKR";
+/// Token sent to the games in the token exchange, unless another one is configured.
+///
+/// Every GTS request is made twice: first with only the `pid` query parameter, to which the
+/// server answers with a 32-character token; then again with the `hash` (the SHA-1 of a salt and
+/// the token) and `data` parameters. The games do not check the token, nor does this server check
+/// the hash, so any 32-character token works.
+const DEFAULT_GTS_TOKEN: &str = "c9KcX1Cry3QKS2Ai7yxL6QiQGeBGeQKR";
+/// Length of the tokens of the token exchange.
+const GTS_TOKEN_LEN: usize = 32;
+/// Query parameter sent alone by the games to request a token.
+const TOKEN_QUERY_KEY: &str = "pid";
+/// Status code answered to `info.asp`, unless another one is configured: `0x0001` means the GTS
+/// is available.
+const DEFAULT_INFO_STATUS: u16 = 0x0001;
 /// Salt used for generating the footer in Gen 5 responses.
 const GEN5_SALT: &[u8; 20] = b"HZEdGCzcGGLvguqUEKQN";
+/// Length of the footer of Gen 5 responses: a SHA-1 hash, in hexadecimal.
+const GEN5_FOOTER_LEN: usize = 40;
+/// Log target of the bytes of the deposits' GTS data whose meaning is unknown.
+///
+/// It can be enabled on its own with `RUST_LOG=gts::unknown=debug`.
+const UNKNOWN_DATA_LOG_TARGET: &str = "gts::unknown";
 
-/// Generates a proper response for the Gen 4 Pokémon games' GTS service, given the body of the
-/// HTTP response.
+/// Token sent to the games in the token exchange, if different from the default one.
+static GTS_TOKEN: OnceLock<String> = OnceLock::new();
+/// Status code answered to `info.asp`.
+static INFO_STATUS: AtomicU16 = AtomicU16::new(DEFAULT_INFO_STATUS);
+
+/// Sets the token sent to the games in the token exchange, instead of the default one.
 ///
-/// This function mainly sets the appropriate headers and builds a response with it and the
-/// provided body.
+/// # Arguments
+/// * `token` - The token, of 32 ASCII letters and digits.
+///
+/// Returns an error of kind `InvalidInput` if the token is not 32 ASCII letters and digits, or of
+/// kind `AlreadyExists` if the token was already set.
+pub fn set_gts_token(token: String) -> Result<()> {
+    if token.len() != GTS_TOKEN_LEN || !token.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid GTS token {:?}: it must be {} ASCII letters and digits",
+                token, GTS_TOKEN_LEN
+            ),
+        ));
+    }
+
+    GTS_TOKEN
+        .set(token)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "GTS token is already set"))
+}
+
+/// Sets the status code answered to `info.asp`, the first request of the games to the GTS.
 ///
 /// # Arguments
-/// * `body` - The body of the HTTP response, containing the proper data to send to the client.
-fn gts_response_gen4(body: impl MessageBody + 'static) -> HttpResponse<BoxBody> {
-    // Response headers for the GTS service:
-    // (These seem to be optional.)
-    let headers = vec![
+/// * `code` - The status code, sent as a 16-bit little-endian integer. `0x0001` (the default)
+///   means the GTS is available.
+pub fn set_info_status(code: u16) {
+    INFO_STATUS.store(code, Ordering::Relaxed);
+}
+
+/// Whether to hex-dump the payloads of all GTS requests and responses.
+static VERBOSE_PROTOCOL: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the hex dumps of the payloads of all GTS requests and responses.
+///
+/// This is intended for protocol research, and disabled by default.
+pub fn set_verbose_protocol(enabled: bool) {
+    VERBOSE_PROTOCOL.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether to apply safe legality fixes to the deposited Pokémon, before saving them.
+static AUTO_LEGALIZE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the legality fixes of the deposited Pokémon, before saving them.
+///
+/// Every change made is logged. See `Pokemon::auto_legalize` for the fixes applied. Disabled by
+/// default, so that the Pokémon are saved exactly as received.
+pub fn set_auto_legalize(enabled: bool) {
+    AUTO_LEGALIZE.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the endpoints must not have side effects. See `set_dry_run`.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the dry-run mode, in which the endpoints answer the requests without
+/// side effects, e.g., to replay a recorded session.
+///
+/// Deposited Pokémon are parsed and logged, but not saved, kept track of, nor relayed; and no
+/// Pokémon is taken from the queue, the sources or the prompt, so the games are answered as if
+/// there were no Pokémon to send. The usage metrics are not updated either. Disabled by default.
+///
+/// # Arguments
+/// * `enabled` - Whether to run the endpoints without side effects.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether to send the deposited Pokémon back to the next game receiving one.
+static RELAY: AtomicBool = AtomicBool::new(false);
+/// Whether to evolve the relayed Pokémon that evolve by trade.
+static RELAY_TRADE_EVOLUTION: AtomicBool = AtomicBool::new(false);
+/// Deposited Pokémon waiting to be relayed, in the order they were deposited.
+static RELAYED: Mutex<VecDeque<Pokemon>> = Mutex::new(VecDeque::new());
+
+/// Enables or disables the relay mode, in which each deposited Pokémon is sent back to the next
+/// game receiving a Pokémon (the same one, or another console), instead of a Pokémon from the
+/// configured source or the prompt.
+///
+/// Relayed Pokémon are converted to the generation of the receiving game, if needed. Disabled by
+/// default.
+///
+/// # Arguments
+/// * `enabled` - Whether to relay the deposited Pokémon.
+/// * `trade_evolution` - Whether to evolve the relayed Pokémon that evolve by trade, as a real
+///   trade would. See `Pokemon::evolve_by_trade`.
+pub fn set_relay(enabled: bool, trade_evolution: bool) {
+    RELAY.store(enabled, Ordering::Relaxed);
+    RELAY_TRADE_EVOLUTION.store(trade_evolution, Ordering::Relaxed);
+}
+
+/// Whether to never send Pokémon to the games, only collecting their deposits.
+static DEPOSIT_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the deposit-only mode, in which no Pokémon is ever sent to the games: they
+/// are told right away that there is no Pokémon for them, and proceed to deposit one, without
+/// prompting for a Pokémon nor taking one from the configured sources or the relay.
+///
+/// Disabled by default.
+pub fn set_deposit_only(enabled: bool) {
+    DEPOSIT_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether to accept the deposits, keeping the deposited Pokémon until they are returned.
+static ACCEPT_DEPOSITS: AtomicBool = AtomicBool::new(false);
+/// Pokémon deposited by each player, while deposits are accepted, keyed by their profile ID.
+///
+/// Deposits are kept in memory only, until the game deletes them after they are returned.
+static DEPOSITS: LazyLock<Mutex<HashMap<u32, Pokemon>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Enables or disables accepting the deposits, as the real GTS did: the games remove the
+/// deposited Pokémon from their save files, and the server keeps them, by profile ID.
+///
+/// The next time the game of a profile with a deposit checks the GTS, its Pokémon is sent back to
+/// it, and forgotten once the game confirms the reception (its following `delete.asp` request).
+/// The deposited Pokémon are still saved to disk. Disabled by default, in which case deposits are
+/// rejected, and the games keep their Pokémon. See `POST_REJECTED`.
+pub fn set_accept_deposits(enabled: bool) {
+    ACCEPT_DEPOSITS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether to shut down the HTTP server after a Pokémon is sent to a game.
+static EXIT_AFTER_RECEPTION: AtomicBool = AtomicBool::new(false);
+/// Whether a Pokémon was sent to a game, and its reception is not confirmed yet.
+static RECEPTION_PENDING: AtomicBool = AtomicBool::new(false);
+/// Notified when the game confirms the reception of the Pokémon sent to it.
+static RECEPTION_CONFIRMED: Notify = Notify::const_new();
+/// Handle of the running HTTP server, for shutting it down.
+static SERVER_HANDLE: OnceLock<ServerHandle> = OnceLock::new();
+/// Time to wait for the game to confirm the reception of a Pokémon, before shutting down anyway.
+const RECEPTION_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Enables or disables shutting down the HTTP server after a Pokémon is sent to a game, so that
+/// each run of the application delivers a single Pokémon.
+///
+/// The server is shut down gracefully once the game confirms the reception (its following
+/// `delete.asp` request), or after a timeout. Disabled by default.
+pub fn set_exit_after_reception(enabled: bool) {
+    EXIT_AFTER_RECEPTION.store(enabled, Ordering::Relaxed);
+}
+
+/// Shuts down the HTTP server once the game confirms the reception of the Pokémon sent to it, if
+/// requested. See `set_exit_after_reception`.
+///
+/// Does nothing if no HTTP server is running (e.g., when replaying a session).
+fn shut_down_after_reception() {
+    if !EXIT_AFTER_RECEPTION.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(handle) = SERVER_HANDLE.get().cloned() else {
+        return;
+    };
+
+    log::info!("Pokémon sent; shutting down once the game confirms its reception.");
+    RECEPTION_PENDING.store(true, Ordering::Relaxed);
+    rt::spawn(async move {
+        let confirmed = RECEPTION_CONFIRMED.notified();
+        if timeout(RECEPTION_CONFIRMATION_TIMEOUT, confirmed)
+            .await
+            .is_err()
+        {
+            log::warn!("The game did not confirm the reception; shutting down anyway.");
+        }
+        handle.stop(true).await;
+    });
+}
+
+/// Source of the Pokémon to send to Gen 4 games, if not prompting for them.
+static GEN4_SOURCE: OnceLock<PokemonSource> = OnceLock::new();
+/// Source of the Pokémon to send to Gen 5 games, if not prompting for them.
+static GEN5_SOURCE: OnceLock<PokemonSource> = OnceLock::new();
+/// Pokémon files queued to be sent to the next games receiving a Pokémon, of either generation,
+/// before the sources.
+static QUEUE: Mutex<VecDeque<PathBuf>> = Mutex::new(VecDeque::new());
+
+/// Headers of the GTS responses, if different from the default ones.
+static RESPONSE_HEADERS: OnceLock<Vec<(String, String)>> = OnceLock::new();
+/// Whether to randomize the session ID set by the `Set-Cookie` header of each response.
+static RANDOMIZE_SESSION_ID: AtomicBool = AtomicBool::new(false);
+/// Random `Set-Cookie` header values given to each profile, keyed by the profile ID (`None` for
+/// requests without one), so that each profile keeps the same session ID.
+static SESSION_COOKIES: LazyLock<Mutex<HashMap<Option<u32>, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Gets the default headers of the GTS responses, which mimic those of the real GTS server.
+pub fn default_response_headers() -> Vec<(String, String)> {
+    [
         ("Server", "Microsoft-IIS/6.0"),
         ("P3P", "CP='NOI ADMa OUR STP'"),
         ("cluster-server", "aphexweb3"),
@@ -71,14 +285,404 @@ fn gts_response_gen4(body: impl MessageBody + 'static) -> HttpResponse<BoxBody>
             "ASPSESSIONIDQCDBDDQS=JFDOAMPAGACBDMLNLFBCCNCI; path=/",
         ),
         ("Cache-control", "private"),
-    ];
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+/// Overrides the default headers of the GTS responses.
+///
+/// Each given header replaces the default header with the same name (case-insensitively), or is
+/// added if there is none. Headers with an empty value remove the default header instead.
+///
+/// # Arguments
+/// * `overrides` - The headers to override, as (name, value) pairs.
+///
+/// Returns an error of kind `AlreadyExists` if the headers were already overridden.
+pub fn set_response_headers(overrides: Vec<(String, String)>) -> Result<()> {
+    let mut headers = default_response_headers();
+    for (name, value) in overrides {
+        headers.retain(|(default_name, _)| !default_name.eq_ignore_ascii_case(&name));
+        if !value.is_empty() {
+            headers.push((name, value));
+        }
+    }
+
+    RESPONSE_HEADERS
+        .set(headers)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Response headers are already set"))
+}
+
+/// Enables or disables the randomization of the session ID set by the `Set-Cookie` header.
+///
+/// The real GTS server gives each client its own ASP session ID, while this server always sets the
+/// same one by default. When randomized, each profile is given its own session ID on its first
+/// request, and keeps it for the following ones.
+pub fn set_randomize_session_id(enabled: bool) {
+    RANDOMIZE_SESSION_ID.store(enabled, Ordering::Relaxed);
+}
+
+/// Generates a `Set-Cookie` header value with a random ASP session ID.
+///
+/// ASP session IDs are made of 24 random uppercase letters.
+fn random_session_cookie() -> String {
+    let mut rng = SmallRng::from_rng(&mut rand::rng());
+    let session_id = (0..24)
+        .map(|_| rng.random_range(b'A'..=b'Z') as char)
+        .collect::<String>();
+
+    format!("ASPSESSIONIDQCDBDDQS={}; path=/", session_id)
+}
+
+/// Gets the `Set-Cookie` header value with the random ASP session ID of a profile, generating it
+/// on the profile's first request.
+///
+/// # Arguments
+/// * `profile_id` - The profile ID of the request (the `pid` query parameter), if any.
+fn session_cookie(profile_id: Option<u32>) -> String {
+    SESSION_COOKIES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(profile_id)
+        .or_insert_with(random_session_cookie)
+        .clone()
+}
+
+/// Gets the profile ID of a GTS request, i.e., its `pid` query parameter.
+///
+/// # Arguments
+/// * `query_string` - The query string of the request's URL.
+fn profile_id(query_string: &str) -> Option<u32> {
+    Query::<HashMap<String, String>>::from_query(query_string)
+        .ok()?
+        .get(TOKEN_QUERY_KEY)?
+        .parse()
+        .ok()
+}
+
+/// Pool of Pokémon listed in the GTS search results, if any.
+static SEARCH_POOL: OnceLock<PokemonSource> = OnceLock::new();
+
+/// Directory the deposited Pokémon are saved to, if not the default one.
+static SAVE_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Format the deposited Pokémon are saved in, if not the default one.
+static SAVE_FORMAT: OnceLock<SaveFormat> = OnceLock::new();
+
+/// Seed of the random GTS data fields of the receptions, if they should be repeatable.
+static RECEPTION_SEED: OnceLock<u64> = OnceLock::new();
+/// Whether to make the Pokémon sent appear as received through a link trade.
+static LINK_TRADE_ORIGIN: AtomicBool = AtomicBool::new(false);
+
+/// Time the HTTP server was started at, for reporting its uptime.
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+/// Usage counters of the server, for the status page and the periodic summaries.
+static METRICS: Metrics = Metrics::new();
+/// Last Pokémon deposited by a game, for the status page.
+static LAST_DEPOSIT: Mutex<Option<DepositedPokemon>> = Mutex::new(None);
+/// Last Pokémon sent to a game, for the status page.
+static LAST_SERVED: Mutex<Option<ServedPokemon>> = Mutex::new(None);
+/// IP address the DNS server advertises for the GTS host, for the status page.
+static ADVERTISED_IP: OnceLock<Ipv4Addr> = OnceLock::new();
+
+/// Sets the source of the Pokémon to send to the games of a generation, instead of prompting for
+/// them on the standard input.
+///
+/// Each generation has its own source, so that a Gen 4 and a Gen 5 console can be served at the
+/// same time.
+///
+/// # Arguments
+/// * `is_gen5` - Whether the source is for Gen 5 games, instead of Gen 4 games.
+/// * `source` - The source of the Pokémon to send.
+///
+/// Returns an error of kind `AlreadyExists` if a source was already set for the generation.
+pub fn set_pokemon_source(is_gen5: bool, source: PokemonSource) -> Result<()> {
+    let generation_source = if !is_gen5 { &GEN4_SOURCE } else { &GEN5_SOURCE };
+
+    generation_source.set(source).map_err(|_| {
+        Error::new(
+            ErrorKind::AlreadyExists,
+            "Pokémon source is already set for this generation",
+        )
+    })
+}
+
+/// Queues Pokémon files to be sent, in order, to the next games receiving a Pokémon, so that
+/// several trades can be made in a row without prompting for each Pokémon.
+///
+/// The queue is shared by both generations; its Pokémon are converted to the generation of the
+/// game receiving them. Once it is empty, the Pokémon are taken from the source of the generation,
+/// or prompted for, as usual. Relayed Pokémon take precedence over the queued ones.
+///
+/// # Arguments
+/// * `files` - The Pokémon files to queue.
+pub fn queue_pokemon_files(files: Vec<PathBuf>) {
+    QUEUE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .extend(files);
+}
+
+/// Sets the pool of Pokémon listed in the GTS search results.
+///
+/// Without a pool, searches never find any Pokémon. The pool is shared by both generations; its
+/// Pokémon are converted to the generation of the game searching.
+///
+/// # Arguments
+/// * `pool` - The Pokémon file, or directory of files, to list in the search results.
+///
+/// Returns an error of kind `AlreadyExists` if the search pool was already set.
+pub fn set_search_pool(pool: PokemonSource) -> Result<()> {
+    SEARCH_POOL
+        .set(pool)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Search pool is already set"))
+}
+
+/// Sets the seed of the GTS data fields of the receptions that cannot be derived from the
+/// Pokémon (the profile ID and the requested species), so that they are the same on every run.
+///
+/// # Arguments
+/// * `seed` - The seed of the random number generator.
+///
+/// Returns an error of kind `AlreadyExists` if the reception seed was already set.
+pub fn set_reception_seed(seed: u64) -> Result<()> {
+    RECEPTION_SEED
+        .set(seed)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Reception seed is already set"))
+}
+
+/// Enables or disables rewriting the met location and date of the Pokémon sent to the games, so
+/// that they appear as received through a link trade. See `GTSReception::with_link_trade_origin`.
+///
+/// The deposits returned to their games are left as they were. Disabled by default, so that the
+/// Pokémon are sent exactly as loaded.
+pub fn set_link_trade_origin(enabled: bool) {
+    LINK_TRADE_ORIGIN.store(enabled, Ordering::Relaxed);
+}
+
+/// Sets the directory the deposited Pokémon are saved to, instead of the default one.
+///
+/// # Arguments
+/// * `dir` - The path of the directory. It is created when the first Pokémon is saved.
+///
+/// Returns an error of kind `AlreadyExists` if the save directory was already set.
+pub fn set_save_dir(dir: PathBuf) -> Result<()> {
+    SAVE_DIR
+        .set(dir)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Save directory is already set"))
+}
+
+/// Sets the format the deposited Pokémon are saved in, instead of the default one (decrypted
+/// `.pk4` and `.pk5` files).
+///
+/// # Arguments
+/// * `format` - The format of the saved files.
+///
+/// Returns an error of kind `AlreadyExists` if the save format was already set.
+pub fn set_save_format(format: SaveFormat) -> Result<()> {
+    SAVE_FORMAT
+        .set(format)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Save format is already set"))
+}
+
+/// Sets the IP address the DNS server advertises for the GTS host, to be reported by the status
+/// page.
+///
+/// # Arguments
+/// * `ip` - The IP address the GTS host is resolved to.
+///
+/// Returns an error of kind `AlreadyExists` if the advertised IP was already set.
+pub fn set_advertised_ip(ip: Ipv4Addr) -> Result<()> {
+    ADVERTISED_IP
+        .set(ip)
+        .map_err(|_| Error::new(ErrorKind::AlreadyExists, "Advertised IP is already set"))
+}
+
+/// Loads a Pokémon from a file, converting it to the generation it is going to be sent to.
+///
+/// Errors are logged instead of returned.
+///
+/// # Arguments
+/// * `path` - The path of the Pokémon file.
+/// * `is_gen5` - Whether the Pokémon is going to be sent to a Gen 5 game, instead of a Gen 4 one.
+///
+/// Returns the Pokémon, or `None` if it could not be loaded or sent to the generation.
+fn load_pokemon_for_generation(path: &Path, is_gen5: bool) -> Option<Pokemon> {
+    let generation = if !is_gen5 { 4 } else { 5 };
+    let pokemon = match Pokemon::load(path) {
+        Ok(pokemon) => pokemon,
+        Err(e) => {
+            log::error!(
+                "Failed to load Gen {} Pokémon from {}: {}",
+                generation,
+                path.display(),
+                e
+            );
+            return None;
+        }
+    };
+    log::info!("Pokémon loaded from {} successfully.", path.display());
+
+    pokemon_for_generation(pokemon, is_gen5)
+}
+
+/// Converts a Pokémon to the generation it is going to be sent to, and checks that the game would
+/// accept it.
+///
+/// Errors are logged instead of returned.
+///
+/// # Arguments
+/// * `pokemon` - The Pokémon to send.
+/// * `is_gen5` - Whether the Pokémon is going to be sent to a Gen 5 game, instead of a Gen 4 one.
+///
+/// Returns the Pokémon, or `None` if it cannot be sent to the generation.
+fn pokemon_for_generation(mut pokemon: Pokemon, is_gen5: bool) -> Option<Pokemon> {
+    if !pokemon.is_gen5() && is_gen5 {
+        if let Err(e) = pokemon.try_convert_to_gen5() {
+            log::error!("Cannot send that Gen 4 Pokémon to a Gen 5 game: {}", e);
+            return None;
+        }
+        log::warn!("Sending a Gen 4 Pokémon to a Gen 5 game.");
+    } else if pokemon.is_gen5() && !is_gen5 {
+        if pokemon.try_convert_to_gen4().is_ok() {
+            log::warn!("Sending a Gen 5 Pokémon to a Gen 4 game.");
+        } else {
+            log::error!("Cannot send that Gen 5 Pokémon to a Gen 4 game.");
+            return None;
+        }
+    }
+
+    if let Err(issues) = pokemon.is_valid_trade_target() {
+        log::error!("The game would not accept that Pokémon:");
+        for issue in issues {
+            log::error!("  - {}", issue);
+        }
+        return None;
+    }
+
+    Some(pokemon)
+}
+
+/// Lock held while prompting for a Pokémon on the standard input.
+static PROMPT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Prompts for the Pokémon to send to a game on the standard input.
+///
+/// The prompt is repeated until a valid Pokémon is specified, or no Pokémon is sent.
+///
+/// # Arguments
+/// * `is_gen5` - Whether the Pokémon is going to be sent to a Gen 5 game, instead of a Gen 4 one.
+///
+/// Returns the Pokémon, or `None` if the user chose not to send one.
+fn prompt_pokemon_for_generation(is_gen5: bool) -> Option<Pokemon> {
+    let generation = if !is_gen5 { 4 } else { 5 };
+
+    // Prompt for one Pokémon at a time, even if several consoles are waiting for one:
+    let _prompt = PROMPT_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    loop {
+        let mut path = String::new();
+
+        println!(
+            "Enter the path or drag the .pkm/.pk{} file here.",
+            generation
+        );
+        println!("Leave blank to not send a Pokémon and proceed through the GTS (for deposits).");
+
+        // Read and sanitize the path, or skip:
+        if stdin().read_line(&mut path).is_err() {
+            log::error!("Error reading from stdin.");
+            continue;
+        } else if path.trim().is_empty() {
+            return None;
+        }
+
+        let path = unquote_path(path.trim());
+
+        // Load the Pokémon struct and return it:
+        if let Some(pokemon) = load_pokemon_for_generation(Path::new(path), is_gen5) {
+            return Some(pokemon);
+        }
+    }
+}
+
+/// Logs a hex dump of a GTS payload, if verbose protocol logging is enabled.
+///
+/// Each line of the dump shows the offset of its first byte, 16 bytes in hexadecimal, and their
+/// ASCII representation.
+///
+/// # Arguments
+/// * `description` - A description of the payload, printed before the dump.
+/// * `bytes` - The payload to dump.
+fn log_hex_dump(description: &str, bytes: &[u8]) {
+    if !VERBOSE_PROTOCOL.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut dump = format!("{} ({} bytes):", description, bytes.len());
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let hex = line
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let ascii = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        dump.push_str(&format!("\n{:04X}: {:<47}  |{}|", i * 16, hex, ascii));
+    }
+
+    log::info!("{}", dump);
+}
+
+/// Generates a proper response for the Gen 4 Pokémon games' GTS service, given the body of the
+/// HTTP response.
+///
+/// This function mainly sets the appropriate headers and builds a response with it and the
+/// provided body.
+///
+/// # Arguments
+/// * `body` - The body of the HTTP response, containing the proper data to send to the client.
+/// * `profile_id` - The profile ID of the request, if any, to keep its session ID. See
+///   `set_randomize_session_id`.
+fn gts_response_gen4(
+    body: impl MessageBody + 'static,
+    profile_id: Option<u32>,
+) -> HttpResponse<BoxBody> {
+    // Response headers for the GTS service:
+    // (These seem to be optional.)
+    let headers = RESPONSE_HEADERS
+        .get()
+        .cloned()
+        .unwrap_or_else(default_response_headers);
+    let randomize_session_id = RANDOMIZE_SESSION_ID.load(Ordering::Relaxed);
 
     // Build the response object, with the headers and the body, and try to return it:
     let mut response_builder = HttpResponseBuilder::new(StatusCode::OK);
-    for header in headers {
-        response_builder.append_header(header);
+    for (name, value) in headers {
+        if randomize_session_id && name.eq_ignore_ascii_case("Set-Cookie") {
+            response_builder.append_header((name, session_cookie(profile_id)));
+        } else {
+            response_builder.append_header((name, value));
+        }
     }
-    let response = response_builder.message_body(BoxBody::new(body));
+    let body = match BoxBody::new(body).try_into_bytes() {
+        Ok(bytes) => {
+            log_hex_dump("GTS response body", &bytes);
+            BoxBody::new(bytes)
+        }
+        Err(body) => body,
+    };
+    let response = response_builder.message_body(body);
     if let Ok(response) = response {
         response
     } else {
@@ -98,10 +702,14 @@ fn gts_response_gen4(body: impl MessageBody + 'static) -> HttpResponse<BoxBody>
 ///
 /// # Arguments
 /// * `body` - The body of the HTTP response, containing the proper data to send to the client.
-fn gts_response_gen5(body: impl MessageBody + 'static) -> HttpResponse<BoxBody> {
+/// * `profile_id` - The profile ID of the request, if any. See `gts_response_gen4`.
+fn gts_response_gen5(
+    body: impl MessageBody + 'static,
+    profile_id: Option<u32>,
+) -> HttpResponse<BoxBody> {
     // Check body size, and skip footer generation if empty:
     if body.size().is_eof() {
-        return gts_response_gen4(body);
+        return gts_response_gen4(body, profile_id);
     }
 
     // Extract the body from the message in a format we can work with:
@@ -114,21 +722,61 @@ fn gts_response_gen5(body: impl MessageBody + 'static) -> HttpResponse<BoxBody>
     };
 
     // Generate and append the footer:
-    let b64_body = URL_SAFE.encode(&body_bytes);
+    let footer = gen5_footer(&body_bytes);
+    body_bytes.extend(footer.as_bytes());
+
+    // Add the proper heads, similarly to Gen 4 responses:
+    gts_response_gen4(body_bytes, profile_id)
+}
+
+/// Computes the footer appended to the body of Gen 5 responses: the SHA-1 hash of the body encoded
+/// in URL-safe base64, salted with `GEN5_SALT` before and after it, in lowercase hexadecimal.
+///
+/// # Arguments
+/// * `body` - The body of the response, without the footer.
+pub fn gen5_footer(body: &[u8]) -> String {
+    let b64_body = URL_SAFE.encode(body);
     let mut hasher = Sha1::new();
     hasher.update(GEN5_SALT);
     hasher.update(b64_body.as_bytes());
     hasher.update(GEN5_SALT);
-    let footer = format!("{:x}", hasher.finalize());
 
-    body_bytes.extend(footer.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
 
-    // Add the proper heads, similarly to Gen 4 responses:
-    gts_response_gen4(body_bytes)
+/// Checks whether the body of a Gen 5 response ends with the footer of the rest of it. See
+/// `gen5_footer`.
+///
+/// # Arguments
+/// * `response` - The body of the response, with the footer.
+///
+/// Returns `false` if the response is too short to have a footer. Empty responses, and the
+/// responses to token requests, have none.
+pub fn verify_gen5_footer(response: &[u8]) -> bool {
+    let Some(footer_start) = response.len().checked_sub(GEN5_FOOTER_LEN) else {
+        return false;
+    };
+    let (body, footer) = response.split_at(footer_start);
+
+    footer == gen5_footer(body).as_bytes()
 }
 
 // Middleware functions to perform request pre-processing:
 
+/// Checks whether a GTS request is a token request, i.e., the first request of the token exchange.
+///
+/// Token requests only include the PID query in the URL (i.e., they end with "?pid=<pid>"). Other
+/// requests with a single query parameter are not token requests. See `DEFAULT_GTS_TOKEN` for the
+/// token exchange.
+///
+/// # Arguments
+/// * `query_string` - The query string of the request's URL.
+pub fn is_token_request(query_string: &str) -> bool {
+    Query::<HashMap<String, String>>::from_query(query_string)
+        .map(|args| args.len() == 1 && args.contains_key(TOKEN_QUERY_KEY))
+        .unwrap_or(false)
+}
+
 /// Macro to generate the middleware functions for Gen 4 and Gen 5.
 ///
 /// As the middleware functions only differ in the GTS response generation function used,
@@ -145,6 +793,11 @@ macro_rules! handle_request {
                 req: ServiceRequest,
                 next: Next<BoxBody>,
             ) -> ActixResult<ServiceResponse<BoxBody>, ActixError> {
+                log_hex_dump(
+                    &format!("GTS request query for {}", req.path()),
+                    req.query_string().as_bytes(),
+                );
+
                 // Handle requests to unknown routes:
                 if req.match_name().is_none() {
                     log::warn!("No route found for {}", req.path());
@@ -152,20 +805,21 @@ macro_rules! handle_request {
                     return Ok(req.into_response("").map_into_boxed_body());
                 }
                 // Handle token requets:
-                // These include one query in the URL (i.e., they end with "?<key>=<value>").
-                let args_map = Query::<HashMap<String, String>>::from_query(req.query_string())
-                    .expect("Failed to parse URL args")
-                    .into_inner();
-                if args_map.len() == 1 {
+                let profile_id = profile_id(req.query_string());
+                if is_token_request(req.query_string()) {
+                    let token = GTS_TOKEN
+                        .get()
+                        .map_or(DEFAULT_GTS_TOKEN, String::as_str)
+                        .to_string();
                     return Ok(ServiceResponse::new(
                         req.request().clone(),
-                        gts_response_gen4(GTS_TOKEN),
+                        gts_response_gen4(token, profile_id),
                     ));
                 }
 
                 // Progress request into the chain and build the proper response with the result:
                 let (req, res) = next.call(req).await?.into_parts();
-                let new_res = [<gts_response_gen$gen>](res.into_body());
+                let new_res = [<gts_response_gen$gen>](res.into_body(), profile_id);
                 Ok(ServiceResponse::new(req, new_res))
             }
         }
@@ -211,11 +865,28 @@ async fn set_profile() -> HttpResponse {
 #[get("/info.asp")]
 async fn info() -> HttpResponse {
     log::info!("Connection established.");
-    response_from_body!(b"\x01\x00")
+    let body = INFO_STATUS.load(Ordering::Relaxed).to_le_bytes().to_vec();
+    response_from_body!(body)
 }
 
+/// Response to `post.asp` rejecting the deposit.
+///
+/// The responses to `post.asp` are 16-bit little-endian status codes, which are the same for all
+/// Gen 4 (DP, Pt, HGSS) and Gen 5 (BW, B2W2) games (see the GTS server implementations in
+/// IR-GTS-MG and pkmn-classic-framework):
+/// * `0x0001` - The Pokémon was deposited. The game removes it from the player's save file.
+/// * `0x000C` - The Pokémon could not be deposited. The game shows an error, and keeps it.
+///
+/// This server rejects deposits by default, even successful ones: the Pokémon is saved to disk,
+/// and rejecting the deposit keeps it in the game too, instead of losing it to a GTS that does not
+/// really store it. See `set_accept_deposits` for accepting them.
+const POST_REJECTED: &[u8] = b"\x0c\x00";
+/// Response to `post.asp` accepting the deposit. See `POST_REJECTED`.
+const POST_ACCEPTED: &[u8] = b"\x01\x00";
+
 #[derive(Deserialize)]
 struct PostData {
+    pid: u32,
     data: String,
 }
 
@@ -233,25 +904,102 @@ macro_rules! post_endpoint {
             #[get("/post.asp")]
             async fn [<post_gen$gen>](data: Query<PostData>) -> HttpResponse {
                 log::info!("Receiving Gen {} Pokémon...", $gen);
+                log_hex_dump("Deposit data (base64)", data.data.as_bytes());
+                if let Ok(decoded) = URL_SAFE.decode(&data.data) {
+                    log_hex_dump("Deposit data (decoded)", &decoded);
+                }
 
                 // Create the GTS deposit struct from the received base64 data:
                 let deposit = match GTSDeposit::from_base64(&data.data, $gen == 5) {
                     Ok(deposit) => deposit,
                     Err(e) => {
                         log::error!("Failed to process Pokémon deposit: {}", e);
-                        return response_from_body!(b"\x0c\x00");
+                        return response_from_body!(POST_REJECTED);
                     }
                 };
+                let dry_run = DRY_RUN.load(Ordering::Relaxed);
+                if !dry_run {
+                    METRICS.record_deposit(deposit.pokemon(), $gen == 5);
+                }
+                for (offset, byte) in deposit.unknown_gts_data() {
+                    log::debug!(
+                        target: UNKNOWN_DATA_LOG_TARGET,
+                        "Gen {} deposit of {}, unknown GTS data at {:#04X}: {:#04X}",
+                        $gen,
+                        deposit.pokemon().species_name(),
+                        offset,
+                        byte
+                    );
+                }
 
-                // Extract the Pokémon and save it to disk:
-                let pokemon = deposit.pokemon();
-                let saved = pokemon
-                    .save(None, None)
-                    .expect(format!("Failed to save Gen {} Pokémon", $gen).as_str());
-                if saved {
-                    log::info!("Pokémon saved successfully.");
+                // Tell the exact game apart, for diagnostics:
+                let game = deposit.game();
+                let from_game = match game {
+                    Some(game) => format!("Pokémon {}", game),
+                    None => format!("an unknown Gen {} game", $gen),
+                };
+                if !dry_run {
+                    *LAST_DEPOSIT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                        Some(DepositedPokemon {
+                            species: deposit.pokemon().species_name().to_string(),
+                            generation: $gen,
+                            game: game.map(|game| game.to_string()),
+                        });
+                }
+
+                // Point out obviously edited Pokémon, without rejecting them:
+                let summary = deposit.pokemon().legality_summary();
+                if summary.is_clean() {
+                    log::info!(
+                        "Deposited {} from {}: {}",
+                        deposit.pokemon().species_name(),
+                        from_game,
+                        summary
+                    );
                 } else {
-                    log::warn!("Pokémon already saved. Skipping save.");
+                    log::warn!(
+                        "Deposited {} from {}: {}",
+                        deposit.pokemon().species_name(),
+                        from_game,
+                        summary
+                    );
+                }
+
+                if dry_run {
+                    log::info!("Dry run; the deposited Pokémon is not kept.");
+                    return response_from_body!(POST_REJECTED);
+                }
+
+                // Extract the Pokémon, fix it if requested, relay it if enabled, and save it to disk:
+                let mut pokemon = deposit.pokemon().clone();
+                if AUTO_LEGALIZE.load(Ordering::Relaxed) {
+                    for change in pokemon.auto_legalize() {
+                        log::info!("Auto-legalized {}: {}", pokemon.species_name(), change);
+                    }
+                }
+                if RELAY.load(Ordering::Relaxed) {
+                    let mut relayed = pokemon.clone();
+                    if RELAY_TRADE_EVOLUTION.load(Ordering::Relaxed) && relayed.evolve_by_trade() {
+                        log::info!(
+                            "{} evolved into {} by trade.",
+                            pokemon.species_name(),
+                            relayed.species_name()
+                        );
+                    }
+                    log::info!("{} queued to be sent back.", relayed.species_name());
+                    RELAYED
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push_back(relayed);
+                }
+                // A failed save (e.g., a full disk) must not take the server down:
+                match pokemon.save_with_format(
+                    SAVE_DIR.get().map(PathBuf::as_path),
+                    SAVE_FORMAT.get().copied().unwrap_or_default(),
+                ) {
+                    Ok(true) => log::info!("Pokémon saved successfully."),
+                    Ok(false) => log::warn!("Pokémon already saved. Skipping save."),
+                    Err(e) => log::error!("Failed to save Gen {} Pokémon: {}", $gen, e),
                 }
 
                 // Dump Pokémon to the debug output and a file:
@@ -263,6 +1011,7 @@ macro_rules! post_endpoint {
                     Ok(_) =>{
                         if let Ok(file_metadata) = fs::metadata("recv_pkm.log") {
                             let mut file_permissions = file_metadata.permissions();
+                            #[allow(clippy::permissions_set_readonly_false)]
                             file_permissions.set_readonly(false);
                             if fs::set_permissions("recv_pkm.log", file_permissions).is_err() {
                                 log::warn!("Failed to change permissions for `recv_pkm.log`");
@@ -276,7 +1025,22 @@ macro_rules! post_endpoint {
                     Err(e) => log::error!("Failed to log received Pokémon to `recv_pkm.log`: {}", e)
                 }
 
-                response_from_body!(b"\x0c\x00")
+                if !ACCEPT_DEPOSITS.load(Ordering::Relaxed) {
+                    return response_from_body!(POST_REJECTED);
+                }
+                log::info!("{} deposited by profile {}.", pokemon.species_name(), data.pid);
+                let replaced = DEPOSITS
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .insert(data.pid, pokemon);
+                if let Some(replaced) = replaced {
+                    log::warn!(
+                        "Profile {} already had a deposit; {} replaced.",
+                        data.pid,
+                        replaced.species_name()
+                    );
+                }
+                response_from_body!(POST_ACCEPTED)
             }
         }
     };
@@ -285,11 +1049,84 @@ macro_rules! post_endpoint {
 post_endpoint!(4);
 post_endpoint!(5);
 
-#[get("/search.asp")]
-async fn search() -> HttpResponse {
-    response_from_body!(b"")
+#[derive(Deserialize)]
+struct SearchData {
+    data: String,
+}
+
+/// Macro to generate the search endpoints for Gen 4 and Gen 5.
+///
+/// This macro is used to avoid code repetition, as the Gen 4 and Gen 5 search endpoints differ
+/// only in the generation the search data is parsed as, and the Pokémon of the pool converted to.
+///
+/// The response is the list of Pokémon found, serialized as a `GTSSearchResult`: each one as in a
+/// `result.asp` response, one after the other. An empty response means no Pokémon were found.
+///
+/// # Arguments
+/// * `$gen` - The generation number for which to generate the search endpoint function (4 or 5).
+macro_rules! search_endpoint {
+    ($gen:literal) => {
+        paste! {
+            #[get("/search.asp")]
+            async fn [<search_gen$gen>](data: Query<SearchData>) -> HttpResponse {
+                let search = match GTSSearch::from_base64(&data.data, $gen == 5) {
+                    Ok(search) => search,
+                    Err(e) => {
+                        log::error!("Failed to process GTS search: {}", e);
+                        return response_from_body!(b"");
+                    }
+                };
+                log::debug!("Gen {} GTS search: {:?}", $gen, search);
+
+                let Some(pool) = SEARCH_POOL.get() else {
+                    log::info!("No search pool set; the GTS search finds no Pokémon.");
+                    return response_from_body!(b"");
+                };
+                let files = match pool.files() {
+                    Ok(files) => files,
+                    Err(e) => {
+                        log::error!("Failed to list the search pool: {}", e);
+                        return response_from_body!(b"");
+                    }
+                };
+
+                // List the matching Pokémon of the pool, up to the number of results requested:
+                let mut results = GTSSearchResult::new($gen == 5);
+                for path in files {
+                    if results.len() >= search.max_results() as usize {
+                        break;
+                    }
+                    let Some(pokemon) = load_pokemon_for_generation(&path, $gen == 5) else {
+                        continue;
+                    };
+                    let reception = GTSReception::from_pokemon_with_seed(
+                        &pokemon,
+                        RECEPTION_SEED.get().copied(),
+                    );
+                    if !search.matches(&reception) {
+                        continue;
+                    }
+                    if let Err(e) = results.push(reception) {
+                        log::error!("Failed to list {} in the GTS search: {}", path.display(), e);
+                    }
+                }
+                log::info!("GTS search found {} Pokémon.", results.len());
+                let body = results.serialize();
+                log_hex_dump("Serialized GTS search results", &body);
+
+                response_from_body!(body)
+            }
+        }
+    };
 }
 
+search_endpoint!(4);
+search_endpoint!(5);
+
+/// Response to `result.asp` telling the game that there is no Pokémon for it, so that it proceeds
+/// to deposit one.
+const NO_RECEPTION: &[u8] = b"\x05\x00";
+
 /// Macro to generate the result endpoints for Gen 4 and Gen 5.
 ///
 /// This macro is used to avoid code repetition, as the Gen 4 and Gen 5 result endpoints differ
@@ -303,60 +1140,118 @@ macro_rules! result_endpoint {
     ($gen:literal) => {
         paste! {
             #[get("/result.asp")]
-            async fn [<result_gen$gen>]() -> HttpResponse {
-                // Loop until a valid Pokémon is specified, or no Pokémon is sent:
-                let pokemon = loop {
-                    let mut path = String::new();
-
-                    println!("Enter the path or drag the .pkm/.pk{} file here.", $gen);
-                    println!("Leave blank to not send a Pokémon and proceed through the GTS \
-                        (for deposits).");
-
-                    // Read and sanitize the path, or skip:
-                    if stdin().read_line(&mut path).is_err() {
-                        log::error!("Error reading from stdin.");
-                        continue;
-                    } else if path.trim().is_empty() {
-                        log::warn!("No Pokémon path provided; letting the game proceed to \
-                            Pokémon deposit.");
-                        return response_from_body!(b"\x05\x00");
-                    }
+            async fn [<result_gen$gen>](req: HttpRequest) -> HttpResponse {
+                if DEPOSIT_ONLY.load(Ordering::Relaxed) {
+                    log::info!("Deposit-only mode; letting the game proceed to Pokémon deposit.");
+                    return response_from_body!(NO_RECEPTION);
+                }
+                if DRY_RUN.load(Ordering::Relaxed) {
+                    log::info!("Dry run; letting the game proceed to Pokémon deposit.");
+                    return response_from_body!(NO_RECEPTION);
+                }
+                let profile_id = profile_id(req.query_string());
 
-                    path = path.trim().to_string();
-                    if (path.starts_with("'") && path.ends_with("'"))
-                        || path.starts_with("\"") && path.ends_with("\"")
-                    {
-                        path = path[1..path.len() - 1].to_string();
-                    }
+                // Return the Pokémon deposited by this profile, if any; it is kept until the game
+                // deletes it from the GTS:
+                let deposit = profile_id.and_then(|pid| {
+                    DEPOSITS
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .get(&pid)
+                        .cloned()
+                });
+                if let Some(deposit) = deposit {
+                    log::info!(
+                        "Returning the {} deposited by profile {}.",
+                        deposit.species_name(),
+                        profile_id.unwrap_or_default()
+                    );
+                    let Some(pokemon) = pokemon_for_generation(deposit, $gen == 5) else {
+                        log::warn!("Deposit not returned; letting the game proceed to deposit.");
+                        return response_from_body!(NO_RECEPTION);
+                    };
+                    let body =
+                        GTSReception::from_pokemon_with_seed(&pokemon, RECEPTION_SEED.get().copied())
+                            .serialize();
+                    log_hex_dump("Serialized GTS reception", &body);
+                    return response_from_body!(body);
+                }
 
-                    // Load the Pokémon struct and return it:
-                    let pokemon_load = Pokemon::load(Path::new(&path));
-                    let mut pokemon = match pokemon_load {
-                        Ok(pokemon) => pokemon,
-                        Err(e) => {
-                            log::error!("Failed to load Gen {} Pokémon from {}: {}", $gen, path, e);
-                            continue;
+                // Take the next relayed Pokémon, if any; otherwise, take the next queued Pokémon,
+                // or the Pokémon from the configured source, or prompt for it:
+                let relayed = RELAYED
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .pop_front();
+                let queued = if relayed.is_none() {
+                    let mut queue = QUEUE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    queue.pop_front().map(|path| (path, queue.len()))
+                } else {
+                    None
+                };
+                let pokemon = match (relayed, queued, [<GEN$gen _SOURCE>].get()) {
+                    // The relayed and queued Pokémon that cannot be sent are put back, to not lose them:
+                    (Some(relayed), _, _) => {
+                        log::info!("Relaying {}.", relayed.species_name());
+                        let pokemon = pokemon_for_generation(relayed.clone(), $gen == 5);
+                        if pokemon.is_none() {
+                            log::warn!("{} put back in the relay queue.", relayed.species_name());
+                            RELAYED
+                                .lock()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                                .push_front(relayed);
                         }
-                    };
-                    log::info!("Pokémon loaded from {} successfully.", path);
-
-                    if !pokemon.is_gen5() && ($gen == 5) {
-                        log::warn!("Sending a Gen 4 Pokémon to a Gen 5 game.");
-                        pokemon.convert_to_gen5();
-                    } else if pokemon.is_gen5() && ($gen == 4) {
-                        if pokemon.try_convert_to_gen4().is_ok() {
-                            log::warn!("Sending a Gen 5 Pokémon to a Gen 4 game.");
-                        } else {
-                            log::error!("Cannot send that Gen 5 Pokémon to a Gen 4 game.");
-                            continue;
+                        pokemon
+                    }
+                    (None, Some((path, left)), _) => {
+                        log::info!("Sending queued {}; {} left in the queue.", path.display(), left);
+                        let pokemon = load_pokemon_for_generation(&path, $gen == 5);
+                        if pokemon.is_none() {
+                            log::warn!("{} put back in the queue.", path.display());
+                            QUEUE
+                                .lock()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                                .push_front(path);
                         }
+                        pokemon
                     }
-
-                    break pokemon;
+                    (None, None, Some(source)) => match source.next_file(profile_id) {
+                        Ok(path) => load_pokemon_for_generation(&path, $gen == 5),
+                        Err(e) => {
+                            log::error!("Failed to get the next Gen {} Pokémon: {}", $gen, e);
+                            None
+                        }
+                    },
+                    // The prompt blocks on the standard input, so it is run on the blocking
+                    // thread pool, to keep the workers serving the other requests meanwhile:
+                    (None, None, None) => web::block(|| prompt_pokemon_for_generation($gen == 5))
+                        .await
+                        .unwrap_or_else(|e| {
+                            log::error!("Failed to prompt for the Gen {} Pokémon: {}", $gen, e);
+                            None
+                        }),
+                };
+                let Some(pokemon) = pokemon else {
+                    log::warn!("No Pokémon sent; letting the game proceed to Pokémon deposit.");
+                    return response_from_body!(NO_RECEPTION);
                 };
 
+                METRICS.record_reception(&pokemon, $gen == 5);
+                *LAST_SERVED.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    Some(ServedPokemon {
+                        species: pokemon.species_name().to_string(),
+                        generation: $gen,
+                    });
+
                 // Build response:
-                let body = GTSReception::from_pokemon(&pokemon).serialize();
+                let mut reception =
+                    GTSReception::from_pokemon_with_seed(&pokemon, RECEPTION_SEED.get().copied());
+                if LINK_TRADE_ORIGIN.load(Ordering::Relaxed) {
+                    reception = reception.with_link_trade_origin();
+                }
+                let body = reception.serialize();
+                log_hex_dump("Serialized GTS reception", &body);
+                shut_down_after_reception();
 
                 response_from_body!(body)
             }
@@ -367,60 +1262,601 @@ macro_rules! result_endpoint {
 result_endpoint!(4);
 result_endpoint!(5);
 
+#[derive(Deserialize)]
+struct DeleteData {
+    pid: u32,
+}
+
 #[get("/delete.asp")]
-async fn delete() -> HttpResponse {
+async fn delete(data: Query<DeleteData>) -> HttpResponse {
+    if DRY_RUN.load(Ordering::Relaxed) {
+        return response_from_body!(b"\x01\x00");
+    }
+    if RECEPTION_PENDING.swap(false, Ordering::Relaxed) {
+        RECEPTION_CONFIRMED.notify_one();
+    }
+    // The game received the Pokémon sent to it, if any, so it can be dispensed:
+    for source in [GEN4_SOURCE.get(), GEN5_SOURCE.get()].into_iter().flatten() {
+        if let Err(e) = source.confirm_reception(Some(data.pid)) {
+            log::error!(
+                "Failed to dispense the Pokémon sent to profile {}: {}",
+                data.pid,
+                e
+            );
+        }
+    }
+    // The deposit of this profile, if any, was returned to the game, so it is forgotten:
+    match DEPOSITS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(&data.pid)
+    {
+        Some(deposit) => log::info!(
+            "Deleted the {} deposited by profile {}.",
+            deposit.species_name(),
+            data.pid
+        ),
+        None => log::info!("Profile {} has no deposit to delete.", data.pid),
+    }
+
     response_from_body!(b"\x01\x00")
 }
 
+/// Pokémon deposited by a game, as reported by the status page.
+#[derive(Clone, Serialize)]
+struct DepositedPokemon {
+    species: String,
+    generation: u8,
+    /// The game the Pokémon was deposited from, if it could be told. See `GTSDeposit::game`.
+    game: Option<String>,
+}
+
+/// Pokémon sent to a game, as reported by the status page.
+#[derive(Clone, Serialize)]
+struct ServedPokemon {
+    species: String,
+    generation: u8,
+}
+
+/// Usage counters of the server, since it was started.
+///
+/// The counters of each generation are indexed by `is_gen5 as usize`.
+struct Metrics {
+    /// Pokémon deposits received from the games of each generation.
+    deposits: [AtomicUsize; 2],
+    /// Pokémon sent to the games of each generation.
+    receptions: [AtomicUsize; 2],
+    /// Shiny Pokémon deposits received.
+    shiny_deposits: AtomicUsize,
+    /// IDs of the species deposited or sent.
+    species_seen: Mutex<BTreeSet<u16>>,
+}
+
+impl Metrics {
+    /// Creates the counters, all at zero.
+    const fn new() -> Self {
+        Self {
+            deposits: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            receptions: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            shiny_deposits: AtomicUsize::new(0),
+            species_seen: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Counts a Pokémon deposited by a game.
+    ///
+    /// # Arguments
+    /// * `pokemon` - The deposited Pokémon.
+    /// * `is_gen5` - Whether it was deposited by a Gen 5 game.
+    fn record_deposit(&self, pokemon: &Pokemon, is_gen5: bool) {
+        self.deposits[is_gen5 as usize].fetch_add(1, Ordering::Relaxed);
+        if pokemon.is_shiny() {
+            self.shiny_deposits.fetch_add(1, Ordering::Relaxed);
+        }
+        self.record_species(pokemon);
+    }
+
+    /// Counts a Pokémon sent to a game.
+    ///
+    /// # Arguments
+    /// * `pokemon` - The sent Pokémon.
+    /// * `is_gen5` - Whether it was sent to a Gen 5 game.
+    fn record_reception(&self, pokemon: &Pokemon, is_gen5: bool) {
+        self.receptions[is_gen5 as usize].fetch_add(1, Ordering::Relaxed);
+        self.record_species(pokemon);
+    }
+
+    /// Adds the species of a Pokémon to the ones seen.
+    fn record_species(&self, pokemon: &Pokemon) {
+        self.species_seen
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(pokemon.species.id());
+    }
+
+    /// Returns the current values of the counters.
+    fn snapshot(&self) -> MetricsSnapshot {
+        let generation = |is_gen5: bool| GenerationMetrics {
+            deposits: self.deposits[is_gen5 as usize].load(Ordering::Relaxed),
+            receptions: self.receptions[is_gen5 as usize].load(Ordering::Relaxed),
+        };
+        let (gen4, gen5) = (generation(false), generation(true));
+
+        MetricsSnapshot {
+            deposits: gen4.deposits + gen5.deposits,
+            receptions: gen4.receptions + gen5.receptions,
+            unique_species: self
+                .species_seen
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .len(),
+            shiny_deposits: self.shiny_deposits.load(Ordering::Relaxed),
+            gen4,
+            gen5,
+        }
+    }
+}
+
+/// Usage counters of the games of a generation, as reported by the status page.
+#[derive(Serialize)]
+struct GenerationMetrics {
+    deposits: usize,
+    receptions: usize,
+}
+
+/// Usage counters of the server at some point, as reported by the status page and the periodic
+/// summaries.
+#[derive(Serialize)]
+struct MetricsSnapshot {
+    deposits: usize,
+    receptions: usize,
+    unique_species: usize,
+    shiny_deposits: usize,
+    gen4: GenerationMetrics,
+    gen5: GenerationMetrics,
+}
+
+impl fmt::Display for MetricsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} deposits (Gen 4: {}, Gen 5: {}, shiny: {}), {} receptions (Gen 4: {}, Gen 5: {}), \
+             {} unique species",
+            self.deposits,
+            self.gen4.deposits,
+            self.gen5.deposits,
+            self.shiny_deposits,
+            self.receptions,
+            self.gen4.receptions,
+            self.gen5.receptions,
+            self.unique_species
+        )
+    }
+}
+
+/// Starts logging a summary of the usage counters of the server periodically, for as long as the
+/// application runs.
+///
+/// The summaries are logged from their own thread, as the runtime's workers may all be busy
+/// serving DNS queries.
+///
+/// # Arguments
+/// * `period` - The time between two summaries. The first one is logged after a full period.
+pub fn spawn_metrics_logger(period: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(period);
+        log::info!("Usage since startup: {}.", METRICS.snapshot());
+    });
+}
+
+/// Activity of the server, as reported by the status page.
+#[derive(Serialize)]
+struct Status {
+    uptime_secs: u64,
+    #[serde(flatten)]
+    metrics: MetricsSnapshot,
+    last_deposit: Option<DepositedPokemon>,
+    last_served: Option<ServedPokemon>,
+    /// Number of Pokémon files left in the queue. See `queue_pokemon_files`.
+    queued: usize,
+    advertised_ip: Option<Ipv4Addr>,
+}
+
+/// Status page, for checking that the server is alive and its activity without reading the logs.
+///
+/// It is only served to requests from this machine; to the others, the route does not exist.
+#[get("/status")]
+async fn status(req: HttpRequest) -> HttpResponse {
+    if !req.peer_addr().is_some_and(|addr| addr.ip().is_loopback()) {
+        return HttpResponse::NotFound().finish();
+    }
+
+    HttpResponse::Ok().json(Status {
+        uptime_secs: STARTED_AT.elapsed().as_secs(),
+        metrics: METRICS.snapshot(),
+        last_deposit: LAST_DEPOSIT
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone(),
+        queued: QUEUE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len(),
+        last_served: LAST_SERVED
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone(),
+        advertised_ip: ADVERTISED_IP.get().copied(),
+    })
+}
+
+/// Listing of the Pokémon files in the save directory, with the summary of each Pokémon (see
+/// `Pokemon::summary_json`), for tools built on top of the server.
+///
+/// Like the status page, it is only served to requests from this machine. The files of every save
+/// format are listed (see `SaveFormat::FILE_EXTENSIONS`); files that fail to load are skipped.
+#[get("/admin/list")]
+async fn admin_list(req: HttpRequest) -> HttpResponse {
+    if !req.peer_addr().is_some_and(|addr| addr.ip().is_loopback()) {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let save_dir = SAVE_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("pokemon"));
+    let files = PokemonSource::new(save_dir)
+        .and_then(|source| source.files())
+        .unwrap_or_default();
+
+    let listing = files
+        .iter()
+        .filter_map(|file| {
+            let pokemon = Pokemon::load(file).ok()?;
+            Some(serde_json::json!({
+                "file": file.file_name()?.to_string_lossy(),
+                "pokemon": pokemon.summary_json(),
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok().json(listing)
+}
+
+/// Generations of games the GTS endpoints are served to.
+///
+/// The endpoints of a disabled generation are not mounted at all, so its games cannot connect.
+/// Both generations are enabled by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Vec<u8>")]
+pub struct Generations {
+    gen4: bool,
+    gen5: bool,
+}
+
+impl Generations {
+    /// Checks whether a generation is enabled.
+    ///
+    /// # Arguments
+    /// * `generation` - The generation number: 4 or 5.
+    pub fn contains(&self, generation: u8) -> bool {
+        match generation {
+            4 => self.gen4,
+            5 => self.gen5,
+            _ => false,
+        }
+    }
+}
+
+impl Default for Generations {
+    fn default() -> Self {
+        Self {
+            gen4: true,
+            gen5: true,
+        }
+    }
+}
+
+impl TryFrom<Vec<u8>> for Generations {
+    type Error = Error;
+
+    /// Builds the set of enabled generations from their numbers.
+    ///
+    /// Returns an error of kind `InvalidInput` if a generation other than 4 or 5 is given, or if
+    /// none is.
+    fn try_from(numbers: Vec<u8>) -> Result<Self> {
+        let mut generations = Self {
+            gen4: false,
+            gen5: false,
+        };
+        for generation in numbers {
+            match generation {
+                4 => generations.gen4 = true,
+                5 => generations.gen5 = true,
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unsupported generation: {}", generation),
+                    ))
+                }
+            }
+        }
+        if !generations.gen4 && !generations.gen5 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "At least one generation must be enabled",
+            ));
+        }
+
+        Ok(generations)
+    }
+}
+
+impl FromStr for Generations {
+    type Err = Error;
+
+    /// Parses a comma-separated list of generation numbers, e.g. `4,5`.
+    fn from_str(s: &str) -> Result<Self> {
+        let numbers = s
+            .split(',')
+            .map(|number| {
+                number.trim().parse().map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid generation: {}", number.trim()),
+                    )
+                })
+            })
+            .collect::<Result<Vec<u8>>>()?;
+
+        Self::try_from(numbers)
+    }
+}
+
 /// Wildcard IP address to listen to all IPv4 interfaces on this system.
 const ALL_V4_INTERFACES: Ipv4Addr = Ipv4Addr::new(0, 0, 0, 0);
-/// Port to listen to incoming HTTP requests on:
-const LISTENING_PORT: u16 = 80;
+
+/// Builds the application mimicking the Pokémon GTS service, with the endpoints of the enabled
+/// generations.
+///
+/// This is used both by the HTTP server and by the session replay harness. See
+/// `crate::session::replay`.
+///
+/// # Arguments
+/// * `generations` - The generations whose endpoints are mounted.
+pub fn gts_app(
+    generations: Generations,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = ActixError,
+        InitError = (),
+    >,
+> {
+    let mut app = App::new()
+        // Record the requests, if session recording is enabled:
+        .wrap(from_fn(record_request))
+        // Log actix HTTP server activity, if the log level is Debug or higher:
+        .wrap(Logger::default().log_level(log::Level::Debug));
+
+    // Endpoints/services:
+    if generations.contains(4) {
+        app = app.service(
+            scope("/pokemondpds")
+                .wrap(from_fn(handle_request_gen4))
+                .service(
+                    scope("/worldexchange")
+                        .service(info)
+                        .service(post_gen4)
+                        .service(search_gen4)
+                        .service(result_gen4)
+                        .service(delete),
+                )
+                .service(set_profile),
+        );
+    }
+    if generations.contains(5) {
+        app = app.service(
+            scope("/syachi2ds/web")
+                .wrap(from_fn(handle_request_gen5))
+                .service(
+                    scope("/worldexchange")
+                        .service(info)
+                        .service(post_gen5)
+                        .service(search_gen5)
+                        .service(result_gen5)
+                        .service(delete),
+                )
+                .service(set_profile),
+        );
+    }
+
+    app.service(status).service(admin_list)
+}
+
+/// Middleware function that records every request received into the session log.
+///
+/// Does nothing if session recording is not enabled. See `crate::session::record`.
+async fn record_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> ActixResult<ServiceResponse<impl MessageBody>, ActixError> {
+    session::record(SessionEntry::Http {
+        path: req.path().to_string(),
+        query: req.query_string().to_string(),
+    });
+
+    next.call(req).await
+}
 
 /// Creates the HTTP server mimicking the Pokémon GTS service, starts it, and returns the server
 /// instance.
 ///
-/// The server is bound to port 80 (HTTP) on all IPv4 interfaces in the system.
-pub fn run_http_server() -> Result<Server> {
-    let server = HttpServer::new(|| {
-        App::new()
-            // Log actix HTTP server activity, if the log level is Debug or higher:
-            .wrap(Logger::default().log_level(log::Level::Debug))
-            // Endpoints/services:
-            .service(
-                scope("/pokemondpds")
-                    .wrap(from_fn(handle_request_gen4))
-                    .service(
-                        scope("/worldexchange")
-                            .service(info)
-                            .service(post_gen4)
-                            .service(search)
-                            .service(result_gen4)
-                            .service(delete),
-                    )
-                    .service(set_profile),
-            )
-            .service(
-                scope("/syachi2ds/web")
-                    .wrap(from_fn(handle_request_gen5))
-                    .service(
-                        scope("/worldexchange")
-                            .service(info)
-                            .service(post_gen5)
-                            .service(search)
-                            .service(result_gen5)
-                            .service(delete),
-                    )
-                    .service(set_profile),
-            )
-    })
-    // Disable signal handling, for exiting with Ctrl + C:
-    .disable_signals()
-    // Spawn just one worker, as (many) concurrent petitions are not expected:
-    .workers(1)
-    .bind((ALL_V4_INTERFACES, LISTENING_PORT))?;
+/// The server is bound to the given port on all IPv4 interfaces in the system.
+///
+/// # Arguments
+/// * `port` - The port to listen to incoming HTTP requests on. The consoles always connect to
+///   port 80 (HTTP).
+/// * `workers` - The number of worker threads of the server. One is usually enough, as (many)
+///   concurrent requests are not expected.
+/// * `generations` - The generations of games to serve. The endpoints of the other one are not
+///   mounted.
+pub fn run_http_server(port: u16, workers: usize, generations: Generations) -> Result<Server> {
+    LazyLock::force(&STARTED_AT);
+    let server = HttpServer::new(move || gts_app(generations))
+        // Disable signal handling, for exiting with Ctrl + C:
+        .disable_signals()
+        .workers(workers)
+        .bind((ALL_V4_INTERFACES, port))?;
 
     log::info!("Running HTTP server on {}", server.addrs()[0]);
+    for (generation, root) in [(4, "/pokemondpds"), (5, "/syachi2ds/web")] {
+        if !generations.contains(generation) {
+            log::info!("Gen {} disabled; {} is not served.", generation, root);
+        }
+    }
+
+    let server = server.run();
+    // Only fails if already set, i.e., if another server was started, which is not done:
+    let _ = SERVER_HANDLE.set(server.handle());
+
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_and_read_body, init_service, TestRequest};
+
+    #[test]
+    fn gen5_footers_are_verified() {
+        let footer = gen5_footer(&[0x01, 0x00]);
+        assert_eq!(footer, "729abc61b2d89c9b4ed122265c827524a032cb42");
 
-    Ok(server.run())
+        let mut response = vec![0x01, 0x00];
+        response.extend(footer.as_bytes());
+        assert!(verify_gen5_footer(&response));
+
+        response[0] = 0x02;
+        assert!(!verify_gen5_footer(&response));
+        assert!(!verify_gen5_footer(&[0x01, 0x00]));
+    }
+
+    #[actix_web::test]
+    async fn gen5_responses_have_a_valid_footer() {
+        let app = init_service(gts_app(Generations::default())).await;
+
+        let request = TestRequest::get()
+            .uri("/syachi2ds/web/worldexchange/info.asp?hash=abcdef")
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        assert!(verify_gen5_footer(&body));
+        assert_eq!(body.len(), 2 + GEN5_FOOTER_LEN);
+    }
+
+    #[test]
+    fn session_cookies_are_kept_per_profile() {
+        assert_eq!(profile_id("pid=123&hash=abc"), Some(123));
+        assert_eq!(profile_id("hash=abc"), None);
+
+        let cookie = session_cookie(profile_id("pid=123&hash=abc"));
+        assert_eq!(session_cookie(Some(123)), cookie);
+        assert_ne!(session_cookie(Some(456)), cookie);
+        assert_eq!(session_cookie(None), session_cookie(None));
+    }
+
+    #[test]
+    fn token_requests_only_have_the_pid_query() {
+        assert!(is_token_request("pid=12345678"));
+
+        assert!(!is_token_request(""));
+        assert!(!is_token_request("hash=abcdef"));
+        assert!(!is_token_request("pid=12345678&hash=abcdef&data=AAAA"));
+    }
+
+    #[actix_web::test]
+    async fn one_query_requests_are_routed_normally() {
+        let app = init_service(gts_app(Generations::default())).await;
+
+        let request = TestRequest::get()
+            .uri("/pokemondpds/worldexchange/info.asp?pid=12345678")
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        assert_eq!(body, DEFAULT_GTS_TOKEN.as_bytes());
+
+        let request = TestRequest::get()
+            .uri("/pokemondpds/worldexchange/info.asp?hash=abcdef")
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        assert_eq!(body, DEFAULT_INFO_STATUS.to_le_bytes().as_slice());
+    }
+
+    #[actix_web::test]
+    async fn deposits_are_returned_until_deleted() {
+        let app = init_service(gts_app(Generations::default())).await;
+        let deposit = Pokemon::deserialize(include_bytes!("../tests/fixtures/gen4_boxed.pk4"));
+        DEPOSITS.lock().unwrap().insert(424242, deposit.clone());
+
+        let request = TestRequest::get()
+            .uri("/pokemondpds/worldexchange/result.asp?pid=424242&hash=abcdef")
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        let expected = GTSReception::from_pokemon_with_seed(&deposit, None).serialize();
+        assert_eq!(body.len(), expected.len());
+        assert!(DEPOSITS.lock().unwrap().contains_key(&424242));
+
+        let request = TestRequest::get()
+            .uri("/pokemondpds/worldexchange/delete.asp?pid=424242&hash=abcdef")
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        assert_eq!(body, b"\x01\x00".as_slice());
+        assert!(!DEPOSITS.lock().unwrap().contains_key(&424242));
+    }
+
+    #[actix_web::test]
+    async fn admin_list_lists_every_save_format() {
+        let app = init_service(gts_app(Generations::default())).await;
+        let dir = std::env::temp_dir().join(format!("gts-rs-admin-list-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        set_save_dir(dir.clone()).unwrap();
+        let pokemon = Pokemon::deserialize(include_bytes!("../tests/fixtures/gen4_boxed.pk4"));
+        for format in [SaveFormat::Pk, SaveFormat::Ek, SaveFormat::Json] {
+            assert!(pokemon.save_with_format(Some(&dir), format).unwrap());
+        }
+
+        let request = TestRequest::get()
+            .uri("/admin/list")
+            .peer_addr("127.0.0.1:12345".parse().unwrap())
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        fs::remove_dir_all(&dir).unwrap();
+        let listing: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let mut extensions = listing
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                let file = entry["file"].as_str().unwrap();
+                file.rsplit('.').next().unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        extensions.sort();
+        assert_eq!(extensions, ["ek4", "json", "pk4"]);
+    }
+
+    #[actix_web::test]
+    async fn queued_files_that_fail_to_load_are_kept() {
+        let app = init_service(gts_app(Generations::default())).await;
+        let missing = PathBuf::from("missing-queued-file.pk4");
+        queue_pokemon_files(vec![missing.clone()]);
+
+        let request = TestRequest::get()
+            .uri("/pokemondpds/worldexchange/result.asp?pid=434343&hash=abcdef")
+            .to_request();
+        let body = call_and_read_body(&app, request).await;
+        assert_eq!(body, NO_RECEPTION);
+        assert_eq!(QUEUE.lock().unwrap().pop_front(), Some(missing));
+    }
 }
@@ -16,13 +16,35 @@
  * You should have received a copy of the GNU General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+mod cli;
+mod config;
+mod data_dump;
 mod dns_server;
 mod http_server;
+mod pokemon_source;
+mod session;
 
-use crate::{dns_server::DNSServer, http_server::run_http_server};
-use futures::future::join;
+use crate::{
+    cli::Args,
+    config::Config,
+    dns_server::{DNSServer, LISTENING_PORT as DNS_PORT, REWRITE_LOG_TARGET as DNS_LOG_TARGET},
+    http_server::{
+        queue_pokemon_files, run_http_server, set_accept_deposits, set_advertised_ip,
+        set_auto_legalize, set_deposit_only, set_exit_after_reception, set_gts_token,
+        set_info_status, set_link_trade_origin, set_pokemon_source, set_randomize_session_id,
+        set_reception_seed, set_relay, set_response_headers, set_save_dir, set_save_format,
+        set_search_pool, set_verbose_protocol, spawn_metrics_logger,
+    },
+    pokemon_source::{read_playlist, PokemonSource},
+};
 use is_superuser::is_superuser;
-use std::io::{Error, ErrorKind, Result};
+use pkm_utils::{pokemon::Pokemon, savefile::SaveFile};
+use std::{
+    fs::{File, OpenOptions},
+    io::{stdout, Error, ErrorKind, Result, Write},
+    path::Path,
+    time::Duration,
+};
 
 fn print_license() {
     println!(
@@ -35,36 +57,337 @@ fn print_license() {
     );
 }
 
+/// Converts the Pokémon in a file to the other generation, and writes it to a new file.
+///
+/// # Arguments
+/// * `input` - The Gen 4 or Gen 5 Pokémon file to convert.
+/// * `output` - The file to write the converted Pokémon to. It must not exist.
+///
+/// Returns an error if the Pokémon cannot be loaded, or converted (e.g., because it holds an item
+/// that does not exist in the other generation), or if the output file cannot be created.
+fn migrate(input: &Path, output: &Path) -> Result<()> {
+    let mut pokemon = Pokemon::load(input)?;
+    if !pokemon.is_gen5() {
+        pokemon.try_convert_to_gen5()?;
+    } else {
+        pokemon.try_convert_to_gen4()?;
+    }
+
+    File::create_new(output)?.write_all(&pokemon.serialize())?;
+    println!(
+        "Converted {} to Gen {}: {}",
+        input.display(),
+        if pokemon.is_gen5() { 5 } else { 4 },
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Saves the Pokémon in the PC boxes of a save file to a directory, as Pokémon files.
+///
+/// # Arguments
+/// * `save` - The Gen 4 or Gen 5 save file to extract the Pokémon from.
+/// * `dir` - The directory to save the Pokémon files to. Identical files already in it are kept.
+///
+/// Returns an error if the save file cannot be loaded, any of its Pokémon cannot be
+/// deserialized, or a Pokémon file cannot be written.
+fn extract_save(save: &Path, dir: &Path) -> Result<()> {
+    let save_file = SaveFile::load(save)?;
+    let pokemon = save_file.all_box_pokemon()?;
+
+    let mut saved = 0;
+    for pokemon in &pokemon {
+        if pokemon.save(Some(dir), None)? {
+            saved += 1;
+        }
+    }
+    println!(
+        "Extracted {} Pokémon from {} to {} ({} already there)",
+        saved,
+        save.display(),
+        dir.display(),
+        pokemon.len() - saved
+    );
+
+    Ok(())
+}
+
 // Log level: default to "info" for release builds, and "debug" for debug builds.
 #[cfg(debug_assertions)]
 const DEFAULT_LOG_LEVEL: &str = "debug";
 #[cfg(not(debug_assertions))]
 const DEFAULT_LOG_LEVEL: &str = "info";
 
+/// Logger writing the DNS activity (the `REWRITE_LOG_TARGET` log target) to its own file, and the
+/// rest of the log as usual.
+struct SplitLogger {
+    /// Logger of everything but the DNS activity, following the log filter.
+    main: env_logger::Logger,
+    /// Logger of the DNS activity, to its file.
+    dns: env_logger::Logger,
+}
+
+impl SplitLogger {
+    /// Gets the logger of the records of a log target.
+    ///
+    /// # Arguments
+    /// * `target` - The log target of the records.
+    fn logger(&self, target: &str) -> &env_logger::Logger {
+        if target == DNS_LOG_TARGET {
+            &self.dns
+        } else {
+            &self.main
+        }
+    }
+}
+
+impl log::Log for SplitLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.logger(metadata.target()).enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.logger(record.target()).log(record);
+    }
+
+    fn flush(&self) {
+        self.main.flush();
+        self.dns.flush();
+    }
+}
+
+/// Initializes the logger.
+///
+/// # Arguments
+/// * `filter` - The log filter, in the `RUST_LOG` syntax. The `RUST_LOG` environment variable
+///   takes precedence over it.
+/// * `dns_log` - The file to write the DNS activity to, apart from the rest of the log, if any. It
+///   is appended to, and all the DNS activity is written to it, whatever the log filter.
+///
+/// Returns the corresponding error if the DNS log file could not be opened.
+fn init_logger(filter: &str, dns_log: Option<&Path>) -> Result<()> {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(filter));
+    let Some(dns_log) = dns_log else {
+        builder.init();
+        return Ok(());
+    };
+
+    let file = OpenOptions::new().create(true).append(true).open(dns_log)?;
+    let dns = env_logger::Builder::new()
+        .filter(Some(DNS_LOG_TARGET), log::LevelFilter::Debug)
+        .write_style(env_logger::WriteStyle::Never)
+        .target(env_logger::Target::Pipe(Box::new(file)))
+        .build();
+    let main = builder.build();
+    log::set_max_level(main.filter().max(dns.filter()));
+    log::set_boxed_logger(Box::new(SplitLogger { main, dns })).map_err(Error::other)
+}
+
+/// Explains an error binding one of the servers to its port, suggesting how to fix it.
+///
+/// # Arguments
+/// * `e` - The error binding the server.
+/// * `server` - The name of the server, e.g., "DNS".
+/// * `port` - The port the server was being bound to.
+pub fn port_bind_error(e: Error, server: &str, port: u16) -> Error {
+    let hint = match e.kind() {
+        ErrorKind::PermissionDenied => " Ports below 1024 need superuser privileges, or the \
+            CAP_NET_BIND_SERVICE capability (e.g., \
+            `sudo setcap cap_net_bind_service=+ep target/release/gts-rs`)."
+            .to_string(),
+        ErrorKind::AddrInUse => " Another application is listening on it; stop it first \
+            (e.g., `systemd-resolved` for the DNS port)."
+            .to_string(),
+        _ => String::new(),
+    };
+
+    Error::new(
+        e.kind(),
+        format!(
+            "Could not bind the {} server to port {}: {}.{}",
+            server, port, e, hint
+        ),
+    )
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Parse the command-line arguments:
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(e);
+        }
+    };
+
+    // Print a data table, if requested, alone so that the output can be used by scripts:
+    // (A closed output, e.g., when piped to `head`, is not an error.)
+    if let Some(table) = args.dump_table {
+        let dump = data_dump::dump(table, args.dump_format);
+        return match writeln!(stdout(), "{}", dump) {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        };
+    }
+
+    // Convert a Pokémon file to the other generation, if requested, and exit:
+    if let Some((input, output)) = &args.migrate {
+        let result = migrate(input, output);
+        if let Err(e) = &result {
+            eprintln!("{}", e);
+        }
+        return result;
+    }
+
+    // Extract the PC box Pokémon of a save file, if requested, and exit:
+    if let Some((save, dir)) = &args.extract_save {
+        let result = extract_save(save, dir);
+        if let Err(e) = &result {
+            eprintln!("{}", e);
+        }
+        return result;
+    }
+
     print_license();
 
-    // Check for superuser privileges:
+    // Load the configuration; the command-line options override the configuration file:
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(e);
+        }
+    };
+
+    // Initialize the logger; with the configured level, or the default one for this build.
+    let log_filter = config.log.as_deref().unwrap_or(DEFAULT_LOG_LEVEL);
+    if let Err(e) = init_logger(log_filter, config.dns_log.as_deref()) {
+        eprintln!("Failed to open the DNS log file: {}", e);
+        return Err(e);
+    }
+
+    // Load the Pokémon data before any request, instead of on the first one:
+    if let Err(e) = pkm_utils::data_maps::preload() {
+        eprintln!("{}", e);
+        return Err(e.into());
+    }
+
+    set_verbose_protocol(config.verbose_protocol);
+    set_auto_legalize(config.auto_legalize);
+    set_deposit_only(config.deposit_only);
+    set_accept_deposits(config.accept_deposits);
+    set_exit_after_reception(config.once);
+    set_relay(
+        config.relay || config.relay_trade_evolution,
+        config.relay_trade_evolution,
+    );
+    set_randomize_session_id(config.random_session_id);
+    if let Some(token) = config.gts_token {
+        set_gts_token(token)?;
+    }
+    set_info_status(config.info_status);
+    if !config.headers.is_empty() {
+        set_response_headers(config.headers.into_iter().collect())?;
+    }
+    set_save_dir(config.save_dir)?;
+    set_save_format(config.save_format)?;
+    if let Some(seed) = config.reception_seed {
+        set_reception_seed(seed)?;
+    }
+    set_link_trade_origin(config.link_trade_origin);
+
+    // Check the Pokémon file to send to both generations, if given, so that a headless server
+    // fails on startup rather than on the first request:
+    if let Some(path) = &config.pokemon_file {
+        if !path.is_file() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Pokémon file not found: {}", path.display()),
+            ));
+        }
+        Pokemon::load(path)?;
+    }
+
+    // Queue the given Pokémon files, checking them on startup too:
+    let mut queue = config.queue;
+    if let Some(path) = &config.queue_file {
+        queue.extend(read_playlist(path)?);
+    }
+    for path in &queue {
+        Pokemon::load(path).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid queued Pokémon file {}: {}", path.display(), e),
+            )
+        })?;
+    }
+    if !queue.is_empty() {
+        log::info!("Queued {} Pokémon to send.", queue.len());
+        queue_pokemon_files(queue);
+    }
+
+    // Set the Pokémon sources of each generation, if given, or the Pokémon file for both:
+    let gen4_source = config.gen4_source.or_else(|| config.pokemon_file.clone());
+    let gen5_source = config.gen5_source.or(config.pokemon_file);
+    for (source, is_gen5) in [(gen4_source, false), (gen5_source, true)] {
+        if let Some(path) = source {
+            let mut source = PokemonSource::new(path)?.with_order(config.source_order);
+            if config.dispense || config.dispense_replenish {
+                source = source.with_dispensing(config.dispense_replenish)?;
+            }
+            log::info!(
+                "{} Pokémon from {} to Gen {} games.",
+                if config.dispense || config.dispense_replenish {
+                    "Dispensing"
+                } else {
+                    "Sending"
+                },
+                source.path().display(),
+                if !is_gen5 { 4 } else { 5 }
+            );
+            set_pokemon_source(is_gen5, source)?;
+        }
+    }
+    if let Some(path) = config.search_pool {
+        let pool = PokemonSource::new(path)?;
+        log::info!(
+            "Listing Pokémon from {} in GTS searches.",
+            pool.path().display()
+        );
+        set_search_pool(pool)?;
+    }
+
+    // Replay a recorded session, if requested, without running the servers:
+    if let Some(path) = &args.replay_session {
+        return session::replay(path, config.generations).await;
+    }
+
+    // Privileged ports can also be bound with capabilities, so only warn if not running as
+    // superuser; binding the ports fails with an explanation otherwise:
     if !is_superuser() {
-        eprintln!("This program must be run as superuser.");
-        return Err(Error::new(
-            ErrorKind::PermissionDenied,
-            "Not running as superuser",
-        ));
+        log::warn!(
+            "Not running as superuser; binding ports {} and {} may fail.",
+            DNS_PORT,
+            config.http_port
+        );
     }
 
-    // Initialize the logger; with the default level for this build.
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(DEFAULT_LOG_LEVEL))
-        .init();
+    // Start recording the session, if requested:
+    if let Some(path) = &config.record_session {
+        session::start_recording(path)?;
+    }
 
     // Create and run servers, print exteral IP:
-    let dns_server = DNSServer::new(None)
-        .await
-        .expect("Could not create the DNS server");
+    let dns_server = DNSServer::new(Some(config.upstream_dns.to_string()), config.advertise_ip)
+        .await?
+        .with_cache_max_ttl(Duration::from_secs(config.dns_cache_max_ttl));
 
     let ip = dns_server.ip();
     println!("GTS-RS servers running on IP: {}", ip);
+    set_advertised_ip(ip)?;
 
     let dns_handle = tokio::spawn(async move {
         dns_server
@@ -73,12 +396,23 @@ async fn main() -> Result<()> {
             .expect("The DNS server failed to run");
     });
 
-    let http_handle = run_http_server().expect("The HTTP server failed to run.");
+    if config.metrics_log_interval > 0 {
+        spawn_metrics_logger(Duration::from_secs(config.metrics_log_interval));
+    }
+
+    let http_handle = run_http_server(config.http_port, config.http_workers, config.generations)
+        .map_err(|e| port_bind_error(e, "HTTP", config.http_port))?;
 
-    // Await for both servers to finish (which should never happen):
-    let (http_result, dns_result) = join(http_handle, dns_handle).await;
-    http_result.expect("The HTTP server failed to run");
-    dns_result.expect("The DNS server failed to run");
+    // Await for the HTTP server to finish, which only happens after a single reception with
+    // `--once`; then, the DNS server is no longer needed:
+    http_handle.await.expect("The HTTP server failed to run");
+    if config.once {
+        // The DNS server blocks on its socket, so aborting its task would not let the runtime
+        // shut down; exit the process directly instead:
+        log::info!("Single reception done; exiting.");
+        std::process::exit(0);
+    }
+    dns_handle.await.expect("The DNS server failed to run");
 
     // The app will actually terminate when it is killed, e.g., with Ctrl+C.
 
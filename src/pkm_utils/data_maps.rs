@@ -17,10 +17,15 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 use bimap::{BiHashMap, BiMap};
+use serde::de::DeserializeOwned;
 use serde_json::from_str;
 use std::{collections::HashMap, fs::read_to_string, sync::LazyLock};
 
-use crate::gts::Geonet;
+use crate::{
+    error::{GtsError, Result},
+    gts::Geonet,
+    legality::GameAvailability,
+};
 
 /// Character map for Gen4 and Gen5 Pokémon games, for character encoding.
 ///
@@ -42,7 +47,7 @@ pub static CHARMAP: LazyLock<BiMap<u16, char>> = LazyLock::new(|| {
     for (id, character) in charmap.iter() {
         // Convert the character from String to char:
         character_map.insert(
-            u16::from_str_radix(id, 16).expect(format!("Invalid u16 number: {}", id).as_str()),
+            u16::from_str_radix(id, 16).unwrap_or_else(|_| panic!("Invalid u16 number: {}", id)),
             character.chars().collect::<Vec<char>>()[0],
         );
     }
@@ -69,13 +74,7 @@ pub static NATURES: LazyLock<BiMap<u16, String>> = LazyLock::new(|| {
 });
 
 pub static NATURE_MODIFIERS: LazyLock<Vec<[f32; 5]>> = LazyLock::new(|| {
-    // Read from file and cast:
-    from_str::<Vec<[f32; 5]>>(
-        read_to_string("data/nature_modifiers.json")
-            .expect("Failed to read nature_modifiers.json file")
-            .as_str(),
-    )
-    .expect("Couldn't parse nature_modifiers.json as valid JSON for a `Vec<[f32; 5]>>`")
+    read_table::<f32, 5>("nature_modifiers.json", Some(25)).unwrap_or_else(|e| panic!("{}", e))
 });
 
 pub static SPECIES: LazyLock<BiMap<u16, String>> = LazyLock::new(|| {
@@ -96,6 +95,23 @@ pub static SPECIES: LazyLock<BiMap<u16, String>> = LazyLock::new(|| {
     species_map
 });
 
+/// Default names the games give to each species, as lists indexed by species ID, keyed by the
+/// language name (e.g., "English").
+///
+/// Only the English names are included for now.
+pub static SPECIES_DEFAULT_NAMES: LazyLock<HashMap<String, Vec<String>>> = LazyLock::new(|| {
+    // Read from file and cast:
+    from_str::<HashMap<String, Vec<String>>>(
+        read_to_string("data/species_default_names.json")
+            .expect("Failed to read species_default_names.json file")
+            .as_str(),
+    )
+    .expect(
+        "Couldn't parse species_default_names.json as valid JSON for a \
+        `HashMap<String, Vec<String>>`",
+    )
+});
+
 pub static ITEMS_GEN4: LazyLock<BiMap<u16, String>> = LazyLock::new(|| {
     // Read from file and cast:
     let item_names = from_str::<Vec<String>>(
@@ -160,6 +176,17 @@ pub static MOVES: LazyLock<Vec<String>> = LazyLock::new(|| {
     .expect("Couldn't parse moves.json as valid JSON for a `Vec<String>>`")
 });
 
+/// Base PP of each move, indexed by move ID, as in Gen 5. The empty move has 0 PP.
+pub static MOVE_BASE_PP: LazyLock<Vec<u8>> = LazyLock::new(|| {
+    // Read from file and cast:
+    from_str::<Vec<u8>>(
+        read_to_string("data/move_pp.json")
+            .expect("Failed to read move_pp.json file")
+            .as_str(),
+    )
+    .expect("Couldn't parse move_pp.json as valid JSON for a `Vec<u8>`")
+});
+
 pub static HIDDEN_POWERS: LazyLock<Vec<String>> = LazyLock::new(|| {
     // Read from file and cast:
     from_str::<Vec<String>>(
@@ -171,25 +198,55 @@ pub static HIDDEN_POWERS: LazyLock<Vec<String>> = LazyLock::new(|| {
 });
 
 pub static BASE_STATS: LazyLock<Vec<[u8; 7]>> = LazyLock::new(|| {
+    read_table::<u8, 7>("base_stats.json", None).unwrap_or_else(|e| panic!("{}", e))
+});
+
+/// Gender thresholds of each species, indexed by species ID.
+///
+/// A Pokémon is female if the lowest byte of its PID is lower than the threshold, and male
+/// otherwise. The special values 0, 254, and 255 stand for male-only, female-only, and genderless
+/// species, respectively.
+pub static GENDER_RATIOS: LazyLock<Vec<u8>> = LazyLock::new(|| {
     // Read from file and cast:
-    from_str::<Vec<[u8; 7]>>(
-        read_to_string("data/base_stats.json")
-            .expect("Failed to read base_stats.json file")
+    from_str::<Vec<u8>>(
+        read_to_string("data/gender_ratios.json")
+            .expect("Failed to read gender_ratios.json file")
             .as_str(),
     )
-    .expect("Couldn't parse base_stats.json as valid JSON for a `Vec<[u8; 7]>>`")
+    .expect("Couldn't parse gender_ratios.json as valid JSON for a `Vec<u8>`")
+});
+
+/// Abilities of each species, as triples of ability IDs indexed by National Pokédex number: the
+/// first and second regular abilities, and the hidden ability.
+///
+/// The second and hidden abilities are 0 ("None") for species without them. Hidden abilities are
+/// only obtainable in Gen 5.
+pub static SPECIES_ABILITIES: LazyLock<Vec<[u16; 3]>> = LazyLock::new(|| {
+    read_table::<u16, 3>("abilities_by_species.json", None).unwrap_or_else(|e| panic!("{}", e))
+});
+
+/// Species that evolve when traded, as triples of the species ID, the item ID it must hold (0 if
+/// none), and the ID of the species it evolves into.
+///
+/// Karrablast and Shelmet are not included, as they only evolve when traded for each other.
+pub static TRADE_EVOLUTIONS: LazyLock<Vec<[u16; 3]>> = LazyLock::new(|| {
+    read_table::<u16, 3>("trade_evolutions.json", None).unwrap_or_else(|e| panic!("{}", e))
+});
+
+/// Type effectiveness chart of Gen 4 and Gen 5, indexed by the attacking type ID and then by the
+/// defending type ID (see `Type`).
+pub static TYPE_CHART: LazyLock<[[f32; 17]; 17]> = LazyLock::new(|| {
+    read_table::<f32, 17>("type_chart.json", Some(17))
+        .unwrap_or_else(|e| panic!("{}", e))
+        .try_into()
+        .expect("The shape of type_chart.json was checked when reading it")
 });
 
 pub static LEVEL_CURVES: LazyLock<[[u32; 6]; 101]> = LazyLock::new(|| {
-    // Read from file and cast:
-    from_str::<Vec<[u32; 6]>>(
-        read_to_string("data/level_curves.json")
-            .expect("Failed to read level_curves.json file")
-            .as_str(),
-    )
-    .expect("Couldn't parse level_curves.json as valid JSON for a `Vec<[u8; 6]>>`")
-    .try_into()
-    .expect("Couldn't convert level_curves.json to a fixed-size array of 101 elements")
+    read_table::<u32, 6>("level_curves.json", Some(101))
+        .unwrap_or_else(|e| panic!("{}", e))
+        .try_into()
+        .expect("The shape of level_curves.json was checked when reading it")
 });
 
 pub static GEONET_GEN5: LazyLock<Geonet> = LazyLock::new(|| {
@@ -201,3 +258,240 @@ pub static GEONET_GEN5: LazyLock<Geonet> = LazyLock::new(|| {
     )
     .expect("Couldn't parse geonet_gen5.json as valid JSON for a `Geonet`")
 });
+
+pub static GAME_AVAILABILITY: LazyLock<HashMap<String, GameAvailability>> = LazyLock::new(|| {
+    // Read from file and cast:
+    from_str::<HashMap<String, GameAvailability>>(
+        read_to_string("data/game_availability.json")
+            .expect("Failed to read game_availability.json file")
+            .as_str(),
+    )
+    .expect(
+        "Couldn't parse game_availability.json as valid JSON for a \
+        `HashMap<String, GameAvailability>`",
+    )
+});
+
+/// Gets the Gen 5 ID of a Gen 4 item, matching the item names of both generations.
+///
+/// # Arguments
+/// * `id` - The Gen 4 ID of the item.
+///
+/// Returns `None` if the ID is not a valid Gen 4 item ID, or if the item does not exist in Gen 5
+/// (e.g., Gen 4 mail).
+pub fn item_gen4_to_gen5(id: u16) -> Option<u16> {
+    convert_item_id(id, &ITEMS_GEN4, &ITEMS_GEN5)
+}
+
+/// Gets the Gen 4 ID of a Gen 5 item, matching the item names of both generations.
+///
+/// # Arguments
+/// * `id` - The Gen 5 ID of the item.
+///
+/// Returns `None` if the ID is not a valid Gen 5 item ID, or if the item does not exist in Gen 4
+/// (e.g., Gems, or Gen 5 mail).
+pub fn item_gen5_to_gen4(id: u16) -> Option<u16> {
+    convert_item_id(id, &ITEMS_GEN5, &ITEMS_GEN4)
+}
+
+/// Gets the ID of an item in another generation's item map, matching the item names.
+///
+/// The names are compared loosely, as the spelling of some items differs between the item tables
+/// of each generation (e.g., "Poke Ball" and "Poké Ball"). The placeholder names of unused IDs
+/// never match.
+///
+/// # Arguments
+/// * `id` - The ID of the item in the source item map.
+/// * `from` - The item map of the source generation.
+/// * `to` - The item map of the target generation.
+fn convert_item_id(id: u16, from: &BiMap<u16, String>, to: &BiMap<u16, String>) -> Option<u16> {
+    let key = item_name_key(from.get_by_left(&id)?);
+    if key == "???" || key == "unknown" {
+        return None;
+    }
+
+    to.iter()
+        .filter(|(_, name)| item_name_key(name) == key)
+        .map(|(&id, _)| id)
+        .min()
+}
+
+/// Normalizes an item name for comparisons across the item tables of each generation.
+fn item_name_key(name: &str) -> String {
+    name.to_lowercase().replace('é', "e").replace('"', "'")
+}
+
+/// Loads all the data maps eagerly.
+///
+/// The data maps are otherwise loaded from their files the first time they are used, which would
+/// slow down the first request handled by a server. Calling this on startup avoids it.
+///
+/// The loaded maps are then checked to hold every ID looked up in them with `should_be_some!`
+/// (e.g., a gender ratio for every species), as a failed lookup there is undefined behavior in
+/// release builds. A data file that does not match the others is so reported on startup.
+///
+/// Returns `GtsError::InvalidFile` describing the problem if a map lacks any of those IDs. Panics
+/// if a data file cannot be read or parsed, or if a table has the wrong shape (naming the row at
+/// fault), as the maps would do when first used.
+pub fn preload() -> Result<()> {
+    LazyLock::force(&CHARMAP);
+    LazyLock::force(&NATURES);
+    LazyLock::force(&NATURE_MODIFIERS);
+    LazyLock::force(&SPECIES);
+    LazyLock::force(&SPECIES_DEFAULT_NAMES);
+    LazyLock::force(&ITEMS_GEN4);
+    LazyLock::force(&ITEMS_GEN5);
+    LazyLock::force(&ABILITIES);
+    LazyLock::force(&MOVES);
+    LazyLock::force(&MOVE_BASE_PP);
+    LazyLock::force(&HIDDEN_POWERS);
+    LazyLock::force(&BASE_STATS);
+    LazyLock::force(&GENDER_RATIOS);
+    LazyLock::force(&SPECIES_ABILITIES);
+    LazyLock::force(&TRADE_EVOLUTIONS);
+    LazyLock::force(&LEVEL_CURVES);
+    LazyLock::force(&TYPE_CHART);
+    LazyLock::force(&GEONET_GEN5);
+    LazyLock::force(&GAME_AVAILABILITY);
+
+    check_len("natures.json", NATURES.len(), NATURE_MODIFIERS.len())?;
+    check_len("gender_ratios.json", GENDER_RATIOS.len(), SPECIES.len())?;
+    check_len(
+        "abilities_by_species.json",
+        SPECIES_ABILITIES.len(),
+        SPECIES.len(),
+    )?;
+    check_len("move_pp.json", MOVE_BASE_PP.len(), MOVES.len())?;
+    check_len("hidden_power.json", HIDDEN_POWERS.len(), 16)?;
+    if let Some(id) = SPECIES_ABILITIES
+        .iter()
+        .flatten()
+        .find(|id| !ABILITIES.contains_left(id))
+    {
+        return Err(GtsError::InvalidFile(format!(
+            "abilities_by_species.json has the ability ID {}, missing from abilities.json",
+            id
+        )));
+    }
+    if let Some([_, _, id]) = TRADE_EVOLUTIONS
+        .iter()
+        .find(|[_, _, evolution]| !SPECIES.contains_left(evolution))
+    {
+        return Err(GtsError::InvalidFile(format!(
+            "trade_evolutions.json has the species ID {}, missing from species.json",
+            id
+        )));
+    }
+    if MOVES.is_empty() || !MOVES.iter().any(|name| name == "Struggle") {
+        return Err(GtsError::InvalidFile(
+            "moves.json lacks the empty move or Struggle".to_string(),
+        ));
+    }
+    for (file_name, items) in [("items.json", &ITEMS_GEN4), ("itemsg5.json", &ITEMS_GEN5)] {
+        if !items.contains_left(&0) {
+            return Err(GtsError::InvalidFile(format!(
+                "{} lacks the empty item (ID 0)",
+                file_name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the length of a loaded data file against the one it must have.
+///
+/// # Arguments
+/// * `file_name` - The name of the file, in the `data/` directory.
+/// * `len` - The number of elements loaded from the file.
+/// * `expected` - The number of elements the file must have.
+///
+/// Returns `GtsError::InvalidFile` describing the problem if the lengths differ.
+fn check_len(file_name: &str, len: usize, expected: usize) -> Result<()> {
+    if len != expected {
+        return Err(GtsError::InvalidFile(format!(
+            "{} has {} elements, expected {}",
+            file_name, len, expected
+        )));
+    }
+
+    Ok(())
+}
+
+/// Reads a data file with a table of fixed-length rows, checking its shape.
+///
+/// # Arguments
+/// * `file_name` - The name of the file, in the `data/` directory.
+/// * `rows` - The number of rows the table must have, if it is fixed.
+///
+/// Returns `GtsError::InvalidFile` describing the problem if the file cannot be read or parsed,
+/// if it does not have the expected number of rows, or if any row does not have `N` elements.
+fn read_table<T: DeserializeOwned, const N: usize>(
+    file_name: &str,
+    rows: Option<usize>,
+) -> Result<Vec<[T; N]>> {
+    let contents = read_to_string(format!("data/{}", file_name))
+        .map_err(|e| GtsError::InvalidFile(format!("Failed to read {}: {}", file_name, e)))?;
+    let table = from_str::<Vec<Vec<T>>>(&contents).map_err(|e| {
+        GtsError::InvalidFile(format!("Couldn't parse {} as a table: {}", file_name, e))
+    })?;
+
+    if let Some(rows) = rows.filter(|&rows| rows != table.len()) {
+        return Err(GtsError::InvalidFile(format!(
+            "{} has {} rows, expected {}",
+            file_name,
+            table.len(),
+            rows
+        )));
+    }
+
+    table
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let len = row.len();
+            row.try_into().map_err(|_| {
+                GtsError::InvalidFile(format!(
+                    "{} row {} has {} elements, expected {}",
+                    file_name, i, len, N
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_ids_are_matched_by_name() {
+        // Same item, spelled differently:
+        assert_eq!(item_gen4_to_gen5(4), Some(4));
+        assert_eq!(item_gen5_to_gen4(4), Some(4));
+        // Same item, different IDs:
+        assert_eq!(item_gen4_to_gen5(486), Some(487));
+        // Gen 4 mail, and a Gen 5 Gem:
+        assert_eq!(item_gen4_to_gen5(137), None);
+        assert_eq!(item_gen5_to_gen4(548), None);
+    }
+
+    #[test]
+    fn the_data_files_match_each_other() {
+        preload().unwrap();
+
+        let result = check_len("gender_ratios.json", 649, SPECIES.len());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            GtsError::InvalidFile("gender_ratios.json has 649 elements, expected 650".to_string())
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn every_move_has_a_base_pp() {
+        assert_eq!(MOVE_BASE_PP.len(), MOVES.len());
+        assert_eq!(MOVE_BASE_PP[0], 0);
+        assert!(MOVE_BASE_PP[1..].iter().all(|&pp| pp > 0));
+    }
+}
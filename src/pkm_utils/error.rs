@@ -0,0 +1,90 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Error type used throughout the library.
+
+use std::{fmt, io};
+
+/// Result type returned by the fallible functions of the library.
+pub type Result<T> = std::result::Result<T, GtsError>;
+
+/// Enum that identifies the different errors that can happen when handling Pokémon and GTS data.
+#[derive(Debug)]
+pub enum GtsError {
+    /// An I/O error, e.g., when reading or writing Pokémon files.
+    Io(io::Error),
+    /// A Pokémon or Trainer name that is not valid, e.g., because it is too long.
+    InvalidName(String),
+    /// A species ID that is not valid.
+    InvalidSpecies(u16),
+    /// A value that is out of the valid range for the field it is set to.
+    InvalidValue(String),
+    /// A file that is not a valid Pokémon file.
+    InvalidFile(String),
+    /// Data that could not be encoded into the games' internal representation.
+    Encode(String),
+    /// Data that could not be decoded from the games' internal representation.
+    Decode(String),
+    /// An operation that is not supported for the given data, e.g., converting a Gen 5-exclusive
+    /// Pokémon to Gen 4.
+    Unsupported(String),
+}
+
+impl fmt::Display for GtsError {
+    /// Formats the `GtsError` as a printed string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GtsError::Io(e) => write!(f, "I/O error: {}", e),
+            GtsError::InvalidName(msg) => write!(f, "Invalid name: {}", msg),
+            GtsError::InvalidSpecies(id) => write!(f, "Invalid species ID: {}", id),
+            GtsError::InvalidValue(msg) => write!(f, "Invalid value: {}", msg),
+            GtsError::InvalidFile(msg) => write!(f, "Invalid file: {}", msg),
+            GtsError::Encode(msg) => write!(f, "Encoding error: {}", msg),
+            GtsError::Decode(msg) => write!(f, "Decoding error: {}", msg),
+            GtsError::Unsupported(msg) => write!(f, "Unsupported operation: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for GtsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GtsError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GtsError {
+    fn from(error: io::Error) -> Self {
+        GtsError::Io(error)
+    }
+}
+
+impl From<GtsError> for io::Error {
+    /// Converts the `GtsError` into an `io::Error`, for interoperability with I/O code.
+    ///
+    /// I/O errors are unwrapped; every other error is converted to an error of kind
+    /// `InvalidData`.
+    fn from(error: GtsError) -> Self {
+        match error {
+            GtsError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Pokémon fixtures shared by the unit tests.
+//!
+//! The data is kept in `tests/fixtures`, apart from the fuzzing seeds, so that changing the seeds
+//! does not change what the tests check.
+use crate::pokemon::Pokemon;
+
+/// Enum that identifies the Pokémon data files available to the tests.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Fixture {
+    /// A Gen 4 party Turtwig, with no experience and a level byte of 0.
+    Gen4Party,
+    /// A Gen 4 boxed Pokémon, without battle stats.
+    Gen4Boxed,
+    /// A Gen 5 party Snivy.
+    Gen5Party,
+}
+
+impl Fixture {
+    /// Gets the decrypted data of the fixture.
+    pub(crate) fn bytes(self) -> &'static [u8] {
+        match self {
+            Fixture::Gen4Party => include_bytes!("../../tests/fixtures/gen4_party.pk4"),
+            Fixture::Gen4Boxed => include_bytes!("../../tests/fixtures/gen4_boxed.pk4"),
+            Fixture::Gen5Party => include_bytes!("../../tests/fixtures/gen5_party.pk5"),
+        }
+    }
+
+    /// Gets the Pokémon of the fixture.
+    pub(crate) fn pokemon(self) -> Pokemon {
+        Pokemon::try_deserialize(self.bytes()).unwrap()
+    }
+}
@@ -20,16 +20,17 @@
 // WARNING: That documentation is at times somewhat unclear.
 use base64::{engine::general_purpose::URL_SAFE as URL_SAFE_B64, Engine as _};
 use chrono::{DateTime, Local as LocalTime, NaiveDateTime};
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashMap,
-    io::{Error, ErrorKind, Result},
-};
+use std::collections::HashMap;
 
 use crate::{
-    data_maps::GEONET_GEN5, internal_types::*, pokemon::Pokemon, should_be_ok, should_be_some,
+    data_maps::GEONET_GEN5,
+    error::{GtsError, Result},
+    internal_types::*,
+    pokemon::{Pokemon, GEN4_PKM_LEN, GEN5_PKM_LEN, MAX_TRAINER_NAME_CHARS},
+    should_be_ok, should_be_some,
 };
 
 /// Struct representing a location for a Pokémon in the GTS.
@@ -41,6 +42,34 @@ pub struct Geonet {
     states: HashMap<String, Vec<String>>,
 }
 
+// Information on real-world locations extracted from:
+// https://bulbapedia.bulbagarden.net/wiki/Pok%C3%A9mon_world_in_relation_to_the_real_world
+/// Location (country, region) of the trainers of Gen 4 receptions: Twinleaf Town's closest match.
+const GEN4_RECEPTION_LOCATION: (&str, &str) = ("Japan", "Hokkaido (Sapporo)");
+/// Location (country, region) of the trainers of Black and White receptions: Nuvema Town's closest
+/// match.
+const BW_RECEPTION_LOCATION: (&str, &str) = ("United States of America", "New York");
+/// Location (country, region) of the trainers of Black 2 and White 2 receptions: Aspertia City's
+/// closest match.
+const B2W2_RECEPTION_LOCATION: (&str, &str) = ("United States of America", "New Jersey");
+
+/// Gets the names of all the countries of the GTS, ordered by their codes.
+pub fn countries() -> Vec<String> {
+    GEONET_GEN5.countries.clone()
+}
+
+/// Gets the names of all the regions of a country of the GTS, ordered by their codes.
+///
+/// Countries without regions have a single region, whose name is an empty string.
+///
+/// Returns `None` if the country name is invalid.
+///
+/// # Arguments
+/// * `country` - The name of the country to get the regions of.
+pub fn regions(country: &str) -> Option<Vec<String>> {
+    GEONET_GEN5.states.get(country).cloned()
+}
+
 /// Gets the country and state names, for the respective country and state codes.
 ///
 /// The state might be an empty string.
@@ -80,8 +109,16 @@ pub fn country_code(country_name: &String, state_name: &String) -> Option<(u8, u
     Some((country_index as u8, state_index as u8))
 }
 
+/// Length of the GTS data sent alongside the Pokémon in Gen 4 receptions.
+const GEN4_GTS_DATA_LEN: usize = 0x38;
+/// Length of the GTS data sent alongside the Pokémon in Gen 5 receptions.
+const GEN5_GTS_DATA_LEN: usize = 0x3C;
+/// Length of the padding between the Pokémon and the GTS data in Gen 5 receptions.
+const GEN5_RECEPTION_PADDING: usize = 0x10;
+
 /// Struct representing the extra data sent from the GTS servers in Pokémon receptions, alongside
 /// the Pokémon itself.
+#[derive(Debug, PartialEq)]
 pub struct GTSData {
     pkm_id: u16,
     gender: Gender,
@@ -114,11 +151,9 @@ impl GTSData {
     ///
     /// # Arguments
     /// * `pokemon` - The Pokémon to construct the GTS data from.
-    fn from_pokemon(pokemon: &Pokemon) -> Self {
-        // Create RNG object for generating some fields for which I cannot think of any other more
-        // creative way of generating lol:
-        let mut rng = SmallRng::from_rng(&mut rand::rng());
-
+    /// * `rng` - The random number generator for the fields that cannot be derived from the
+    ///   Pokémon (the profile ID and the requested species).
+    fn from_pokemon(pokemon: &Pokemon, rng: &mut impl Rng) -> Self {
         // Generate random GTS PID:
         let profile_id = rng.random();
 
@@ -134,25 +169,14 @@ impl GTSData {
         let trainer_secret_id = Some(pokemon.trainer_secret_id);
         let trainer_name = pokemon.trainer_name().clone();
         let game = pokemon.origin_game;
-        let country = if !pokemon.is_gen5() {
-            "Japan".to_string()
+        let (country, region) = if !pokemon.is_gen5() {
+            GEN4_RECEPTION_LOCATION
+        } else if game == Game::White2 || game == Game::Black2 {
+            B2W2_RECEPTION_LOCATION
         } else {
-            "United States of America".to_string()
-        };
-        let region = if !pokemon.is_gen5() {
-            // Twinleaf Town's closest match:
-            "Hokkaido (Sapporo)".to_string()
-        } else {
-            if game == Game::White2 || game == Game::Black2 {
-                // Asperia City's closest match:
-                "New Jersey".to_string()
-            } else {
-                // Nuvema Town's closest match:
-                "New York".to_string()
-            }
-            // Information on real-world locations extracted from:
-            // https://bulbapedia.bulbagarden.net/wiki/Pok%C3%A9mon_world_in_relation_to_the_real_world
+            BW_RECEPTION_LOCATION
         };
+        let (country, region) = (country.to_string(), region.to_string());
         let trainer_class = should_be_ok!(
             TrainerClass::try_from(
                 ((trainer_id ^ pokemon.trainer_secret_id) as u8) % TrainerClass::COUNT
@@ -200,18 +224,39 @@ impl GTSData {
         // Unknown data (offsets):
         // 0x09
         // 0x0B
-        // [Gen 5] 0x3A - 0x3B?
+        // [Gen 5] 0x3A
+    }
+
+    /// Creates a builder for `GTSData`, to set every field by hand instead of deriving them from a
+    /// Pokémon. See `GTSDataBuilder`.
+    pub fn builder() -> GTSDataBuilder {
+        GTSDataBuilder::new()
+    }
+
+    /// Gets the length of the serialized GTS data of a generation.
+    ///
+    /// # Arguments
+    /// * `is_gen5` - Whether the data is from a Gen 5 GTS reception or not.
+    fn serialized_len(is_gen5: bool) -> usize {
+        if !is_gen5 {
+            GEN4_GTS_DATA_LEN
+        } else {
+            GEN5_GTS_DATA_LEN
+        }
     }
 
     /// Serializes the GTS data into a vector of bytes.
     ///
+    /// If the country or region cannot be found in the geonet, a warning is logged and they are
+    /// serialized as 0 (no location).
+    ///
     /// # Arguments
     /// * `is_gen5` - Whether the data is from a Gen 5 GTS reception or not.
-    fn serialize(&self, is_gen5: bool) -> Vec<u8> {
-        let mut data = vec![0; if !is_gen5 { 0x38 } else { 0x3C }];
+    pub fn serialize(&self, is_gen5: bool) -> Vec<u8> {
+        let mut data = vec![0; Self::serialized_len(is_gen5)];
 
         data[0x00..0x02].copy_from_slice(&self.pkm_id.to_le_bytes());
-        data[0x02] = self.gender as u8;
+        data[0x02] = self.gender as u8 + 1;
         data[0x03] = self.lvl;
         data[0x04..0x06].copy_from_slice(&self.req_pkm_id.to_le_bytes());
         data[0x06] = self.req_gender as u8 + 1;
@@ -242,12 +287,17 @@ impl GTSData {
             data[0x24..0x34].copy_from_slice(&encoded_name);
         }
         let extra_offset = if is_gen5 { 2 } else { 0 };
-        let (country, region) = should_be_some!(
-            country_code(&self.country, &self.region),
-            "Invalid country or region: {} {}",
-            self.country,
-            self.region
-        );
+        // The location usually comes from the hardcoded reception defaults, so it should always
+        // exist in the geonet; if the geonet data was edited, send no location rather than failing
+        // in the middle of a trade.
+        let (country, region) = country_code(&self.country, &self.region).unwrap_or_else(|| {
+            log::warn!(
+                "Country or region not in the geonet: {} {}; sending no location",
+                self.country,
+                self.region
+            );
+            (0, 0)
+        });
         data[0x32 + extra_offset] = country;
         data[0x33 + extra_offset] = region;
         data[0x34 + extra_offset] = self.trainer_class as u8;
@@ -264,72 +314,57 @@ impl GTSData {
         data
     }
 
-    // This was intended to be used for client deposits, but then I realized the data send in those
-    // is not exacty the same as the one managed here.
-    #[allow(dead_code)]
-    /// Deserializes the GTS data from a byte slice.
+    /// Deserializes the GTS data sent alongside the Pokémon in a GTS reception.
     ///
-    /// This is useful when attempting to send a Pokémon to a Gen 4 or Gen 5 game using the GTS
-    /// services.
+    /// The deposited and traded times are replaced by the current time if they are out of range.
     ///
     /// # Arguments
-    /// * `data` - The byte slice containing the GTS data to deserialize.
+    /// * `data` - The serialized GTS data, as returned by `serialize`.
     /// * `is_gen5` - Whether the data is from a Gen 5 GTS reception.
-    fn deserialize(data: &[u8], is_gen5: bool) -> Self {
-        if !is_gen5 {
-            assert!(
-                data.len() == 0x124,
-                "Invalid GTS data length for Gen 4: {}",
-                data.len()
-            );
-        } else {
-            assert!(
-                data.len() == 0x128,
-                "Invalid GTS data length for Gen 5: {}",
-                data.len()
-            );
+    ///
+    /// Returns `GtsError::Decode` if the data does not have the length of the generation's GTS
+    /// data, or if any field has an invalid value.
+    fn deserialize(data: &[u8], is_gen5: bool) -> Result<Self> {
+        let expected_len = Self::serialized_len(is_gen5);
+        if data.len() != expected_len {
+            return Err(GtsError::Decode(format!(
+                "Invalid GTS data length: {} bytes, expected {}",
+                data.len(),
+                expected_len
+            )));
         }
+        let invalid = |field: &str, value: u8| {
+            GtsError::Decode(format!("Invalid {} in GTS data: {}", field, value))
+        };
+        // Genders are stored plus one, except the trainer's:
+        let stored_gender = |field: &str, value: u8| {
+            value
+                .checked_sub(1)
+                .and_then(|gender| Gender::try_from(gender).ok())
+                .ok_or_else(|| invalid(field, value))
+        };
+        let timestamp = |bytes: &[u8]| {
+            let seconds = i64::from_be_bytes(
+                bytes
+                    .try_into()
+                    .expect("Failed to convert timestamp slice to array"),
+            );
+            DateTime::from_timestamp(seconds, 0)
+                .unwrap_or(LocalTime::now().to_utc())
+                .naive_utc()
+        };
 
         let pkm_id = u16::from_le_bytes([data[0x00], data[0x01]]);
-        let gender = should_be_ok!(
-            Gender::try_from(data[0x02] - 1),
-            "Invalid gender: {}",
-            data[0x02]
-        );
+        let gender = stored_gender("gender", data[0x02])?;
         let lvl = data[0x03];
         let req_pkm_id = u16::from_le_bytes([data[0x04], data[0x05]]);
-        let req_gender = should_be_ok!(
-            Gender::try_from(data[0x06] - 1),
-            "Invalid requested gender: {}",
-            data[0x06]
-        );
+        let req_gender = stored_gender("requested gender", data[0x06])?;
         let req_min_lvl = data[0x07];
         let req_max_lvl = data[0x08];
-        let trainer_gender = should_be_ok!(
-            Gender::try_from(data[0x0A]),
-            "Invalid trainer gender: {}",
-            data[0x0A]
-        );
-        let deposited_time = DateTime::from_timestamp(
-            i64::from_be_bytes(
-                data[0x0C..0x14]
-                    .try_into()
-                    .expect("Failed to convert deposited time slice to array"),
-            ),
-            0,
-        )
-        .unwrap_or(LocalTime::now().to_utc())
-        .naive_utc();
-        let traded_time = DateTime::from_timestamp(
-            i64::from_be_bytes(
-                data[0x14..0x1C]
-                    .try_into()
-                    .expect("Failed to convert traded time slice to array"),
-            ),
-            0,
-        )
-        .unwrap_or(LocalTime::now().to_utc())
-        .naive_utc();
+        let trainer_gender =
+            Gender::try_from(data[0x0A]).map_err(|_| invalid("trainer gender", data[0x0A]))?;
+        let deposited_time = timestamp(&data[0x0C..0x14]);
+        let traded_time = timestamp(&data[0x14..0x1C]);
         let profile_id = u32::from_le_bytes(
             data[0x1C..0x20]
                 .try_into()
@@ -346,42 +381,28 @@ impl GTSData {
             None
         };
         let trainer_name = if !is_gen5 {
-            should_be_ok!(
-                Pokemon::decode_name_gen4(&data[0x20..0x30]),
-                "Failed to decode Gen 4 trainer name from GTS data"
-            )
+            Pokemon::decode_name_gen4(&data[0x20..0x30])?
         } else {
-            should_be_ok!(
-                Pokemon::decode_name_gen5(&data[0x24..0x34]),
-                "Failed to decode Gen 5 trainer name from GTS data"
-            )
+            Pokemon::decode_name_gen5(&data[0x24..0x34])?
         };
         let extra_offset = if is_gen5 { 2 } else { 0 };
-        let (country, region) = should_be_some!(
-            country(data[0x32 + extra_offset], data[0x33 + extra_offset]),
-            "Invalid country or region code: {} {}",
-            data[0x32 + extra_offset],
-            data[0x33 + extra_offset]
-        );
-        let trainer_class = should_be_ok!(
-            TrainerClass::try_from(data[0x34 + extra_offset]),
-            "Invalid trainer class ID: {}",
-            data[0x34 + extra_offset]
-        );
+        let (country_code, region_code) = (data[0x32 + extra_offset], data[0x33 + extra_offset]);
+        let (country, region) = country(country_code, region_code).ok_or_else(|| {
+            GtsError::Decode(format!(
+                "Invalid country or region code in GTS data: {} {}",
+                country_code, region_code
+            ))
+        })?;
+        let trainer_class = TrainerClass::try_from(data[0x34 + extra_offset])
+            .map_err(|_| invalid("trainer class ID", data[0x34 + extra_offset]))?;
         let is_exchanged = data[0x35 + extra_offset] != 0;
-        let game = should_be_ok!(
-            data[0x36 + extra_offset].try_into(),
-            "Invalid game ID: {}",
-            data[0x36 + extra_offset]
-        );
-        let language = should_be_ok!(
-            Language::try_from(data[0x37 + extra_offset]),
-            "Invalid language ID: {}",
-            data[0x37 + extra_offset]
-        );
+        let game = Game::try_from(data[0x36 + extra_offset])
+            .map_err(|_| invalid("game ID", data[0x36 + extra_offset]))?;
+        let language = Language::try_from(data[0x37 + extra_offset])
+            .map_err(|_| invalid("language ID", data[0x37 + extra_offset]))?;
         let unity_tower_floors = if is_gen5 { Some(data[0x3B]) } else { None };
 
-        Self {
+        Ok(Self {
             pkm_id,
             gender,
             lvl,
@@ -403,10 +424,178 @@ impl GTSData {
             game,
             language,
             unity_tower_floors,
+        })
+    }
+}
+
+/// Macro to generate the setters of `GTSDataBuilder`, one per field of `GTSData`.
+///
+/// # Arguments
+/// * `$field` - The name of the field, which is also the name of the setter.
+/// * `$type` - The type of the field.
+/// * `$doc` - The description of the field, for the setter's documentation.
+macro_rules! gts_data_setters {
+    ($($field:ident: $type:ty => $doc:literal),* $(,)?) => {
+        $(
+            #[doc = concat!("Sets ", $doc, ".")]
+            pub fn $field(mut self, $field: $type) -> Self {
+                self.data.$field = $field;
+                self
+            }
+        )*
+    };
+}
+
+/// Builder of `GTSData`, to craft the metadata of a GTS reception independently of a Pokémon.
+///
+/// Every field starts with a default value, and can be set with the setter of the same name. The
+/// fields are validated when building the data. See `GTSDataBuilder::build`.
+pub struct GTSDataBuilder {
+    data: GTSData,
+}
+
+impl GTSDataBuilder {
+    /// Creates a builder with the default values: a level 1 male Bulbasaur, deposited and traded
+    /// now by a male trainer in Japan, requesting any level 1 to 100 Bulbasaur in exchange.
+    fn new() -> Self {
+        let now = LocalTime::now().naive_utc();
+
+        Self {
+            data: GTSData {
+                pkm_id: 1,
+                gender: Gender::Male,
+                lvl: 1,
+                req_pkm_id: 1,
+                req_gender: Gender::Genderless,
+                req_min_lvl: 1,
+                req_max_lvl: 100,
+                trainer_gender: Gender::Male,
+                deposited_time: now,
+                traded_time: now,
+                profile_id: 0,
+                trainer_id: 0,
+                trainer_secret_id: Some(0),
+                trainer_name: String::new(),
+                country: "Japan".to_string(),
+                region: "Hokkaido (Sapporo)".to_string(),
+                trainer_class: TrainerClass::default(),
+                is_exchanged: true,
+                game: Game::default(),
+                language: Language::default(),
+                unity_tower_floors: Some(0),
+            },
+        }
+    }
+
+    gts_data_setters! {
+        pkm_id: u16 => "the National Pokédex number of the offered Pokémon",
+        gender: Gender => "the gender of the offered Pokémon",
+        lvl: u8 => "the level of the offered Pokémon",
+        req_pkm_id: u16 => "the National Pokédex number of the requested Pokémon",
+        req_gender: Gender => "the gender of the requested Pokémon (`Genderless` for either)",
+        req_min_lvl: u8 => "the minimum level of the requested Pokémon",
+        req_max_lvl: u8 => "the maximum level of the requested Pokémon",
+        trainer_gender: Gender => "the gender of the trainer",
+        deposited_time: NaiveDateTime => "the time the Pokémon was deposited at",
+        traded_time: NaiveDateTime => "the time the Pokémon was traded at",
+        profile_id: u32 => "the GTS profile ID of the trainer",
+        trainer_id: u16 => "the Trainer ID of the trainer",
+        trainer_name: String => "the name of the trainer",
+        country: String => "the name of the country of the trainer",
+        region: String => "the name of the region of the trainer, or an empty string if none",
+        trainer_class: TrainerClass => "the trainer class shown in the game",
+        is_exchanged: bool => "whether the Pokémon was already exchanged",
+        game: Game => "the game the trainer deposited the Pokémon from",
+        language: Language => "the language of the trainer's game",
+    }
+
+    /// Sets the Trainer Secret ID of the trainer. Only used in Gen 5.
+    pub fn trainer_secret_id(mut self, trainer_secret_id: u16) -> Self {
+        self.data.trainer_secret_id = Some(trainer_secret_id);
+        self
+    }
+
+    /// Sets the number of Unity Tower floors of the trainer. Only used in Gen 5.
+    pub fn unity_tower_floors(mut self, unity_tower_floors: u8) -> Self {
+        self.data.unity_tower_floors = Some(unity_tower_floors);
+        self
+    }
+
+    /// Validates the fields set, and builds the `GTSData`.
+    ///
+    /// # Returns
+    /// Returns Ok(`GTSData`) if all fields are valid, or:
+    /// * `GtsError::InvalidName` if the trainer name is longer than 7 characters.
+    /// * `GtsError::Encode` if the trainer name cannot be encoded for the game's generation.
+    /// * `GtsError::InvalidValue` if the country and region do not resolve to a GTS location (see
+    ///   `country_code`), or the levels are out of the 1 to 100 range, or the requested minimum
+    ///   level is higher than the maximum one.
+    pub fn build(self) -> Result<GTSData> {
+        let data = self.data;
+
+        Pokemon::validate_name(
+            &data.trainer_name,
+            MAX_TRAINER_NAME_CHARS,
+            data.game.generation() == 5,
+            "Trainer",
+        )?;
+
+        if country_code(&data.country, &data.region).is_none() {
+            return Err(GtsError::InvalidValue(format!(
+                "Unknown GTS location: {} {}",
+                data.country, data.region
+            )));
+        }
+
+        if !(1..=100).contains(&data.lvl) {
+            return Err(GtsError::InvalidValue(format!(
+                "Level must be between 1 and 100, got {}",
+                data.lvl
+            )));
+        }
+        validate_requested_levels(data.req_min_lvl, data.req_max_lvl)?;
+
+        Ok(data)
+    }
+}
+
+/// Validates a range of requested levels: `1 <= min_level <= max_level <= 100`.
+///
+/// # Arguments
+/// * `min_level` - The minimum level of the requested Pokémon.
+/// * `max_level` - The maximum level of the requested Pokémon.
+///
+/// Returns `GtsError::InvalidValue` if a level is out of the 1 to 100 range, or the minimum level
+/// is higher than the maximum one.
+fn validate_requested_levels(min_level: u8, max_level: u8) -> Result<()> {
+    for (description, level) in [
+        ("Requested minimum level", min_level),
+        ("Requested maximum level", max_level),
+    ] {
+        if !(1..=100).contains(&level) {
+            return Err(GtsError::InvalidValue(format!(
+                "{} must be between 1 and 100, got {}",
+                description, level
+            )));
         }
     }
+    if min_level > max_level {
+        return Err(GtsError::InvalidValue(format!(
+            "Requested minimum level {} is higher than the maximum level {}",
+            min_level, max_level
+        )));
+    }
+
+    Ok(())
 }
 
+/// Offsets of the GTS data whose meaning is unknown, in both generations.
+const UNKNOWN_GTS_DATA_OFFSETS: [usize; 2] = [0x09, 0x0B];
+/// Offsets of the GTS data whose meaning is unknown, only in Gen 5.
+///
+/// 0x3B is not included, as it holds the Unity Tower floors (see `GTSData::serialize`).
+const UNKNOWN_GTS_DATA_OFFSETS_GEN5: [usize; 1] = [0x3A];
+
 #[allow(dead_code)]
 /// Struct representing a Pokémon deposit to the GTS.
 ///
@@ -420,6 +609,11 @@ pub struct GTSDeposit {
     profile_id: u32,   // Unused field; kept for reference.
     #[get = "pub"]
     pokemon: Pokemon,
+    /// Raw (decrypted) GTS data sent after the Pokémon, kept for research purposes. See
+    /// `self.unknown_gts_data()`.
+    gts_data: Vec<u8>,
+    /// Whether the deposit comes from a Gen 5 game.
+    is_gen5: bool,
     // Ignore everything else received, as it is not useful for this app.
 }
 
@@ -437,19 +631,33 @@ impl GTSDeposit {
     ///
     /// # Returns
     /// Returns Ok(`GTSDeposit`) on correct execution, or an error if the data could not be decoded
-    /// as base64.
+    /// as base64, is too short, or does not contain a valid Pokémon.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "gts_deposit", level = "debug", skip_all, fields(is_gen5))
+    )]
     pub fn from_base64(base64_data: &String, is_gen5: bool) -> Result<Self> {
         // Decode the data:
         let data = match URL_SAFE_B64.decode(base64_data) {
             Ok(decoded) => decoded,
             Err(e) => {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Failed to decode base64 data: {}", e),
-                ));
+                return Err(GtsError::Decode(format!(
+                    "Failed to decode base64 data: {}",
+                    e
+                )));
             }
         };
 
+        // Check the data is long enough to contain the Pokémon:
+        let min_len = if !is_gen5 { 0xF4 } else { 0xE8 };
+        if data.len() < min_len {
+            return Err(GtsError::Decode(format!(
+                "GTS data too short: {} bytes, expected at least {}",
+                data.len(),
+                min_len
+            )));
+        }
+
         // Retrive encryption key:
         let xor_constant = if !is_gen5 { 0x4A3B2C1D } else { 0x2DB842B2 };
         let gts_checksum = u32::from_be_bytes(
@@ -458,9 +666,9 @@ impl GTSDeposit {
                 .expect("Failed to convert GTS checksum slice to array"),
         ) ^ xor_constant;
 
-        // [Gen 4] Decrypt the profile ID and Pokémon data:
+        // [Gen 4] Decrypt the profile ID, Pokémon data, and the GTS data after it:
         let decrypted_data = if !is_gen5 {
-            Self::decrypt_stream_cipher_data(&data[0x04..0xF4], gts_checksum | gts_checksum << 16)
+            Self::decrypt_stream_cipher_data(&data[0x04..], gts_checksum | gts_checksum << 16)
         } else {
             data
         };
@@ -473,17 +681,61 @@ impl GTSDeposit {
         );
         let pkm_offset = if !is_gen5 { 0x04 } else { 0x0C };
         let pkm_end = if !is_gen5 { 0xF0 } else { 0xE8 };
-        let pokemon = Pokemon::deserialize(&Pokemon::to_decrypted_data(
+        let pokemon = Pokemon::try_deserialize(&Pokemon::to_decrypted_data(
             &decrypted_data[pkm_offset..pkm_end],
-        ));
+        ))?;
+        let gts_data = decrypted_data[pkm_end..].to_vec();
 
         Ok(Self {
             gts_checksum,
             profile_id,
             pokemon,
+            gts_data,
+            is_gen5,
         })
     }
 
+    /// Gets the raw bytes at the offsets of the GTS data whose meaning is unknown: 0x09 and 0x0B,
+    /// and 0x3A in Gen 5.
+    ///
+    /// This is meant to help reverse-engineering the GTS protocol, by correlating these bytes with
+    /// known in-game values.
+    ///
+    /// # Returns
+    /// The (offset, byte) pairs, skipping the offsets beyond the end of the received data.
+    pub fn unknown_gts_data(&self) -> Vec<(usize, u8)> {
+        let gen5_offsets: &[usize] = if self.is_gen5 {
+            &UNKNOWN_GTS_DATA_OFFSETS_GEN5
+        } else {
+            &[]
+        };
+
+        UNKNOWN_GTS_DATA_OFFSETS
+            .iter()
+            .chain(gen5_offsets)
+            .filter_map(|&offset| self.gts_data.get(offset).map(|&byte| (offset, byte)))
+            .collect()
+    }
+
+    /// Gets the game the Pokémon was deposited from, e.g., to tell Diamond and Pearl from
+    /// Platinum, or Black and White from Black 2 and White 2.
+    ///
+    /// It is read from the GTS data sent after the Pokémon, which stores the game at the same
+    /// offset as the GTS data of the receptions: 0x36, and 0x38 in Gen 5. This is not the origin
+    /// game of the Pokémon, which can differ (e.g., a Pokémon caught in Diamond, deposited from
+    /// Platinum).
+    ///
+    /// # Returns
+    /// The depositing game, or `None` if the GTS data is too short, or its game byte is not a game
+    /// of the deposit's generation.
+    pub fn game(&self) -> Option<Game> {
+        let offset = if !self.is_gen5 { 0x36 } else { 0x38 };
+        let game = Game::try_from(*self.gts_data.get(offset)?).ok()?;
+        let generation = if !self.is_gen5 { 4 } else { 5 };
+
+        (game.generation() == generation).then_some(game)
+    }
+
     /// Decrypts the received GTS data using a stream cipher algorithm.
     ///
     /// This is intended to be performed for Gen 4 receptions, on the data after the checksum, once
@@ -506,11 +758,142 @@ impl GTSDeposit {
     }
 }
 
+/// Struct representing a search of Pokémon in the GTS.
+///
+/// This struct is used to parse the data received by the GTS when a player looks for Pokémon,
+/// i.e., the filters chosen in the game's search screen.
+#[derive(Clone, Copy, Debug, CopyGetters)]
+pub struct GTSSearch {
+    /// National Pokédex number of the species searched for.
+    #[get_copy = "pub"]
+    species_id: u16,
+    /// Gender searched for, or `None` if either gender is accepted.
+    #[get_copy = "pub"]
+    gender: Option<Gender>,
+    /// Minimum level searched for, or 0 if there is no minimum.
+    #[get_copy = "pub"]
+    min_level: u8,
+    /// Maximum level searched for, or 0 if there is no maximum.
+    #[get_copy = "pub"]
+    max_level: u8,
+    /// Maximum number of results to answer with.
+    #[get_copy = "pub"]
+    max_results: u8,
+    /// Code of the country searched in, or 0 if the search is not limited to a country.
+    #[get_copy = "pub"]
+    country_code: u8,
+}
+
+impl GTSSearch {
+    /// Constructs a `GTSSearch` from the base64-encoded data received from the game.
+    ///
+    /// The search data has the same header as the deposit data (see `GTSDeposit::from_base64`),
+    /// followed by the search filters:
+    /// * 0x00 - 0x01: National Pokédex number of the species.
+    /// * 0x02: Gender (1 for male, 2 for female, 3 for either).
+    /// * 0x03: Minimum level (0 for any).
+    /// * 0x04: Maximum level (0 for any).
+    /// * 0x05: Unknown.
+    /// * 0x06: Maximum number of results.
+    /// * 0x07: \[Optional\] Country code (0 for any).
+    ///
+    /// # Arguments
+    /// * `base64_data` - The base64-encoded data received, as a reference to a String.
+    /// * `is_gen5` - Whether the received data is from a Gen 5 game.
+    ///
+    /// # Returns
+    /// Returns Ok(`GTSSearch`) on correct execution, or an error if the data could not be decoded
+    /// as base64, is too short, or has an invalid gender.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "gts_search", level = "debug", skip_all, fields(is_gen5))
+    )]
+    pub fn from_base64(base64_data: &String, is_gen5: bool) -> Result<Self> {
+        // Decode the data:
+        let data = match URL_SAFE_B64.decode(base64_data) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                return Err(GtsError::Decode(format!(
+                    "Failed to decode base64 data: {}",
+                    e
+                )));
+            }
+        };
+
+        // Check the data is long enough to contain the search filters:
+        let header_len = if !is_gen5 { 0x08 } else { 0x0C };
+        if data.len() < header_len + 0x07 {
+            return Err(GtsError::Decode(format!(
+                "GTS search data too short: {} bytes, expected at least {}",
+                data.len(),
+                header_len + 0x07
+            )));
+        }
+
+        // [Gen 4] Decrypt the data after the checksum:
+        let filters = if !is_gen5 {
+            let gts_checksum = u32::from_be_bytes(
+                data[0x00..0x04]
+                    .try_into()
+                    .expect("Failed to convert GTS checksum slice to array"),
+            ) ^ 0x4A3B2C1D;
+            GTSDeposit::decrypt_stream_cipher_data(&data[0x04..], gts_checksum | gts_checksum << 16)
+                [0x04..]
+                .to_vec()
+        } else {
+            data[header_len..].to_vec()
+        };
+
+        let gender = match filters[0x02] {
+            1 => Some(Gender::Male),
+            2 => Some(Gender::Female),
+            3 => None,
+            other => {
+                return Err(GtsError::Decode(format!(
+                    "Invalid searched gender: {}",
+                    other
+                )));
+            }
+        };
+
+        Ok(Self {
+            species_id: u16::from_le_bytes([filters[0x00], filters[0x01]]),
+            gender,
+            min_level: filters[0x03],
+            max_level: filters[0x04],
+            max_results: filters[0x06],
+            country_code: filters.get(0x07).copied().unwrap_or(0),
+        })
+    }
+
+    /// Checks whether a Pokémon reception matches the search filters.
+    ///
+    /// # Arguments
+    /// * `reception` - The Pokémon reception to check.
+    ///
+    /// Returns `true` if the Pokémon offered in the reception matches all filters.
+    pub fn matches(&self, reception: &GTSReception) -> bool {
+        let gts_data = &reception.gts_data;
+        let country_matches = self.country_code == 0
+            || country_code(&gts_data.country, &gts_data.region)
+                .is_some_and(|(country, _)| country == self.country_code);
+
+        gts_data.pkm_id == self.species_id
+            && self.gender.is_none_or(|gender| gts_data.gender == gender)
+            && (self.min_level == 0 || gts_data.lvl >= self.min_level)
+            && (self.max_level == 0 || gts_data.lvl <= self.max_level)
+            && country_matches
+    }
+}
+
 /// Struct representing a Pokémon reception from the GTS.
 ///
 /// This struct is used to send a Pokémon to a game from the GTS.
+#[derive(Getters)]
 pub struct GTSReception {
+    #[get = "pub"]
     pokemon: Pokemon,
+    #[get = "pub"]
     gts_data: GTSData,
     is_gen5: bool,
 }
@@ -518,10 +901,34 @@ pub struct GTSReception {
 impl GTSReception {
     /// Creates a new `GTSReception` from a Pokémon.
     ///
+    /// The fields of the GTS data that cannot be derived from the Pokémon (the profile ID and the
+    /// requested species) are random. See `from_pokemon_with_seed` to make them repeatable.
+    ///
     /// # Arguments
     /// * `pokemon` - The Pokémon with which to create the reception.
     pub fn from_pokemon(pokemon: &Pokemon) -> Self {
-        let gts_data = GTSData::from_pokemon(pokemon);
+        Self::from_pokemon_with_seed(pokemon, None)
+    }
+
+    /// Creates a new `GTSReception` from a Pokémon, seeding the generation of the GTS data fields
+    /// that cannot be derived from the Pokémon (the profile ID and the requested species).
+    ///
+    /// The same seed and Pokémon always produce the same GTS data, except for the traded time.
+    ///
+    /// # Arguments
+    /// * `pokemon` - The Pokémon with which to create the reception.
+    /// * `seed` - The seed of the random number generator. If `None`, it is seeded from the
+    ///   operating system's entropy.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "gts_reception", level = "debug", skip_all, fields(species = pokemon.species.id()))
+    )]
+    pub fn from_pokemon_with_seed(pokemon: &Pokemon, seed: Option<u64>) -> Self {
+        let mut rng = match seed {
+            Some(seed) => SmallRng::seed_from_u64(seed),
+            None => SmallRng::from_rng(&mut rand::rng()),
+        };
+        let gts_data = GTSData::from_pokemon(pokemon, &mut rng);
 
         Self {
             pokemon: pokemon.clone(),
@@ -530,6 +937,50 @@ impl GTSReception {
         }
     }
 
+    /// Makes the received Pokémon appear as obtained through a link trade.
+    ///
+    /// The Pokémon's met location and date are rewritten to the link trade values of the target
+    /// generation. See `Pokemon::set_link_trade_origin`.
+    ///
+    /// **This changes the Pokémon's apparent history**: the original met location and date are
+    /// not sent to the game.
+    pub fn with_link_trade_origin(mut self) -> Self {
+        self.pokemon.set_link_trade_origin();
+        self
+    }
+
+    /// Sets the range of levels of the Pokémon requested in exchange for the received one, which
+    /// is 1 to 100 by default.
+    ///
+    /// These are the trade terms shown in the game for the Pokémon, as if it had been deposited
+    /// by another trainer: when the Pokémon is listed in the search results, the game searching
+    /// only lets the player offer Pokémon of the requested species, gender and levels in exchange.
+    /// Games may behave oddly with an inverted or out of bounds range, so it is validated.
+    ///
+    /// # Arguments
+    /// * `min_level` - The minimum level of the requested Pokémon.
+    /// * `max_level` - The maximum level of the requested Pokémon.
+    ///
+    /// Returns `GtsError::InvalidValue` if the range is not within `1 <= min_level <= max_level <=
+    /// 100`.
+    pub fn with_requested_levels(mut self, min_level: u8, max_level: u8) -> Result<Self> {
+        validate_requested_levels(min_level, max_level)?;
+
+        self.gts_data.req_min_lvl = min_level;
+        self.gts_data.req_max_lvl = max_level;
+        Ok(self)
+    }
+
+    /// Replaces the GTS data sent alongside the Pokémon, e.g., with one crafted with
+    /// `GTSData::builder`.
+    ///
+    /// # Arguments
+    /// * `gts_data` - The GTS data to send instead of the one derived from the Pokémon.
+    pub fn with_gts_data(mut self, gts_data: GTSData) -> Self {
+        self.gts_data = gts_data;
+        self
+    }
+
     /// Serializes the GTS reception data into a byte vector.
     pub fn serialize(&self) -> Vec<u8> {
         let mut data;
@@ -539,8 +990,7 @@ impl GTSReception {
         data = pokemon_encrypted_data;
 
         if self.is_gen5 {
-            // Gen 5 padding:
-            data.extend(vec![0; 0x10]);
+            data.extend([0; GEN5_RECEPTION_PADDING]);
         }
 
         let gts_data = self.gts_data.serialize(self.is_gen5);
@@ -548,4 +998,488 @@ impl GTSReception {
 
         data
     }
+
+    /// Deserializes the data of a GTS reception, as sent to the game, e.g., captured from another
+    /// GTS server implementation.
+    ///
+    /// It is the inverse of `serialize`: the data is the encrypted Pokémon, followed by 0x10
+    /// bytes of padding in Gen 5, and the GTS data.
+    ///
+    /// # Arguments
+    /// * `bytes` - The serialized GTS reception.
+    /// * `is_gen5` - Whether the reception is for a Gen 5 game.
+    ///
+    /// Returns `GtsError::Decode` if the data does not have the length of the generation's
+    /// receptions, or if the GTS data is invalid, or the errors of `Pokemon::try_deserialize` if
+    /// the Pokémon is invalid.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gts_reception_deserialize",
+            level = "debug",
+            skip_all,
+            fields(is_gen5)
+        )
+    )]
+    pub fn deserialize(bytes: &[u8], is_gen5: bool) -> Result<Self> {
+        let (pkm_len, padding) = if !is_gen5 {
+            (GEN4_PKM_LEN, 0)
+        } else {
+            (GEN5_PKM_LEN, GEN5_RECEPTION_PADDING)
+        };
+        let gts_data_offset = pkm_len + padding;
+        let expected_len = gts_data_offset + GTSData::serialized_len(is_gen5);
+        if bytes.len() != expected_len {
+            return Err(GtsError::Decode(format!(
+                "Invalid GTS reception length: {} bytes, expected {}",
+                bytes.len(),
+                expected_len
+            )));
+        }
+
+        let pokemon = Pokemon::try_deserialize_with_generation(
+            &Pokemon::to_decrypted_data(&bytes[..pkm_len]),
+            is_gen5,
+        )?;
+        let gts_data = GTSData::deserialize(&bytes[gts_data_offset..], is_gen5)?;
+
+        Ok(Self {
+            pokemon,
+            gts_data,
+            is_gen5,
+        })
+    }
+
+    /// Gets the length of the serialized receptions of a generation.
+    ///
+    /// # Arguments
+    /// * `is_gen5` - Whether the receptions are for a Gen 5 game.
+    fn serialized_len(is_gen5: bool) -> usize {
+        if !is_gen5 {
+            GEN4_PKM_LEN + GTSData::serialized_len(false)
+        } else {
+            GEN5_PKM_LEN + GEN5_RECEPTION_PADDING + GTSData::serialized_len(true)
+        }
+    }
+}
+
+/// Struct representing the results of a GTS search: the Pokémon listed in the game's search
+/// screen, each one with the GTS data shown along with it (trainer, country, requested Pokémon...).
+///
+/// The results are serialized as the list of the Pokémon receptions, one after the other, each one
+/// as in a `result.asp` response (see `GTSReception::serialize`). No results serialize to an empty
+/// response.
+#[derive(Getters)]
+pub struct GTSSearchResult {
+    /// The Pokémon found, as receptions, in the order they are listed.
+    #[get = "pub"]
+    receptions: Vec<GTSReception>,
+    is_gen5: bool,
+}
+
+impl GTSSearchResult {
+    /// Creates empty search results, for a game of the given generation.
+    ///
+    /// # Arguments
+    /// * `is_gen5` - Whether the results are for a Gen 5 game.
+    pub fn new(is_gen5: bool) -> Self {
+        Self {
+            receptions: Vec::new(),
+            is_gen5,
+        }
+    }
+
+    /// Adds a Pokémon to the end of the results.
+    ///
+    /// # Arguments
+    /// * `reception` - The reception of the Pokémon found.
+    ///
+    /// Returns `GtsError::InvalidValue` if the reception is not for the generation of the results.
+    pub fn push(&mut self, reception: GTSReception) -> Result<()> {
+        if reception.is_gen5 != self.is_gen5 {
+            return Err(GtsError::InvalidValue(format!(
+                "Cannot list a Gen {} Pokémon in Gen {} search results",
+                if reception.is_gen5 { 5 } else { 4 },
+                if self.is_gen5 { 5 } else { 4 }
+            )));
+        }
+
+        self.receptions.push(reception);
+        Ok(())
+    }
+
+    /// Gets the number of Pokémon found.
+    pub fn len(&self) -> usize {
+        self.receptions.len()
+    }
+
+    /// Gets whether no Pokémon were found.
+    pub fn is_empty(&self) -> bool {
+        self.receptions.is_empty()
+    }
+
+    /// Serializes the search results into a byte vector, as sent to the game.
+    pub fn serialize(&self) -> Vec<u8> {
+        self.receptions
+            .iter()
+            .flat_map(GTSReception::serialize)
+            .collect()
+    }
+
+    /// Deserializes the search results sent to a game, e.g., captured from another GTS server
+    /// implementation.
+    ///
+    /// It is the inverse of `serialize`.
+    ///
+    /// # Arguments
+    /// * `bytes` - The serialized search results.
+    /// * `is_gen5` - Whether the results are for a Gen 5 game.
+    ///
+    /// Returns `GtsError::Decode` if the data is not a whole number of receptions of the
+    /// generation, or the errors of `GTSReception::deserialize` if any of them is invalid.
+    pub fn deserialize(bytes: &[u8], is_gen5: bool) -> Result<Self> {
+        let reception_len = GTSReception::serialized_len(is_gen5);
+        if !bytes.len().is_multiple_of(reception_len) {
+            return Err(GtsError::Decode(format!(
+                "Invalid GTS search results length: {} bytes, expected a multiple of {}",
+                bytes.len(),
+                reception_len
+            )));
+        }
+
+        let receptions = bytes
+            .chunks_exact(reception_len)
+            .map(|reception| GTSReception::deserialize(reception, is_gen5))
+            .collect::<Result<Vec<GTSReception>>>()?;
+
+        Ok(Self {
+            receptions,
+            is_gen5,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::Fixture;
+    use chrono::NaiveDate;
+
+    /// Builds GTS data with non-default values in every field.
+    fn sample_gts_data(game: Game, country: &str, region: &str) -> GTSData {
+        let time = |day| {
+            NaiveDate::from_ymd_opt(2010, 3, day)
+                .unwrap()
+                .and_hms_opt(12, 34, 56)
+                .unwrap()
+        };
+
+        GTSData::builder()
+            .pkm_id(448)
+            .gender(Gender::Female)
+            .lvl(42)
+            .req_pkm_id(25)
+            .req_gender(Gender::Male)
+            .req_min_lvl(10)
+            .req_max_lvl(90)
+            .trainer_gender(Gender::Female)
+            .deposited_time(time(6))
+            .traded_time(time(7))
+            .profile_id(0x12345678)
+            .trainer_id(0xBEEF)
+            .trainer_secret_id(0xCAFE)
+            .trainer_name("LUCAS".to_string())
+            .country(country.to_string())
+            .region(region.to_string())
+            .trainer_class(TrainerClass::MaleResearcher)
+            .is_exchanged(true)
+            .game(game)
+            .language(Language::English)
+            .unity_tower_floors(3)
+            .build()
+            .expect("Sample GTS data should be valid")
+    }
+
+    /// Serializes the GTS data and deserializes it back.
+    fn round_trip(gts_data: &GTSData, is_gen5: bool) -> GTSData {
+        let data = gts_data.serialize(is_gen5);
+        assert_eq!(data.len(), if !is_gen5 { 0x38 } else { 0x3C });
+
+        GTSData::deserialize(&data, is_gen5).unwrap()
+    }
+
+    #[test]
+    fn seeded_receptions_are_repeatable() {
+        let pokemon = Fixture::Gen4Party.pokemon();
+
+        let reception = |seed| {
+            let mut reception = GTSReception::from_pokemon_with_seed(&pokemon, Some(seed));
+            // The traded time is the current time:
+            reception.gts_data.traded_time = NaiveDateTime::default();
+            reception
+        };
+
+        assert_eq!(reception(42).serialize(), reception(42).serialize());
+        assert_ne!(
+            reception(42).gts_data.profile_id,
+            reception(43).gts_data.profile_id
+        );
+    }
+
+    #[test]
+    fn reception_locations_exist_in_geonet() {
+        for (country, region) in [
+            GEN4_RECEPTION_LOCATION,
+            BW_RECEPTION_LOCATION,
+            B2W2_RECEPTION_LOCATION,
+        ] {
+            assert!(
+                country_code(&country.to_string(), &region.to_string()).is_some(),
+                "{} ({}) is not in the geonet data",
+                region,
+                country
+            );
+            assert!(regions(country).unwrap().contains(&region.to_string()));
+        }
+        assert!(countries().contains(&GEN4_RECEPTION_LOCATION.0.to_string()));
+    }
+
+    #[test]
+    fn gen4_round_trip() {
+        let original = sample_gts_data(Game::Platinum, "Japan", "Hokkaido (Sapporo)");
+        let deserialized = round_trip(&original, false);
+
+        // The Gen 5-only fields are not serialized for Gen 4:
+        let expected = GTSData {
+            trainer_secret_id: None,
+            unity_tower_floors: None,
+            ..original
+        };
+        assert_eq!(deserialized, expected);
+    }
+
+    #[test]
+    fn gen5_round_trip() {
+        let original = sample_gts_data(Game::Black2, "United States of America", "New Jersey");
+        let deserialized = round_trip(&original, true);
+
+        assert_eq!(deserialized, original);
+    }
+
+    #[test]
+    fn round_trip_in_full_receptions() {
+        for (fixture, game, country, region, is_gen5) in [
+            (
+                Fixture::Gen4Boxed,
+                Game::Platinum,
+                "Japan",
+                "Hokkaido (Sapporo)",
+                false,
+            ),
+            (
+                Fixture::Gen5Party,
+                Game::Black2,
+                "United States of America",
+                "New Jersey",
+                true,
+            ),
+        ] {
+            let original = sample_gts_data(game, country, region);
+            let reception = GTSReception::from_pokemon_with_seed(&fixture.pokemon(), Some(42))
+                .with_gts_data(sample_gts_data(game, country, region));
+            let data = reception.serialize();
+            assert_eq!(data.len(), if !is_gen5 { 0x124 } else { 0x128 });
+
+            // The GTS data starts after the Pokémon (and the padding, in Gen 5), at 0xEC:
+            let gts_data = &data[0xEC..];
+            let extra_offset = if !is_gen5 { 0 } else { 2 };
+            let (country_code, region_code) = country_code(&original.country, &original.region)
+                .expect("Sample location should be in the geonet");
+            assert_eq!(gts_data[0x00..0x02], 448u16.to_le_bytes());
+            assert_eq!(gts_data[0x1C..0x20], 0x12345678u32.to_le_bytes());
+            assert_eq!(gts_data[0x32 + extra_offset], country_code);
+            assert_eq!(gts_data[0x33 + extra_offset], region_code);
+            assert_eq!(
+                gts_data[0x34 + extra_offset],
+                TrainerClass::MaleResearcher as u8
+            );
+            assert_eq!(gts_data[0x35 + extra_offset], 1);
+            assert_eq!(gts_data[0x36 + extra_offset], game as u8);
+            assert_eq!(gts_data[0x37 + extra_offset], Language::English as u8);
+            if !is_gen5 {
+                assert_eq!(gts_data[0x30..0x32], 0xBEEFu16.to_le_bytes());
+            } else {
+                assert_eq!(gts_data[0x20..0x24], [0xEF, 0xBE, 0xFE, 0xCA]);
+                assert_eq!(gts_data[0x3B], 3);
+            }
+
+            let deserialized = GTSReception::deserialize(&data, is_gen5).unwrap();
+            let expected = if !is_gen5 {
+                GTSData {
+                    trainer_secret_id: None,
+                    unity_tower_floors: None,
+                    ..original
+                }
+            } else {
+                original
+            };
+            assert_eq!(*deserialized.gts_data(), expected);
+        }
+    }
+
+    #[test]
+    fn unknown_gts_data_skips_the_unity_tower_floors() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+        let mut data = vec![0; 0x0C];
+        data.extend(Pokemon::to_encrypted_data(&pokemon.serialize()));
+        let mut gts_data =
+            sample_gts_data(Game::Black2, "United States of America", "New Jersey").serialize(true);
+        gts_data[0x09] = 0x11;
+        gts_data[0x3A] = 0x22;
+        data.extend(gts_data);
+
+        let deposit = GTSDeposit::from_base64(&URL_SAFE_B64.encode(data), true).unwrap();
+        assert_eq!(
+            deposit.unknown_gts_data(),
+            vec![(0x09, 0x11), (0x0B, 0x00), (0x3A, 0x22)]
+        );
+    }
+
+    #[test]
+    fn reception_round_trip() {
+        for (fixture, is_gen5) in [(Fixture::Gen4Boxed, false), (Fixture::Gen5Party, true)] {
+            let mut pokemon = fixture.pokemon();
+            pokemon.discard_original_bytes();
+            let reception = GTSReception::from_pokemon_with_seed(&pokemon, Some(42));
+            let serialized = reception.serialize();
+
+            let deserialized = GTSReception::deserialize(&serialized, is_gen5).unwrap();
+            assert_eq!(deserialized.pokemon().serialize(), pokemon.serialize());
+            assert_eq!(deserialized.gts_data().pkm_id, pokemon.species.id());
+            assert_eq!(deserialized.serialize(), serialized);
+
+            let result = GTSReception::deserialize(&serialized[1..], is_gen5);
+            assert!(matches!(result, Err(GtsError::Decode(_))));
+        }
+    }
+
+    #[test]
+    fn link_trade_origin_rewrites_the_met_location() {
+        // Link trade met locations: Gen 4's "Link trade (met)", and Gen 5's "In-game link trade".
+        for (fixture, met_location) in [(Fixture::Gen4Boxed, 2002u16), (Fixture::Gen5Party, 30002)]
+        {
+            let pokemon = fixture.pokemon();
+            let original = pokemon.serialize();
+            assert_ne!(original[0x80..0x82], met_location.to_le_bytes());
+            let reception =
+                GTSReception::from_pokemon_with_seed(&pokemon, Some(42)).with_link_trade_origin();
+
+            let deserialized =
+                GTSReception::deserialize(&reception.serialize(), pokemon.is_gen5()).unwrap();
+            let bytes = deserialized.pokemon().serialize();
+            // The met location is at 0x80 (Diamond and Pearl, and Gen 5), and 0x46 (Platinum and
+            // HeartGold/SoulSilver); the egg location, which is kept, right before each:
+            assert_eq!(bytes[0x80..0x82], met_location.to_le_bytes());
+            assert_eq!(bytes[0x7E..0x80], original[0x7E..0x80]);
+            if !pokemon.is_gen5() {
+                assert!(bytes[0x46..0x48] == [0, 0] || bytes[0x46..0x48] == bytes[0x80..0x82]);
+                assert_eq!(bytes[0x44..0x46], original[0x44..0x46]);
+            }
+        }
+    }
+
+    #[test]
+    fn search_results_round_trip() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+
+        let mut results = GTSSearchResult::new(true);
+        assert!(results.serialize().is_empty());
+        for seed in [1, 2] {
+            results
+                .push(GTSReception::from_pokemon_with_seed(&pokemon, Some(seed)))
+                .unwrap();
+        }
+        let serialized = results.serialize();
+        assert_eq!(serialized.len(), 2 * GTSReception::serialized_len(true));
+
+        let deserialized = GTSSearchResult::deserialize(&serialized, true).unwrap();
+        assert_eq!(deserialized.len(), 2);
+        assert_eq!(
+            deserialized.receptions()[1].gts_data().pkm_id,
+            pokemon.species.id()
+        );
+        assert_eq!(deserialized.serialize(), serialized);
+
+        let result = GTSSearchResult::deserialize(&serialized[1..], true);
+        assert!(matches!(result, Err(GtsError::Decode(_))));
+        let gen4_reception =
+            GTSReception::from_pokemon_with_seed(&Fixture::Gen4Party.pokemon(), None);
+        assert!(matches!(
+            results.push(gen4_reception),
+            Err(GtsError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn requested_levels_are_validated() {
+        let pokemon = Fixture::Gen4Party.pokemon();
+
+        let reception = GTSReception::from_pokemon_with_seed(&pokemon, Some(42))
+            .with_requested_levels(10, 10)
+            .unwrap();
+        let gts_data = &reception.serialize()[GEN4_PKM_LEN..];
+        assert_eq!(gts_data[0x07..0x09], [10, 10]);
+
+        for (min_level, max_level) in [(0, 50), (50, 101), (60, 50)] {
+            let result = GTSReception::from_pokemon_with_seed(&pokemon, Some(42))
+                .with_requested_levels(min_level, max_level);
+            assert!(matches!(result, Err(GtsError::InvalidValue(_))));
+        }
+    }
+
+    #[test]
+    fn unknown_location_serializes_as_no_location() {
+        let gts_data = GTSData {
+            country: "Atlantis".to_string(),
+            region: "Lost City".to_string(),
+            ..sample_gts_data(Game::Platinum, "Japan", "Hokkaido (Sapporo)")
+        };
+
+        assert_eq!(gts_data.serialize(false)[0x32..0x34], [0, 0]);
+        assert_eq!(gts_data.serialize(true)[0x34..0x36], [0, 0]);
+    }
+
+    #[test]
+    fn location_and_trainer_class_offsets() {
+        let original = sample_gts_data(Game::White, "United States of America", "New York");
+        let (country, region) =
+            country_code(&original.country, &original.region).expect("Location should be valid");
+
+        let gen4 = original.serialize(false);
+        assert_eq!(
+            gen4[0x32..0x38],
+            [
+                country,
+                region,
+                TrainerClass::MaleResearcher as u8,
+                1,
+                Game::White as u8,
+                Language::English as u8
+            ]
+        );
+
+        let gen5 = original.serialize(true);
+        assert_eq!(
+            gen5[0x34..0x3A],
+            [
+                country,
+                region,
+                TrainerClass::MaleResearcher as u8,
+                1,
+                Game::White as u8,
+                Language::English as u8
+            ]
+        );
+        assert_eq!(gen5[0x3B], 3);
+    }
 }
@@ -16,10 +16,13 @@
  * You should have received a copy of the GNU General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{data_maps::*, should_be_ok, should_not_happen};
+use crate::{
+    data_maps::*,
+    error::{GtsError, Result},
+    should_be_ok, should_not_happen,
+};
 use getset::{CopyGetters, Getters};
 use num_enum::TryFromPrimitive;
-use std::io::{Error, ErrorKind, Result};
 use strum::Display;
 
 /// Structure that represent a Pokémon feature that can be identified by a name and an ID.
@@ -247,7 +250,7 @@ impl StatsFeature {
 }
 
 /// Enum that identifies the different Pokémon stats.
-#[derive(Clone, Copy, Debug, Default, Display)]
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq)]
 pub enum Stat {
     /// Health stat.
     Hp,
@@ -273,7 +276,7 @@ impl Stat {
     /// * `2` - `Stat::Spe`
     /// * `3` - `Stat::SpA`
     /// * `4` - `Stat::SpD`
-    /// * Any other value - `Err(Error::InvalidData)`
+    /// * Any other value - `Err(GtsError::InvalidValue)`
     pub fn try_from_iv_index(index: usize) -> Result<Self> {
         match index {
             0 => Ok(Stat::Atk),
@@ -282,7 +285,10 @@ impl Stat {
             3 => Ok(Stat::SpA),
             4 => Ok(Stat::SpD),
 
-            _ => Err(Error::new(ErrorKind::InvalidData, "Invalid IV index")),
+            _ => Err(GtsError::InvalidValue(format!(
+                "Invalid IV index: {}",
+                index
+            ))),
         }
     }
 }
@@ -382,6 +388,82 @@ impl Nature {
     }
 }
 
+impl std::str::FromStr for Nature {
+    type Err = GtsError;
+
+    /// Parses a nature from its name, in any case, e.g., "adamant". See `parse_name`.
+    fn from_str(name: &str) -> Result<Self> {
+        let natures = (0..).map_while(Nature::from_id).collect::<Vec<Nature>>();
+        parse_name(name, "nature", natures, |nature| {
+            nature.id_and_name.name.clone()
+        })
+    }
+}
+
+/// Normalizes a name for comparing it with others: lowercased, without spaces, hyphens,
+/// underscores, dots or accents ("é"), so that, e.g., "Poké Ball" and "PokeBall" are the same.
+///
+/// # Arguments
+/// * `name` - The name to normalize.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_' | '.'))
+        .map(|c| match c {
+            'é' | 'É' => 'e',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Parses a value from its name, among all the possible ones. Names are compared normalized (see
+/// `normalize_name`), so they can be given in any case, and with or without spaces.
+///
+/// # Arguments
+/// * `name` - The name to parse.
+/// * `kind` - What the values are, for the error message, e.g., "game".
+/// * `values` - All the possible values.
+/// * `value_name` - Gets the name of a value.
+///
+/// Returns `GtsError::InvalidValue` if no value has the name, listing the valid names.
+fn parse_name<T>(
+    name: &str,
+    kind: &str,
+    values: impl IntoIterator<Item = T>,
+    value_name: impl Fn(&T) -> String,
+) -> Result<T> {
+    let normalized = normalize_name(name);
+    let mut names = Vec::new();
+    for value in values {
+        let value_name = value_name(&value);
+        if normalize_name(&value_name) == normalized {
+            return Ok(value);
+        }
+        names.push(value_name);
+    }
+
+    Err(GtsError::InvalidValue(format!(
+        "Invalid {}: {} (expected one of: {})",
+        kind,
+        name.trim(),
+        names.join(", ")
+    )))
+}
+
+/// Parses an enum from the name of one of its variants, as printed, e.g., "HeartGold". See
+/// `parse_name`.
+///
+/// # Arguments
+/// * `name` - The name to parse.
+/// * `kind` - What the enum is, for the error message, e.g., "game".
+fn parse_variant_name<T>(name: &str, kind: &str) -> Result<T>
+where
+    T: TryFromPrimitive<Primitive = u8> + std::fmt::Display,
+{
+    let variants = (0..=u8::MAX).filter_map(|value| T::try_from_primitive(value).ok());
+    parse_name(name, kind, variants, T::to_string)
+}
+
 /// Structure that represents a Pokémon's feature that affects is contest stats.
 ///
 /// This is used exclusively to represent the Pokémon's contest stats.
@@ -403,6 +485,39 @@ pub struct ContestStatsFeature {
     pub sheen: u8,
 }
 
+impl ContestStatsFeature {
+    /// Gets the value for the given contest stat.
+    ///
+    /// # Arguments
+    /// * `stat` - The contest stat to get the value of.
+    pub fn get(&self, stat: &ContestStat) -> u8 {
+        match stat {
+            ContestStat::Cool => self.cool,
+            ContestStat::Beauty => self.beauty,
+            ContestStat::Cute => self.cute,
+            ContestStat::Smart => self.smart,
+            ContestStat::Tough => self.tough,
+            ContestStat::Sheen => self.sheen,
+        }
+    }
+
+    /// Sets the value for the given contest stat.
+    ///
+    /// # Arguments
+    /// * `stat` - The contest stat to set the value of.
+    /// * `value` - The value to set.
+    pub fn set(&mut self, stat: &ContestStat, value: u8) {
+        match stat {
+            ContestStat::Cool => self.cool = value,
+            ContestStat::Beauty => self.beauty = value,
+            ContestStat::Cute => self.cute = value,
+            ContestStat::Smart => self.smart = value,
+            ContestStat::Tough => self.tough = value,
+            ContestStat::Sheen => self.sheen = value,
+        }
+    }
+}
+
 /// Enum that identifies the different contest stats.
 #[derive(Clone, Copy, Debug, Default, Display)]
 pub enum ContestStat {
@@ -421,10 +536,22 @@ pub enum ContestStat {
     Sheen,
 }
 
+impl ContestStat {
+    /// All the contest stats, in their internal storage order.
+    pub const ALL: [Self; 6] = [
+        Self::Cool,
+        Self::Beauty,
+        Self::Cute,
+        Self::Smart,
+        Self::Tough,
+        Self::Sheen,
+    ];
+}
+
 /// Enum the represent the different Pokémon and trainer genders.
 ///
 /// A trainer cannot be genderless.
-#[derive(Clone, Copy, Debug, Default, Display, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Gender {
     /// Male gender.
@@ -436,6 +563,96 @@ pub enum Gender {
     Genderless, // 0x2
 }
 
+impl Gender {
+    /// Gets the symbol of the gender, as shown in the games: "♂" for male, "♀" for female, and
+    /// an empty string for genderless.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Gender::Male => "♂",
+            Gender::Female => "♀",
+            Gender::Genderless => "",
+        }
+    }
+
+    /// Gets the gender identified by a symbol or letter, as used by Pokémon Showdown.
+    ///
+    /// # Arguments
+    /// * `symbol` - "♂" or "M" for male, "♀" or "F" for female, and "N" or an empty string for
+    ///   genderless.
+    ///
+    /// Returns `None` if the symbol does not identify a gender.
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "♂" | "M" => Some(Gender::Male),
+            "♀" | "F" => Some(Gender::Female),
+            "" | "N" => Some(Gender::Genderless),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Gender {
+    type Err = GtsError;
+
+    /// Parses a gender from its name ("Male", "Female" or "Genderless", in any case), or from its
+    /// symbol or letter (see `Gender::from_symbol`). Unlike `Gender::from_symbol`, an empty string
+    /// is not a gender.
+    fn from_str(gender: &str) -> Result<Self> {
+        let gender = gender.trim();
+        match gender.to_lowercase().as_str() {
+            "" => Err(GtsError::InvalidValue("Invalid gender: empty".to_string())),
+            "male" => Ok(Gender::Male),
+            "female" => Ok(Gender::Female),
+            "genderless" => Ok(Gender::Genderless),
+            _ => Gender::from_symbol(&gender.to_uppercase())
+                .ok_or_else(|| GtsError::InvalidValue(format!("Invalid gender: {}", gender))),
+        }
+    }
+}
+
+/// Enum that identifies the different Pokémon types.
+///
+/// The IDs follow the Gen 5 ordering, which is the Gen 4 one without the unused "???" type. The
+/// Fairy type does not exist until Gen 6.
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq, Hash, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Type {
+    #[default]
+    Normal, // 0x00
+    Fighting, // 0x01
+    Flying,   // 0x02
+    Poison,   // 0x03
+    Ground,   // 0x04
+    Rock,     // 0x05
+    Bug,      // 0x06
+    Ghost,    // 0x07
+    Steel,    // 0x08
+    Fire,     // 0x09
+    Water,    // 0x0A
+    Grass,    // 0x0B
+    Electric, // 0x0C
+    Psychic,  // 0x0D
+    Ice,      // 0x0E
+    Dragon,   // 0x0F
+    Dark,     // 0x10
+}
+
+/// Gets the damage multiplier of an attacking type against a Pokémon of the given types, as in
+/// the Gen 4 and Gen 5 games.
+///
+/// Returns the product of the multipliers against each of the defending types: 0 (immune), 0.25,
+/// 0.5, 1, 2 or 4.
+///
+/// # Arguments
+/// * `attacking` - The type of the attacking move.
+/// * `defending` - The primary and (optional) secondary types of the defending Pokémon.
+pub fn type_effectiveness(attacking: Type, defending: (Type, Option<Type>)) -> f32 {
+    let row = &TYPE_CHART[attacking as usize];
+    let (primary, secondary) = defending;
+
+    row[primary as usize] * secondary.map_or(1.0, |secondary| row[secondary as usize])
+}
+
 /// Enum that identifies the different shiny leaves a Pokémon can have in HeartGold and SoulSilver.
 #[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash)]
 pub enum ShinyLeaf {
@@ -490,6 +707,32 @@ pub enum Game {
     Black2 = 23,
 }
 
+impl std::str::FromStr for Game {
+    type Err = GtsError;
+
+    /// Parses a game from its name, in any case, with or without spaces, e.g., "Heart Gold". See
+    /// `parse_variant_name`.
+    fn from_str(name: &str) -> Result<Self> {
+        parse_variant_name(name, "game")
+    }
+}
+
+impl Game {
+    /// Gets the generation of the game: 3, 4, or 5.
+    pub fn generation(&self) -> u8 {
+        match self {
+            Game::Sapphire
+            | Game::Ruby
+            | Game::Emerald
+            | Game::FireRed
+            | Game::LeafGreen
+            | Game::ColosseumXD => 3,
+            Game::HeartGold | Game::SoulSilver | Game::Diamond | Game::Pearl | Game::Platinum => 4,
+            Game::White | Game::Black | Game::White2 | Game::Black2 => 5,
+        }
+    }
+}
+
 /// Enum that identifies the different Gen 4 and Gen 5 Poké Balls.
 #[derive(
     Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Display, TryFromPrimitive,
@@ -549,9 +792,190 @@ pub enum Pokeball {
     DreamBall,
 }
 
+impl std::str::FromStr for Pokeball {
+    type Err = GtsError;
+
+    /// Parses a Poké Ball from its name, in any case, with or without spaces and accents, e.g.,
+    /// "Poké Ball". See `parse_variant_name`.
+    fn from_str(name: &str) -> Result<Self> {
+        parse_variant_name(name, "Poké Ball")
+    }
+}
+
 impl Pokeball {
     pub const FIRST_HGSS_BALL: Self = Pokeball::FastBall;
     pub const LAST_GEN4_BALL: Self = Pokeball::SportBall;
+
+    /// Gets whether a Pokémon can be caught in this ball in the given game.
+    ///
+    /// Gen 3 games only have the balls up to the Premier Ball (Colosseum and XD lack the Safari
+    /// Ball), Gen 4 games add the balls up to the Cherish Ball, HeartGold and SoulSilver add the
+    /// Apricorn balls and the Sport Ball, and Gen 5 games add the Dream Ball instead. Cherish Balls
+    /// are only obtainable through events, which are not told apart here.
+    ///
+    /// # Arguments
+    /// * `game` - The game the Pokémon was caught in.
+    pub fn is_obtainable_in(&self, game: Game) -> bool {
+        match game {
+            Game::ColosseumXD => *self <= Pokeball::PremierBall && *self != Pokeball::SafariBall,
+            Game::Sapphire | Game::Ruby | Game::Emerald | Game::FireRed | Game::LeafGreen => {
+                *self <= Pokeball::PremierBall
+            }
+            Game::Diamond | Game::Pearl | Game::Platinum => *self <= Pokeball::CherishBall,
+            Game::HeartGold | Game::SoulSilver => *self <= Pokeball::LAST_GEN4_BALL,
+            Game::White | Game::Black | Game::White2 | Game::Black2 => {
+                (*self <= Pokeball::CherishBall && *self != Pokeball::SafariBall)
+                    || *self == Pokeball::DreamBall
+            }
+        }
+    }
+}
+
+// List of Gen 4 encounter types:
+// https://bulbapedia.bulbagarden.net/wiki/Pok%C3%A9mon_data_structure_(Generation_IV)
+/// Enum that identifies the different kinds of encounter a Pokémon can be met in, in the Gen 4
+/// games.
+///
+/// It is the kind of terrain the Pokémon was found on, with special values for gifts and some
+/// legendary encounters. Pokémon not met in the wild in a Gen 4 game always have `None`.
+///
+/// Values not used by the games are kept as `Unknown`, so that loading a Pokémon never fails
+/// because of them; the legality checks report them instead.
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq)]
+pub enum EncounterType {
+    /// Pal Park, eggs, events, and Pokémon from other generations.
+    #[default]
+    None,
+    /// Sand.
+    Sand,
+    /// Tall grass.
+    TallGrass,
+    /// Puddle.
+    Puddle,
+    /// Rock; the Dialga and Palkia in-game events.
+    Rock,
+    /// Cave; also the Hall of Origin.
+    Cave,
+    /// Snow.
+    Snow,
+    /// Water, when surfing or fishing.
+    Water,
+    /// Ice.
+    Ice,
+    /// Building.
+    Building,
+    /// Great Marsh (Safari Zone of Diamond, Pearl and Platinum).
+    GreatMarsh,
+    /// Bridge.
+    Bridge,
+    /// Starters, fossils, and gifts.
+    Gift,
+    /// Distortion World (Giratina in Platinum).
+    DistortionWorld,
+    /// A value not used by the games.
+    Unknown(u8),
+}
+
+impl From<u8> for EncounterType {
+    /// Gets the encounter type stored in an encounter type byte.
+    fn from(byte: u8) -> Self {
+        match byte {
+            0x00 => EncounterType::None,
+            0x01 => EncounterType::Sand,
+            0x02 => EncounterType::TallGrass,
+            0x03 => EncounterType::Puddle,
+            0x04 => EncounterType::Rock,
+            0x05 => EncounterType::Cave,
+            0x06 => EncounterType::Snow,
+            0x07 => EncounterType::Water,
+            0x08 => EncounterType::Ice,
+            0x09 => EncounterType::Building,
+            0x0A => EncounterType::GreatMarsh,
+            0x0B => EncounterType::Bridge,
+            0x0C => EncounterType::Gift,
+            0x17 => EncounterType::DistortionWorld,
+            _ => EncounterType::Unknown(byte),
+        }
+    }
+}
+
+impl From<EncounterType> for u8 {
+    /// Gets the encounter type byte storing an encounter type.
+    fn from(encounter_type: EncounterType) -> Self {
+        match encounter_type {
+            EncounterType::None => 0x00,
+            EncounterType::Sand => 0x01,
+            EncounterType::TallGrass => 0x02,
+            EncounterType::Puddle => 0x03,
+            EncounterType::Rock => 0x04,
+            EncounterType::Cave => 0x05,
+            EncounterType::Snow => 0x06,
+            EncounterType::Water => 0x07,
+            EncounterType::Ice => 0x08,
+            EncounterType::Building => 0x09,
+            EncounterType::GreatMarsh => 0x0A,
+            EncounterType::Bridge => 0x0B,
+            EncounterType::Gift => 0x0C,
+            EncounterType::DistortionWorld => 0x17,
+            EncounterType::Unknown(byte) => byte,
+        }
+    }
+}
+
+/// Enum that identifies the non-volatile status conditions of a Pokémon, which remain after
+/// battles. Only party Pokémon have them; boxes heal Pokémon.
+///
+/// It is stored in a single byte: bits 0-2 are the turns left asleep, and each of bits 3 to 7 is
+/// one of the other conditions. A Pokémon has one condition at most.
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq)]
+pub enum StatusCondition {
+    /// No status condition.
+    #[default]
+    None,
+    /// Asleep, for the given number of turns (1 to 7).
+    Sleep(u8),
+    /// Poisoned.
+    Poison,
+    /// Burned.
+    Burn,
+    /// Frozen.
+    Freeze,
+    /// Paralyzed.
+    Paralysis,
+    /// Badly poisoned (e.g., by Toxic).
+    BadPoison,
+}
+
+impl From<u8> for StatusCondition {
+    /// Gets the status condition stored in a status byte. If several conditions are set, which the
+    /// games never do, the one in the lowest bits is returned.
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => StatusCondition::None,
+            _ if byte & 0x07 != 0 => StatusCondition::Sleep(byte & 0x07),
+            _ if byte & 0x08 != 0 => StatusCondition::Poison,
+            _ if byte & 0x10 != 0 => StatusCondition::Burn,
+            _ if byte & 0x20 != 0 => StatusCondition::Freeze,
+            _ if byte & 0x40 != 0 => StatusCondition::Paralysis,
+            _ => StatusCondition::BadPoison,
+        }
+    }
+}
+
+impl From<StatusCondition> for u8 {
+    /// Gets the status byte storing a status condition. Sleep turns over 7 are truncated to their
+    /// lowest 3 bits.
+    fn from(status: StatusCondition) -> Self {
+        match status {
+            StatusCondition::None => 0,
+            StatusCondition::Sleep(turns) => turns & 0x07,
+            StatusCondition::Poison => 0x08,
+            StatusCondition::Burn => 0x10,
+            StatusCondition::Freeze => 0x20,
+            StatusCondition::Paralysis => 0x40,
+            StatusCondition::BadPoison => 0x80,
+        }
+    }
 }
 
 // List of Gen 4 locations:
@@ -1206,7 +1630,24 @@ pub enum Gen4Location {
 impl Gen4Location {
     /// Default egg location for Pokémon that did not hatch from an egg.
     pub const NO_EGG_LOCATION: Self = Gen4Location::MysteryZone;
+    /// Last map location of Diamond and Pearl. The following map locations were introduced in
+    /// Platinum, HeartGold and SoulSilver.
     pub const DP_LAST_LOCATION: Self = Gen4Location::BattlePark;
+    /// Last special location (e.g., Day-Care Couple, or Riley) of Diamond and Pearl. The following
+    /// special locations (e.g., Cynthia, or Mr. Pokémon) were introduced in Platinum, HeartGold
+    /// and SoulSilver.
+    pub const DP_LAST_SPECIAL_LOCATION: Self = Gen4Location::Riley;
+
+    /// Returns whether the location exists in Diamond and Pearl, and can therefore be stored in
+    /// their location offsets.
+    ///
+    /// Map locations up to Battle Park, special locations up to Riley, and all event locations
+    /// exist in Diamond and Pearl.
+    pub fn is_in_dp(&self) -> bool {
+        *self <= Self::DP_LAST_LOCATION
+            || (Self::DayCareCouple..=Self::DP_LAST_SPECIAL_LOCATION).contains(self)
+            || *self >= Self::LovelyPlace
+    }
 }
 
 // List of Gen 5 locations:
@@ -1825,13 +2266,13 @@ impl std::fmt::Display for Location {
     }
 }
 
-impl Into<u16> for Location {
+impl From<Location> for u16 {
     /// Converts the `Location` into a `u16` representation.
     ///
     /// This function will return the `u16` value corresponding to the specific location
     /// variant, either from `Gen4Location` or `Gen5Location`.
-    fn into(self) -> u16 {
-        match self {
+    fn from(location: Location) -> u16 {
+        match location {
             Location::Gen4(loc) => loc as u16,
             Location::Gen5(loc) => loc as u16,
         }
@@ -1839,7 +2280,7 @@ impl Into<u16> for Location {
 }
 
 /// Enum identifying the different languages in the Gen 4 and Gen 5 games.
-#[derive(Clone, Copy, Debug, Default, Display, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Language {
     /// Japanese.
@@ -1859,8 +2300,18 @@ pub enum Language {
     Korean,
 }
 
+impl std::str::FromStr for Language {
+    type Err = GtsError;
+
+    /// Parses a language from its name in English, in any case, e.g., "japanese". See
+    /// `parse_variant_name`.
+    fn from_str(name: &str) -> Result<Self> {
+        parse_variant_name(name, "language")
+    }
+}
+
 /// Enum identifying the Trainer Class / sprite of GTS deposits in Generation 5.
-#[derive(Clone, Copy, Debug, Default, Display, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Default, Display, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum TrainerClass {
     /// Youngster.
@@ -1901,3 +2352,77 @@ pub enum TrainerClass {
 impl TrainerClass {
     pub const COUNT: u8 = (Self::FemaleDayCareStudent as u8 + 1);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_effectiveness_combines_both_types() {
+        assert_eq!(type_effectiveness(Type::Fire, (Type::Grass, None)), 2.0);
+        assert_eq!(
+            type_effectiveness(Type::Ice, (Type::Dragon, Some(Type::Flying))),
+            4.0
+        );
+        assert_eq!(
+            type_effectiveness(Type::Fire, (Type::Water, Some(Type::Rock))),
+            0.25
+        );
+        assert_eq!(
+            type_effectiveness(Type::Electric, (Type::Water, Some(Type::Ground))),
+            0.0
+        );
+        // Steel still resists Ghost and Dark before Gen 6:
+        assert_eq!(type_effectiveness(Type::Dark, (Type::Steel, None)), 0.5);
+    }
+
+    #[test]
+    fn gender_symbols_round_trip() {
+        for gender in [Gender::Male, Gender::Female, Gender::Genderless] {
+            assert_eq!(Gender::from_symbol(gender.symbol()), Some(gender));
+            assert_eq!(gender.to_string().parse::<Gender>().unwrap(), gender);
+        }
+        assert_eq!("m".parse::<Gender>().unwrap(), Gender::Male);
+        assert_eq!("F".parse::<Gender>().unwrap(), Gender::Female);
+        assert!("X".parse::<Gender>().is_err());
+        assert!("".parse::<Gender>().is_err());
+        assert!("  ".parse::<Gender>().is_err());
+    }
+
+    #[test]
+    fn names_are_parsed_in_any_case_and_spacing() {
+        let nature = "adamant".parse::<Nature>().unwrap();
+        assert_eq!(nature.id_and_name.id(), 3);
+        assert_eq!("Heart Gold".parse::<Game>().unwrap(), Game::HeartGold);
+        assert_eq!("black-2".parse::<Game>().unwrap(), Game::Black2);
+        assert_eq!("Poké Ball".parse::<Pokeball>().unwrap(), Pokeball::PokeBall);
+        assert_eq!(
+            "cherishball".parse::<Pokeball>().unwrap(),
+            Pokeball::CherishBall
+        );
+        assert_eq!("KOREAN".parse::<Language>().unwrap(), Language::Korean);
+
+        let Err(GtsError::InvalidValue(message)) = "Sturdy".parse::<Nature>() else {
+            panic!("Sturdy is not a nature");
+        };
+        assert!(message.contains("Adamant"));
+        assert!("Crystal".parse::<Game>().is_err());
+    }
+
+    #[test]
+    fn status_conditions_round_trip() {
+        for status in [
+            StatusCondition::None,
+            StatusCondition::Sleep(3),
+            StatusCondition::Poison,
+            StatusCondition::Burn,
+            StatusCondition::Freeze,
+            StatusCondition::Paralysis,
+            StatusCondition::BadPoison,
+        ] {
+            assert_eq!(StatusCondition::from(u8::from(status)), status);
+        }
+        assert_eq!(StatusCondition::from(0x47), StatusCondition::Sleep(7));
+        assert_eq!(u8::from(StatusCondition::BadPoison), 0x80);
+    }
+}
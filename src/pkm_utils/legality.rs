@@ -0,0 +1,1053 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Legality checks for Pokémon.
+//!
+//! These checks are not exhaustive: they only catch inconsistencies that can be detected with the
+//! data available to this library.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{
+    data_maps::GAME_AVAILABILITY,
+    internal_types::{EncounterType, Game, Gen4Location, Gen5Location, Location, Pokeball, Stat},
+    pokemon::{Pokemon, MAX_PP_UPS, MAX_TOTAL_EVS},
+};
+
+/// Struct representing which species and moves are available in a game.
+///
+/// Species and moves are available up until the last one introduced in the game's generation.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GameAvailability {
+    /// National Pokédex number of the last species available in the game.
+    pub last_species: u16,
+    /// ID of the last move available in the game.
+    pub last_move: u16,
+}
+
+/// Enum that identifies the different legality issues that can be found in a Pokémon.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LegalityNote {
+    /// The species is not available in the Pokémon's game of origin.
+    SpeciesNotInOriginGame {
+        /// The name of the species.
+        species: String,
+        /// The game of origin of the Pokémon.
+        game: Game,
+    },
+    /// A move known by the Pokémon does not exist in its game of origin.
+    MoveNotInOriginGame {
+        /// The name of the move.
+        move_name: String,
+        /// The game of origin of the Pokémon.
+        game: Game,
+    },
+    /// The encounter type of the Pokémon does not match how it was obtained.
+    InconsistentEncounterType {
+        /// The encounter type of the Pokémon.
+        encounter_type: EncounterType,
+        /// Why the encounter type does not match.
+        reason: String,
+    },
+    /// The Pokémon has the fateful encounter flag, but was not met in an event nor in an in-game
+    /// fateful encounter.
+    FatefulWithoutEvent {
+        /// The name of the species.
+        species: String,
+        /// The location the Pokémon was met in.
+        met_location: Location,
+    },
+    /// The species can only be obtained with the fateful encounter flag, but the Pokémon lacks it.
+    MissingFatefulFlag {
+        /// The name of the species.
+        species: String,
+    },
+    /// The Pokémon was met in a location of a Gen 5 event chain, but it is not of a species
+    /// distributed in that event.
+    WrongEventChainSpecies {
+        /// The name of the species.
+        species: String,
+        /// The event chain of the Pokémon's met location.
+        chain: EventChain,
+    },
+    /// The Pokémon was obtained as an egg, but has no egg location.
+    MissingEggLocation,
+    /// The Pokémon was not obtained as an egg, but has an egg location.
+    UnexpectedEggLocation(Location),
+    /// The egg location of the Pokémon is not a place eggs can be obtained from.
+    ImplausibleEggLocation(Location),
+    /// The met location of a hatched Pokémon is not a place eggs can hatch in.
+    ImplausibleHatchLocation(Location),
+    /// The nickname flag of the Pokémon does not match whether its name is the default one of its
+    /// species.
+    InconsistentNicknameFlag {
+        /// The name of the Pokémon.
+        name: String,
+        /// Whether the Pokémon is flagged as nicknamed.
+        is_nicknamed: bool,
+    },
+    /// More PP Ups were applied to a move than the games allow.
+    TooManyPpUps {
+        /// The name of the move.
+        move_name: String,
+        /// The number of PP Ups applied to the move.
+        pp_ups: u8,
+    },
+    /// A move has more PP than its maximum, with the PP Ups applied to it.
+    PpAboveMax {
+        /// The name of the move.
+        move_name: String,
+        /// The PP of the move.
+        pp: u8,
+        /// The maximum PP of the move.
+        max_pp: u8,
+    },
+    /// An empty move slot has PP.
+    PpInEmptyMoveSlot {
+        /// The move slot, from 0 to 3.
+        slot: usize,
+        /// The PP of the move slot.
+        pp: u8,
+    },
+    /// The Pokémon has its species' hidden ability, but was not obtained from a source of hidden
+    /// abilities.
+    HiddenAbilityWithoutSource {
+        /// The name of the species.
+        species: String,
+        /// The name of the hidden ability.
+        ability: String,
+        /// The location the Pokémon was met in.
+        met_location: Location,
+    },
+}
+
+impl fmt::Display for LegalityNote {
+    /// Formats the `LegalityNote` as a printed string, describing the issue.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LegalityNote::SpeciesNotInOriginGame { species, game } => {
+                write!(f, "{} cannot originate from Pokémon {}", species, game)
+            }
+            LegalityNote::MoveNotInOriginGame { move_name, game } => {
+                write!(
+                    f,
+                    "The move {} does not exist in Pokémon {}",
+                    move_name, game
+                )
+            }
+            LegalityNote::InconsistentEncounterType {
+                encounter_type,
+                reason,
+            } => {
+                write!(f, "Invalid encounter type {}: {}", encounter_type, reason)
+            }
+            LegalityNote::FatefulWithoutEvent {
+                species,
+                met_location,
+            } => write!(
+                f,
+                "{} has the fateful encounter flag, but {} is not an event location",
+                species, met_location
+            ),
+            LegalityNote::MissingFatefulFlag { species } => write!(
+                f,
+                "{} can only be obtained in a fateful encounter, but the flag is not set",
+                species
+            ),
+            LegalityNote::WrongEventChainSpecies { species, chain } => write!(
+                f,
+                "{} cannot be met in the locations of the {} event, only {} can",
+                species,
+                chain,
+                chain.species_names()
+            ),
+            LegalityNote::MissingEggLocation => {
+                write!(
+                    f,
+                    "The Pokémon was obtained as an egg, but has no egg location"
+                )
+            }
+            LegalityNote::UnexpectedEggLocation(location) => write!(
+                f,
+                "The Pokémon was not obtained as an egg, but has egg location {}",
+                location
+            ),
+            LegalityNote::ImplausibleEggLocation(location) => {
+                write!(f, "Eggs cannot be obtained from {}", location)
+            }
+            LegalityNote::ImplausibleHatchLocation(location) => {
+                write!(f, "Eggs cannot hatch in {}", location)
+            }
+            LegalityNote::InconsistentNicknameFlag { name, is_nicknamed } => {
+                if *is_nicknamed {
+                    write!(
+                        f,
+                        "{} is flagged as a nickname, but it is the species' default name",
+                        name
+                    )
+                } else {
+                    write!(
+                        f,
+                        "{} is not flagged as a nickname, but it is not the species' default name",
+                        name
+                    )
+                }
+            }
+            LegalityNote::TooManyPpUps { move_name, pp_ups } => write!(
+                f,
+                "{} has {} PP Ups applied, but at most {} can be",
+                move_name, pp_ups, MAX_PP_UPS
+            ),
+            LegalityNote::PpAboveMax {
+                move_name,
+                pp,
+                max_pp,
+            } => write!(
+                f,
+                "{} has {} PP, but its maximum is {}",
+                move_name, pp, max_pp
+            ),
+            LegalityNote::PpInEmptyMoveSlot { slot, pp } => {
+                write!(f, "Empty move slot {} has {} PP", slot + 1, pp)
+            }
+            LegalityNote::HiddenAbilityWithoutSource {
+                species,
+                ability,
+                met_location,
+            } => write!(
+                f,
+                "{} has its hidden ability {}, but {} is not a source of hidden abilities",
+                species, ability, met_location
+            ),
+        }
+    }
+}
+
+/// Enum that identifies the different reasons why the games would reject a Pokémon received
+/// through the GTS.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The Pokémon is flagged as a Bad Egg.
+    BadEgg,
+    /// The level of the Pokémon is outside of the 1-100 range.
+    InvalidLevel(u8),
+    /// The checksum stored in the Pokémon data does not match the data.
+    ChecksumMismatch {
+        /// The checksum stored in the data.
+        stored: u16,
+        /// The checksum computed from the data.
+        computed: u16,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    /// Formats the `ValidationIssue` as a printed string, describing the issue.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::BadEgg => write!(f, "The Pokémon is a Bad Egg"),
+            ValidationIssue::InvalidLevel(level) => {
+                write!(f, "Invalid level: {}", level)
+            }
+            ValidationIssue::ChecksumMismatch { stored, computed } => write!(
+                f,
+                "Corrupt data: stored checksum {:#06X}, computed {:#06X}",
+                stored, computed
+            ),
+        }
+    }
+}
+
+/// Struct summarizing the signs of an edited Pokémon that can be seen at a glance, e.g., when a
+/// Pokémon is deposited. See `Pokemon::legality_summary`.
+#[derive(Clone, Debug)]
+pub struct LegalitySummary {
+    /// The sum of the EVs of all stats.
+    pub ev_total: u16,
+    /// The stats whose EVs are over the maximum of a single stat, with their EVs.
+    pub over_cap_evs: Vec<(Stat, u16)>,
+    /// The number of stats with perfect (31) IVs.
+    pub perfect_ivs: u8,
+    /// Whether the stored checksum matched the data when the Pokémon was loaded.
+    pub checksum_matches: bool,
+    /// The first problem found by `Pokemon::validate`, if any.
+    pub validation_error: Option<String>,
+}
+
+impl LegalitySummary {
+    /// Returns whether no problem was found.
+    ///
+    /// Perfect IVs are not a problem, but they are shown in the summary, as they are common in
+    /// edited Pokémon.
+    pub fn is_clean(&self) -> bool {
+        self.ev_total <= MAX_TOTAL_EVS
+            && self.over_cap_evs.is_empty()
+            && self.checksum_matches
+            && self.validation_error.is_none()
+    }
+}
+
+impl fmt::Display for LegalitySummary {
+    /// Formats the `LegalitySummary` as a single line, e.g.: `EVs 510/510, 6/6 perfect IVs,
+    /// checksum OK, valid`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EVs {}/{}", self.ev_total, MAX_TOTAL_EVS)?;
+        if !self.over_cap_evs.is_empty() {
+            let over_cap = self
+                .over_cap_evs
+                .iter()
+                .map(|(stat, evs)| format!("{} {}", stat, evs))
+                .collect::<Vec<String>>();
+            write!(f, " (over cap: {})", over_cap.join(", "))?;
+        }
+        write!(f, ", {}/6 perfect IVs", self.perfect_ivs)?;
+        write!(
+            f,
+            ", checksum {}",
+            if self.checksum_matches {
+                "OK"
+            } else {
+                "mismatch"
+            }
+        )?;
+        match &self.validation_error {
+            Some(error) => write!(f, ", invalid: {}", error),
+            None => write!(f, ", valid"),
+        }
+    }
+}
+
+/// Species that are always met in a fateful encounter (events, or special in-game events), when
+/// obtained in a Gen 4 or Gen 5 game: Mew, Celebi, Jirachi, Deoxys, Manaphy, Darkrai, Shaymin,
+/// Arceus, Victini, Keldeo, Meloetta, and Genesect.
+const FATEFUL_ONLY_SPECIES: [u16; 12] =
+    [151, 251, 385, 386, 490, 491, 492, 493, 494, 647, 648, 649];
+
+/// In-game encounters that set the fateful encounter flag, as (species, met location) pairs.
+const IN_GAME_FATEFUL_ENCOUNTERS: [(u16, Location); 7] = [
+    (483, Location::Gen4(Gen4Location::SinjohRuins)), // Dialga
+    (484, Location::Gen4(Gen4Location::SinjohRuins)), // Palkia
+    (487, Location::Gen4(Gen4Location::SinjohRuins)), // Giratina
+    (491, Location::Gen4(Gen4Location::NewmoonIsland)), // Darkrai
+    (492, Location::Gen4(Gen4Location::FlowerParadise)), // Shaymin
+    (493, Location::Gen4(Gen4Location::HallOfOrigin)), // Arceus
+    (494, Location::Gen5(Gen5Location::LibertyGarden)), // Victini
+];
+
+/// Gets the species and moves available in a game.
+///
+/// # Arguments
+/// * `game` - The game to get the availability of.
+///
+/// Returns `None` if the availability data has no entry for the game.
+pub fn game_availability(game: Game) -> Option<GameAvailability> {
+    GAME_AVAILABILITY.get(&game.to_string()).copied()
+}
+
+/// Checks whether a Pokémon is consistent with its game of origin.
+///
+/// The following checks are performed:
+/// * The species is available in the game of origin (National Pokédex range).
+/// * All moves known by the Pokémon exist in the game of origin.
+///
+/// No checks are performed for games without availability data.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_origin_consistency(pkm: &Pokemon) -> Vec<LegalityNote> {
+    let mut notes = Vec::new();
+    let game = pkm.origin_game;
+    let Some(availability) = game_availability(game) else {
+        return notes;
+    };
+
+    if pkm.species.id() > availability.last_species {
+        notes.push(LegalityNote::SpeciesNotInOriginGame {
+            species: pkm.species_name().to_string(),
+            game,
+        });
+    }
+
+    for mov in pkm.known_moves() {
+        if mov.id() > availability.last_move {
+            notes.push(LegalityNote::MoveNotInOriginGame {
+                move_name: mov.name().clone(),
+                game,
+            });
+        }
+    }
+
+    notes
+}
+
+/// Checks whether the encounter type of a Pokémon is consistent with how it was obtained.
+///
+/// The following checks are performed:
+/// * The encounter type is one used by the games.
+/// * Pokémon not met in a Gen 4 game, eggs, hatched Pokémon, Pal Park transfers and event
+///   Pokémon (Cherish Ball or event met location) have no encounter type.
+/// * Pokémon met in the Great Marsh with a Safari Ball in Diamond, Pearl or Platinum have the
+///   Great Marsh encounter type, and vice versa.
+/// * Pokémon with the Distortion World encounter type were met in the Distortion World.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_encounter_consistency(pkm: &Pokemon) -> Vec<LegalityNote> {
+    let mut notes = Vec::new();
+    let encounter_type = pkm.encounter_type;
+    let mut note = |reason: &str| {
+        notes.push(LegalityNote::InconsistentEncounterType {
+            encounter_type,
+            reason: reason.to_string(),
+        })
+    };
+
+    if let EncounterType::Unknown(value) = encounter_type {
+        note(&format!("the games do not use the value {:#04X}", value));
+        return notes;
+    }
+
+    let met_location = match pkm.met_location {
+        Location::Gen4(location) if pkm.origin_generation() == 4 => location,
+        _ => {
+            if encounter_type != EncounterType::None {
+                note("only Pokémon met in Gen 4 games have an encounter type");
+            }
+            return notes;
+        }
+    };
+
+    let no_encounter_reason = if pkm.is_egg {
+        Some("eggs have no encounter type")
+    } else if pkm.egg_location != Location::Gen4(Gen4Location::NO_EGG_LOCATION) {
+        Some("hatched Pokémon have no encounter type")
+    } else if met_location == Gen4Location::PalPark {
+        Some("Pokémon transferred through the Pal Park have no encounter type")
+    } else if pkm.ball == Pokeball::CherishBall || met_location >= Gen4Location::LovelyPlace {
+        Some("event Pokémon have no encounter type")
+    } else {
+        None
+    };
+    if let Some(reason) = no_encounter_reason {
+        if encounter_type != EncounterType::None {
+            note(reason);
+        }
+        return notes;
+    }
+
+    let in_great_marsh = met_location == Gen4Location::GreatMarsh
+        && pkm.ball == Pokeball::SafariBall
+        && matches!(
+            pkm.origin_game,
+            Game::Diamond | Game::Pearl | Game::Platinum
+        );
+    if in_great_marsh && encounter_type != EncounterType::GreatMarsh {
+        note("Pokémon caught in the Great Marsh have the Great Marsh encounter type");
+    } else if !in_great_marsh && encounter_type == EncounterType::GreatMarsh {
+        note("only Pokémon caught in the Great Marsh with a Safari Ball have it");
+    }
+
+    if encounter_type == EncounterType::DistortionWorld
+        && met_location != Gen4Location::DistortionWorld
+    {
+        note("only Pokémon met in the Distortion World have it");
+    }
+
+    notes
+}
+
+/// Gets whether a location is only used by event Pokémon, e.g., "Faraway place".
+///
+/// # Arguments
+/// * `location` - The location to check.
+fn is_event_location(location: Location) -> bool {
+    match location {
+        Location::Gen4(location) => location >= Gen4Location::LovelyPlace,
+        Location::Gen5(location) => {
+            (Gen5Location::CelebiSpecialPlace..=Gen5Location::BeastsFatefulEncounter)
+                .contains(&location)
+                || (Gen5Location::LovelyPlace..=Gen5Location::EventSite).contains(&location)
+        }
+    }
+}
+
+/// Checks whether the fateful encounter flag of a Pokémon is consistent with how it was met.
+///
+/// The following checks are performed:
+/// * Pokémon with the flag were met (or received as an egg) in an event location, in one of the
+///   known in-game fateful encounters, or were transferred from an older generation, which keeps
+///   the flag.
+/// * Pokémon of species only obtainable in fateful encounters have the flag, if they were
+///   obtained in a Gen 4 or Gen 5 game.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_fateful_consistency(pkm: &Pokemon) -> Vec<LegalityNote> {
+    let mut notes = Vec::new();
+    let species = pkm.species.id();
+
+    if pkm.fateful {
+        let transferred = matches!(
+            pkm.met_location,
+            Location::Gen4(Gen4Location::PalPark) | Location::Gen5(Gen5Location::PokéTransferLab)
+        );
+        let in_game_encounter = IN_GAME_FATEFUL_ENCOUNTERS.contains(&(species, pkm.met_location));
+        if !transferred
+            && !in_game_encounter
+            && !is_event_location(pkm.met_location)
+            && !is_event_location(pkm.egg_location)
+        {
+            notes.push(LegalityNote::FatefulWithoutEvent {
+                species: pkm.species_name().to_string(),
+                met_location: pkm.met_location,
+            });
+        }
+    } else if pkm.origin_generation() >= 4 && FATEFUL_ONLY_SPECIES.contains(&species) {
+        notes.push(LegalityNote::MissingFatefulFlag {
+            species: pkm.species_name().to_string(),
+        });
+    }
+
+    notes
+}
+
+/// Enum that identifies the Gen 5 event chains, in which an event Pokémon brought to Black or
+/// White unlocks an in-game event: the event Celebi unlocks Zorua, and the event Legendary Beasts
+/// unlock Zoroark.
+///
+/// The event Pokémon are met in special locations, which change once they trigger the in-game
+/// event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventChain {
+    /// The event Celebi, which unlocks Zorua.
+    Celebi,
+    /// The event Raikou, Entei and Suicune, which unlock Zoroark.
+    Beasts,
+}
+
+impl EventChain {
+    /// Gets the event chain a met location belongs to, if any.
+    ///
+    /// # Arguments
+    /// * `location` - The met location to check.
+    pub fn from_met_location(location: Location) -> Option<Self> {
+        match location {
+            Location::Gen5(
+                Gen5Location::CelebiSpecialPlace | Gen5Location::CelebiFatefulEncounter,
+            ) => Some(EventChain::Celebi),
+            Location::Gen5(
+                Gen5Location::BeastsSpecialPlace | Gen5Location::BeastsFatefulEncounter,
+            ) => Some(EventChain::Beasts),
+            _ => None,
+        }
+    }
+
+    /// Gets the National Pokédex numbers of the species distributed in the event.
+    pub fn species(&self) -> &'static [u16] {
+        match self {
+            EventChain::Celebi => &[251],
+            EventChain::Beasts => &[243, 244, 245],
+        }
+    }
+
+    /// Gets the names of the species distributed in the event, for messages.
+    fn species_names(&self) -> &'static str {
+        match self {
+            EventChain::Celebi => "Celebi",
+            EventChain::Beasts => "Raikou, Entei and Suicune",
+        }
+    }
+
+    /// Gets whether a met location is the one of the event Pokémon after triggering the in-game
+    /// event, instead of before.
+    ///
+    /// # Arguments
+    /// * `location` - The met location to check.
+    pub fn is_triggered(location: Location) -> bool {
+        matches!(
+            location,
+            Location::Gen5(
+                Gen5Location::CelebiFatefulEncounter | Gen5Location::BeastsFatefulEncounter
+            )
+        )
+    }
+}
+
+impl fmt::Display for EventChain {
+    /// Formats the `EventChain` as a printed string, e.g., "Celebi (Zorua)".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventChain::Celebi => write!(f, "Celebi (Zorua)"),
+            EventChain::Beasts => write!(f, "Legendary Beasts (Zoroark)"),
+        }
+    }
+}
+
+/// Checks whether a Pokémon met in a location of a Gen 5 event chain (see `EventChain`) is one of
+/// the event Pokémon.
+///
+/// The following checks are performed:
+/// * The species is one of the species distributed in the event.
+/// * The Pokémon has the fateful encounter flag, as all event Pokémon do. Celebi is only checked
+///   by `check_fateful_consistency`, which requires the flag for all Celebi.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_event_chain_consistency(pkm: &Pokemon) -> Vec<LegalityNote> {
+    let mut notes = Vec::new();
+    let Some(chain) = EventChain::from_met_location(pkm.met_location) else {
+        return notes;
+    };
+
+    let species = pkm.species.id();
+    if !chain.species().contains(&species) {
+        notes.push(LegalityNote::WrongEventChainSpecies {
+            species: pkm.species_name().to_string(),
+            chain,
+        });
+    }
+    if !pkm.fateful && !FATEFUL_ONLY_SPECIES.contains(&species) {
+        notes.push(LegalityNote::MissingFatefulFlag {
+            species: pkm.species_name().to_string(),
+        });
+    }
+
+    notes
+}
+
+/// Gets whether eggs can be obtained from a location: the Day-Care, link trades, the NPCs giving
+/// eggs away, and events.
+///
+/// # Arguments
+/// * `location` - The egg location to check.
+fn is_egg_giver_location(location: Location) -> bool {
+    is_event_location(location)
+        || match location {
+            Location::Gen4(location) => matches!(
+                location,
+                Gen4Location::DayCareCouple
+                    | Gen4Location::LinkTradeArrive
+                    | Gen4Location::TravelingMan
+                    | Gen4Location::Riley
+                    | Gen4Location::Cynthia
+                    | Gen4Location::MrPokemon
+                    | Gen4Location::Primo
+            ),
+            Location::Gen5(location) => matches!(
+                location,
+                Gen5Location::DayCareCouple
+                    | Gen5Location::LinkTradeEgg
+                    | Gen5Location::Stranger
+                    | Gen5Location::TreasureHunterOrPKMNBreeder
+            ),
+        }
+}
+
+/// Checks whether the egg and met locations of a Pokémon are consistent with whether it was
+/// obtained as an egg.
+///
+/// The following checks are performed:
+/// * Pokémon obtained as an egg have an egg location where eggs can be obtained, and the others
+///   have none.
+/// * Hatched Pokémon were met (i.e., hatched) in a map location, not in a special location such as
+///   a link trade or an event.
+///
+/// Pokémon transferred from an older generation are not checked, as their locations are replaced
+/// by the transfer.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_hatch_consistency(pkm: &Pokemon) -> Vec<LegalityNote> {
+    let mut notes = Vec::new();
+
+    let (no_egg_location, in_hatch_range) = match (pkm.met_location, pkm.origin_generation()) {
+        (Location::Gen4(location), 4) => (
+            Location::Gen4(Gen4Location::NO_EGG_LOCATION),
+            location < Gen4Location::DayCareCouple,
+        ),
+        (Location::Gen5(location), 5) => (
+            Location::Gen5(Gen5Location::NO_EGG_LOCATION),
+            location < Gen5Location::OtherRegionDistantLand,
+        ),
+        _ => return notes,
+    };
+
+    if !pkm.met_as_egg() {
+        if pkm.egg_location != no_egg_location {
+            notes.push(LegalityNote::UnexpectedEggLocation(pkm.egg_location));
+        }
+        return notes;
+    }
+
+    if pkm.egg_location == no_egg_location {
+        notes.push(LegalityNote::MissingEggLocation);
+    } else if !is_egg_giver_location(pkm.egg_location) {
+        notes.push(LegalityNote::ImplausibleEggLocation(pkm.egg_location));
+    }
+
+    if pkm.was_hatched()
+        && (!in_hatch_range || pkm.met_location == Location::Gen4(Gen4Location::PalPark))
+    {
+        notes.push(LegalityNote::ImplausibleHatchLocation(pkm.met_location));
+    }
+
+    notes
+}
+
+/// Checks whether the nickname flag of a Pokémon is consistent with its name: only Pokémon whose
+/// name is not the default one of their species are nicknamed.
+///
+/// Eggs, and Pokémon from languages whose default species names are unknown (all but English, for
+/// now), are not checked.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_nickname_consistency(pkm: &Pokemon) -> Vec<LegalityNote> {
+    if pkm.is_egg || pkm.default_name().is_none() {
+        return Vec::new();
+    }
+
+    if pkm.is_nicknamed == pkm.nickname_is_default() {
+        vec![LegalityNote::InconsistentNicknameFlag {
+            name: pkm.name().clone(),
+            is_nicknamed: pkm.is_nicknamed,
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Checks that the PP of the Pokémon's moves are possible: that no more than 3 PP Ups were applied
+/// to each move, that no move has more PP than its maximum (see `Pokemon::max_pp`), and that empty
+/// move slots have no PP.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_move_pps(pkm: &Pokemon) -> Vec<LegalityNote> {
+    let mut notes = Vec::new();
+
+    for (slot, mov) in pkm.moves.iter().enumerate() {
+        let pp = pkm.move_pps[slot];
+        if mov.id() == 0 {
+            if pp != 0 {
+                notes.push(LegalityNote::PpInEmptyMoveSlot { slot, pp });
+            }
+            continue;
+        }
+
+        let pp_ups = pkm.move_pp_ups[slot];
+        if pp_ups > MAX_PP_UPS {
+            notes.push(LegalityNote::TooManyPpUps {
+                move_name: mov.name().clone(),
+                pp_ups,
+            });
+        }
+        if let Some(max_pp) = pkm.max_pp(slot).filter(|&max_pp| pp > max_pp) {
+            notes.push(LegalityNote::PpAboveMax {
+                move_name: mov.name().clone(),
+                pp,
+                max_pp,
+            });
+        }
+    }
+
+    notes
+}
+
+/// Gets whether Pokémon met in a location come from the Pokémon Dream World (through the Entree
+/// Forest) or the Pokémon Dream Radar, which give Pokémon their hidden ability.
+///
+/// # Arguments
+/// * `location` - The met location to check.
+fn is_dream_location(location: Location) -> bool {
+    matches!(
+        location,
+        Location::Gen5(
+            Gen5Location::EntreeForest | Gen5Location::PokemonDW | Gen5Location::PokemonDreamRadar
+        )
+    )
+}
+
+/// Checks that a Pokémon with a hidden ability was obtained from a source of hidden abilities.
+///
+/// Hidden abilities were introduced in Gen 5, where they are obtained from the Pokémon Dream World
+/// and the Pokémon Dream Radar, from events, and inherited by breeding. Black 2 and White 2 also
+/// give them in Hidden Grottoes and some in-game encounters, which are met in regular map
+/// locations, so Pokémon from these games are only checked when met in a special location.
+///
+/// Pokémon from Gen 3 and Gen 4 games cannot have their hidden ability at all; see
+/// `Pokemon::legal_abilities`.
+///
+/// # Arguments
+/// * `pkm` - The Pokémon to check.
+///
+/// # Returns
+/// A list with all the issues found. An empty list means no issues were found.
+pub fn check_hidden_ability_source(pkm: &Pokemon) -> Vec<LegalityNote> {
+    if pkm.origin_generation() != 5 || !pkm.has_hidden_ability() || pkm.met_as_egg() {
+        return Vec::new();
+    }
+
+    let met_location = pkm.met_location;
+    let in_map_location = matches!(
+        met_location,
+        Location::Gen5(location) if location < Gen5Location::OtherRegionDistantLand
+    );
+    let possible_grotto = matches!(pkm.origin_game, Game::Black2 | Game::White2) && in_map_location;
+    if is_dream_location(met_location)
+        || is_event_location(met_location)
+        || pkm.ball == Pokeball::CherishBall
+        || possible_grotto
+    {
+        return Vec::new();
+    }
+
+    vec![LegalityNote::HiddenAbilityWithoutSource {
+        species: pkm.species_name().to_string(),
+        ability: pkm.ability_name().to_string(),
+        met_location,
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::GtsError, fixtures::Fixture, internal_types::IdFeature};
+
+    #[test]
+    fn origin_game_has_the_species_and_moves() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.origin_game = Game::Platinum;
+        pokemon.set_moves(&["Tackle", "Shadow Force"]).unwrap();
+        assert!(check_origin_consistency(&pokemon).is_empty());
+
+        // Neither Turtwig nor Shadow Force exist in Emerald:
+        pokemon.origin_game = Game::Emerald;
+        let notes = check_origin_consistency(&pokemon);
+        assert_eq!(notes.len(), 2);
+        assert!(matches!(
+            notes[0],
+            LegalityNote::SpeciesNotInOriginGame {
+                game: Game::Emerald,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &notes[1],
+            LegalityNote::MoveNotInOriginGame { move_name, .. } if move_name == "Shadow Force"
+        ));
+        assert!(matches!(pokemon.validate(), Err(GtsError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn encounter_type_matches_where_it_was_met() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.origin_game = Game::Platinum;
+        pokemon.egg_location = Location::Gen4(Gen4Location::NO_EGG_LOCATION);
+        pokemon.met_location = Location::Gen4(Gen4Location::GreatMarsh);
+        pokemon.ball = Pokeball::SafariBall;
+        pokemon.encounter_type = EncounterType::GreatMarsh;
+        assert!(check_encounter_consistency(&pokemon).is_empty());
+
+        pokemon.encounter_type = EncounterType::TallGrass;
+        assert_eq!(check_encounter_consistency(&pokemon).len(), 1);
+        pokemon.is_egg = true;
+        assert!(matches!(
+            check_encounter_consistency(&pokemon)[..],
+            [LegalityNote::InconsistentEncounterType {
+                encounter_type: EncounterType::TallGrass,
+                ..
+            }]
+        ));
+        pokemon.encounter_type = EncounterType::None;
+        assert!(check_encounter_consistency(&pokemon).is_empty());
+    }
+
+    #[test]
+    fn fateful_flag_needs_an_event() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.origin_game = Game::Platinum;
+        pokemon.egg_location = Location::Gen4(Gen4Location::NO_EGG_LOCATION);
+        pokemon.met_location = Location::Gen4(Gen4Location::Route201);
+        pokemon.fateful = false;
+        assert!(check_fateful_consistency(&pokemon).is_empty());
+
+        pokemon.fateful = true;
+        assert!(matches!(
+            check_fateful_consistency(&pokemon)[..],
+            [LegalityNote::FatefulWithoutEvent { .. }]
+        ));
+        assert!(matches!(pokemon.validate(), Err(GtsError::InvalidValue(_))));
+        pokemon.met_location = Location::Gen4(Gen4Location::LovelyPlace);
+        assert!(check_fateful_consistency(&pokemon).is_empty());
+
+        // Shaymin is always met in a fateful encounter, e.g., in the Flower Paradise:
+        pokemon.species = IdFeature::from_species_id(492).unwrap();
+        pokemon.met_location = Location::Gen4(Gen4Location::FlowerParadise);
+        assert!(check_fateful_consistency(&pokemon).is_empty());
+        pokemon.fateful = false;
+        assert!(matches!(
+            check_fateful_consistency(&pokemon)[..],
+            [LegalityNote::MissingFatefulFlag { .. }]
+        ));
+    }
+
+    #[test]
+    fn hatched_pokemon_have_plausible_locations() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.origin_game = Game::Platinum;
+        pokemon.is_egg = false;
+        pokemon.egg_date = pokemon.met_date.into();
+        pokemon.egg_location = Location::Gen4(Gen4Location::DayCareCouple);
+        pokemon.met_location = Location::Gen4(Gen4Location::Route205);
+        assert!(pokemon.was_hatched());
+        assert!(check_hatch_consistency(&pokemon).is_empty());
+
+        // Eggs do not hatch in special locations, nor are given in map locations:
+        pokemon.met_location = Location::Gen4(Gen4Location::PalPark);
+        pokemon.egg_location = Location::Gen4(Gen4Location::SolaceonTown);
+        assert_eq!(
+            check_hatch_consistency(&pokemon),
+            [
+                LegalityNote::ImplausibleEggLocation(pokemon.egg_location),
+                LegalityNote::ImplausibleHatchLocation(pokemon.met_location),
+            ]
+        );
+        assert!(matches!(pokemon.validate(), Err(GtsError::InvalidValue(_))));
+
+        pokemon.met_location = Location::Gen4(Gen4Location::Route205);
+        pokemon.egg_location = Location::Gen4(Gen4Location::NO_EGG_LOCATION);
+        assert_eq!(
+            check_hatch_consistency(&pokemon),
+            [LegalityNote::MissingEggLocation]
+        );
+
+        // Pokémon not met as eggs have no egg location:
+        pokemon.egg_date = None;
+        assert!(check_hatch_consistency(&pokemon).is_empty());
+        pokemon.egg_location = Location::Gen4(Gen4Location::DayCareCouple);
+        assert_eq!(
+            check_hatch_consistency(&pokemon),
+            [LegalityNote::UnexpectedEggLocation(pokemon.egg_location)]
+        );
+    }
+
+    #[test]
+    fn event_chain_locations_need_the_event_pokemon() {
+        let mut pokemon = Fixture::Gen5Party.pokemon();
+        pokemon.met_location = Location::Gen5(Gen5Location::Route1);
+        assert!(check_event_chain_consistency(&pokemon).is_empty());
+
+        // A Snivy, without the fateful encounter flag, met as the event Legendary Beasts:
+        pokemon.fateful = false;
+        pokemon.met_location = Location::Gen5(Gen5Location::BeastsFatefulEncounter);
+        assert!(EventChain::is_triggered(pokemon.met_location));
+        let notes = check_event_chain_consistency(&pokemon);
+        assert_eq!(notes.len(), 2);
+        assert!(matches!(
+            notes[0],
+            LegalityNote::WrongEventChainSpecies {
+                chain: EventChain::Beasts,
+                ..
+            }
+        ));
+
+        // The event Suicune:
+        pokemon.species = IdFeature::from_species_id(245).unwrap();
+        pokemon.fateful = true;
+        assert!(check_event_chain_consistency(&pokemon).is_empty());
+
+        // Only Celebi is met in the locations of the Celebi event:
+        pokemon.met_location = Location::Gen5(Gen5Location::CelebiSpecialPlace);
+        assert!(!EventChain::is_triggered(pokemon.met_location));
+        assert_eq!(check_event_chain_consistency(&pokemon).len(), 1);
+        pokemon.species = IdFeature::from_species_id(251).unwrap();
+        assert!(check_event_chain_consistency(&pokemon).is_empty());
+    }
+
+    #[test]
+    fn hidden_ability_needs_a_source() {
+        let mut pokemon = Fixture::Gen5Party.pokemon();
+        pokemon.origin_game = Game::Black;
+        pokemon.egg_date = None;
+        pokemon.met_location = Location::Gen5(Gen5Location::Route1);
+        assert!(!pokemon.has_hidden_ability());
+        assert!(check_hidden_ability_source(&pokemon).is_empty());
+
+        // Snivy's hidden ability, Contrary:
+        pokemon.ability = IdFeature::from_ability_id(126).unwrap();
+        assert!(pokemon.has_hidden_ability());
+        assert_eq!(check_hidden_ability_source(&pokemon).len(), 1);
+
+        pokemon.met_location = Location::Gen5(Gen5Location::EntreeForest);
+        assert!(check_hidden_ability_source(&pokemon).is_empty());
+
+        // Black 2 and White 2 have Hidden Grottoes in map locations:
+        pokemon.met_location = Location::Gen5(Gen5Location::Route1);
+        pokemon.origin_game = Game::White2;
+        assert!(check_hidden_ability_source(&pokemon).is_empty());
+    }
+
+    #[test]
+    fn move_pps_do_not_exceed_max_pp() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.set_moves(&["Outrage", "Tackle"]).unwrap();
+        pokemon.move_pps = [24, 35, 0, 0];
+        pokemon.move_pp_ups = [3, 0, 0, 0];
+        assert!(check_move_pps(&pokemon).is_empty());
+
+        // Outrage has less PP in Gen 5; too many PP Ups, and PP in an empty slot:
+        pokemon.try_convert_to_gen5().unwrap();
+        pokemon.move_pp_ups[1] = 4;
+        pokemon.move_pps[2] = 5;
+        assert_eq!(check_move_pps(&pokemon).len(), 3);
+    }
+
+    #[test]
+    fn nickname_flag_matches_the_name() {
+        let mut pokemon = Fixture::Gen4Boxed.pokemon();
+
+        pokemon.set_name("TURTWIG".to_string()).unwrap();
+        assert!(pokemon.nickname_is_default());
+        assert!(check_nickname_consistency(&pokemon).is_empty());
+
+        pokemon.set_name("SHELLY".to_string()).unwrap();
+        assert!(!pokemon.nickname_is_default());
+        assert_eq!(check_nickname_consistency(&pokemon).len(), 1);
+    }
+}
@@ -16,8 +16,18 @@
  * You should have received a copy of the GNU General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+//! Pokémon and GTS data handling for Gen 4 and Gen 5 games.
+//!
+//! The library logs through the `log` facade only, and never initializes a logger. With the
+//! `tracing` feature, the GTS transactions (see the `gts` module) are wrapped in `tracing` spans.
 pub mod data_maps;
+pub mod error;
+#[cfg(test)]
+mod fixtures;
 pub mod gts;
 pub mod internal_types;
+pub mod legality;
 pub mod macros;
 pub mod pokemon;
+pub mod savefile;
+pub mod summary;
@@ -57,7 +57,7 @@ macro_rules! should_not_happen {
 #[macro_export]
 macro_rules! should_be_ok {
     ($func:expr, $($msg:expr),*) => {
-        $func.unwrap_or_else(|_| { crate::should_not_happen!($($msg),*) })
+        $func.unwrap_or_else(|_| { $crate::should_not_happen!($($msg),*) })
     };
 }
 
@@ -73,6 +73,6 @@ macro_rules! should_be_ok {
 #[macro_export]
 macro_rules! should_be_some {
     ($func:expr, $($msg:expr),*) => {
-        $func.unwrap_or_else(|| { crate::should_not_happen!($($msg),*) })
+        $func.unwrap_or_else(|| { $crate::should_not_happen!($($msg),*) })
     };
 }
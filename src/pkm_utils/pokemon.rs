@@ -16,25 +16,195 @@
  * You should have received a copy of the GNU General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use base64::{engine::general_purpose::STANDARD as STANDARD_B64, Engine as _};
 use chrono::{Datelike, Local as LocalTime, NaiveDate};
 use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
 use std::{
-    collections::HashSet,
+    collections::{hash_map::Entry, HashMap, HashSet},
     convert::{TryFrom, TryInto},
     fs::{self, File},
-    io::{Error, ErrorKind, Result, Write},
+    io::{ErrorKind, Write},
     path::{Path, PathBuf},
+    str::FromStr,
+    sync::{LazyLock, Mutex},
 };
 
-use crate::{data_maps::*, internal_types::*, should_be_ok, should_be_some, should_not_happen};
+use crate::{
+    data_maps::*,
+    error::{GtsError, Result},
+    internal_types::*,
+    legality::{
+        check_encounter_consistency, check_event_chain_consistency, check_fateful_consistency,
+        check_hatch_consistency, check_hidden_ability_source, check_move_pps,
+        check_nickname_consistency, check_origin_consistency, LegalityNote, LegalitySummary,
+        ValidationIssue,
+    },
+    should_be_ok, should_be_some, should_not_happen,
+    summary::PokemonSummary,
+};
 
 // Games' internal representation constats:
-const BOXED_PKM_LEN: usize = 0x88;
-const GEN4_PKM_LEN: usize = 0xEC;
-const GEN5_PKM_LEN: usize = 0xDC;
+pub(crate) const BOXED_PKM_LEN: usize = 0x88;
+pub(crate) const GEN4_PKM_LEN: usize = 0xEC;
+pub(crate) const GEN5_PKM_LEN: usize = 0xDC;
+
+// Minimum data length required to read each region of the Pokémon data, when deserializing:
+const BLOCKS_END: usize = BOXED_PKM_LEN; // Header and blocks A to D: 0x00 - 0x87.
+const LEVEL_END: usize = 0x8D; // Level: 0x8C.
+const BATTLE_STATS_END: usize = 0x9C; // Stats: 0x90 - 0x9B.
+
+// Boxed data only has the blocks; party data of both generations has every region:
+const _: () = assert!(BOXED_PKM_LEN >= BLOCKS_END && BOXED_PKM_LEN < LEVEL_END);
+const _: () = assert!(GEN4_PKM_LEN >= BATTLE_STATS_END && GEN5_PKM_LEN >= BATTLE_STATS_END);
+
+// Last species and moves of each generation:
 const LAST_GEN4_POKEMON: u16 = 493; // Last Pokémon in Gen 4 has ID 493 (Arceus).
-const LAST_GEN4_ITEM: u16 = 536; // Last item in Gen 4 has ID 536 (Enigma Stone).
+const LAST_GEN5_POKEMON: u16 = 649; // Last Pokémon in Gen 5 has ID 649 (Genesect).
 const LAST_GEN4_MOVE: u16 = 467; // Last move in Gen 4 has ID 467 (Shadow Force).
+const LAST_GEN5_MOVE: u16 = 559; // Last move in Gen 5 has ID 559 (V-create).
+
+// Stat limits:
+/// All the stats, in the order of the base stats.
+const ALL_STATS: [Stat; 6] = [
+    Stat::Hp,
+    Stat::Atk,
+    Stat::Def,
+    Stat::SpA,
+    Stat::SpD,
+    Stat::Spe,
+];
+pub(crate) const MAX_IV: u16 = 31; // Maximum IV of a single stat.
+const MAX_STAT_EVS: u16 = 252; // Maximum useful EVs in a single stat (4 EVs raise it by 1).
+pub(crate) const MAX_TOTAL_EVS: u16 = 510; // Maximum EVs in all stats combined.
+
+// Move limits:
+pub(crate) const MAX_PP_UPS: u8 = 3; // Maximum PP Ups applied to a single move.
+/// Base PP of the moves whose PP was lowered in Gen 5, as they were in Gen 4.
+const GEN4_BASE_PP_CHANGES: [(u16, u8); 5] = [
+    (26, 25),  // Jump Kick.
+    (37, 20),  // Thrash.
+    (80, 20),  // Petal Dance.
+    (136, 20), // Hi Jump Kick.
+    (200, 15), // Outrage.
+];
+
+// Name limits:
+const MAX_NAME_CHARS: usize = 10; // Maximum characters in a Pokémon's name.
+pub(crate) const MAX_TRAINER_NAME_CHARS: usize = 7; // Maximum characters in a trainer's name.
+
+// Special species, items, and values:
+const SHEDINJA: u16 = 292; // Shedinja always has 1 HP.
+const EVERSTONE: u16 = 229; // Held item that prevents evolution; same ID in Gen 4 and Gen 5.
+const SHINY_THRESHOLD: u16 = 8; // Pokémon whose shiny value is below it are shiny (Gen 3 to 5).
+
+/// Files in a save directory, as (extension, contents) pairs.
+type SavedFiles = HashSet<(String, Vec<u8>)>;
+/// Files saved by `Pokemon::save` and `Pokemon::save_with_format`, by save directory, to skip
+/// saving the same Pokémon twice without reading the whole directory on every save.
+static SAVED_FILES_INDEX: LazyLock<Mutex<HashMap<PathBuf, SavedFiles>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Base stats used for species missing from the base stats table: the Medium Fast experience
+// curve, and no base stats.
+const FALLBACK_BASE_STATS: [u8; 7] = [2, 0, 0, 0, 0, 0, 0];
+
+/// Enum that identifies the formats Pokémon can be saved to files in. See
+/// `Pokemon::save_with_format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveFormat {
+    /// The decrypted data, as `.pk4` or `.pk5` files: the usual format of Pokémon files.
+    #[default]
+    #[serde(alias = "pk4", alias = "pk5")]
+    Pk,
+    /// The encrypted data, as `.ek4` or `.ek5` files, as stored in the save files.
+    #[serde(alias = "ek4", alias = "ek5")]
+    Ek,
+    /// A JSON object with the summary of the Pokémon (see `PokemonSummary`), its generation, and
+    /// its decrypted data in base64, as `.json` files.
+    Json,
+}
+
+impl SaveFormat {
+    /// Extensions of the Pokémon files loaded by `Pokemon::load`: the ones of every format, and the
+    /// `.pkm` extension of older tools.
+    pub const FILE_EXTENSIONS: [&'static str; 6] = ["pkm", "pk4", "pk5", "ek4", "ek5", "json"];
+
+    /// Gets the extension of the files of this format.
+    ///
+    /// # Arguments
+    /// * `is_gen5` - Whether the file is of a Gen 5 Pokémon, instead of a Gen 4 one.
+    pub fn extension(&self, is_gen5: bool) -> &'static str {
+        match (self, is_gen5) {
+            (SaveFormat::Pk, false) => "pk4",
+            (SaveFormat::Pk, true) => "pk5",
+            (SaveFormat::Ek, false) => "ek4",
+            (SaveFormat::Ek, true) => "ek5",
+            (SaveFormat::Json, _) => "json",
+        }
+    }
+
+    /// Encodes a Pokémon into the contents of a file of this format.
+    ///
+    /// # Arguments
+    /// * `pokemon` - The Pokémon to encode.
+    pub fn encode(&self, pokemon: &Pokemon) -> Vec<u8> {
+        let data = pokemon.serialize();
+        match self {
+            SaveFormat::Pk => data,
+            SaveFormat::Ek => Pokemon::to_encrypted_data(&data),
+            SaveFormat::Json => {
+                let json = PokemonJson {
+                    summary: PokemonSummary::from(pokemon),
+                    generation: if pokemon.is_gen5 { 5 } else { 4 },
+                    data: STANDARD_B64.encode(&data),
+                };
+                should_be_ok!(
+                    serde_json::to_vec_pretty(&json),
+                    "Failed to serialize {} to JSON",
+                    pokemon.species.name()
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for SaveFormat {
+    type Err = GtsError;
+
+    /// Parses the name of a format: `pk` (or `pk4`, `pk5`), `ek` (or `ek4`, `ek5`), or `json`.
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "pk" | "pk4" | "pk5" => Ok(SaveFormat::Pk),
+            "ek" | "ek4" | "ek5" => Ok(SaveFormat::Ek),
+            "json" => Ok(SaveFormat::Json),
+            _ => Err(GtsError::InvalidValue(format!(
+                "Unknown save format: {} (expected pk, ek or json)",
+                name
+            ))),
+        }
+    }
+}
+
+/// Contents of the Pokémon files saved in the JSON format. See `SaveFormat::Json`.
+#[derive(Serialize)]
+struct PokemonJson {
+    #[serde(flatten)]
+    summary: PokemonSummary,
+    generation: u8,
+    /// The decrypted data of the Pokémon, in base64.
+    data: String,
+}
+
+/// Data read back from the Pokémon files saved in the JSON format; the summary is ignored, since
+/// the data holds all of it.
+#[derive(Deserialize)]
+struct PokemonJsonData {
+    /// The decrypted data of the Pokémon, in base64.
+    data: String,
+}
 
 // Gen 4 Pokémon structure documentation: https://projectpokemon.org/docs/gen-4/pkm-structure-r65/
 // Gen 5 Pokémon structure documentation: https://projectpokemon.org/home/docs/gen-5/bw-save-structure-r60/
@@ -50,6 +220,8 @@ const LAST_GEN4_MOVE: u16 = 467; // Last move in Gen 4 has ID 467 (Shadow Force)
 ///
 /// All fields which can be safely modified have public visibility. Other fields, whose
 /// modification would alter other data in the Pokémon, are accessible through getters and setters.
+/// The IVs and EVs are public too, but their setters (`set_ivs` and `set_evs`) should be preferred,
+/// as they check the values are within the games' limits and keep party stats up to date.
 #[rustfmt::skip]
 #[derive(Clone, Default, Debug, Getters, CopyGetters)]
 pub struct Pokemon {
@@ -66,6 +238,7 @@ pub struct Pokemon {
     pub encryption_bypass: bool,            // 0x04
     pub bad_egg_flag: bool,                 // 0x04
     original_checksum: u16,                 // 0x06 - 0x07
+    computed_checksum: u16,                 // Computed from the deserialized data.
     pub held_item: IdFeature,               // 0x0A - 0x0B
     pub trainer_id: u16,                    // 0x0C - 0x0D
     pub trainer_secret_id: u16,             // 0x0E - 0x0F
@@ -98,32 +271,39 @@ pub struct Pokemon {
     trainer_name: String,                   // 0x68 - 0x77
     pub egg_date: Option<NaiveDate>,        // 0x78 - 0x7A
     pub met_date: NaiveDate,                // 0x7B - 0x7D
-    pub pokerus: u8,                        // 0x82
+    pub pokerus: u8,                        // 0x82, bits 4-7 = strain, bits 0-3 = days left
     pub ball: Pokeball,                     // 0x83 (redundant in 0x86 in HGSS)
     pub met_level: u8,                      // 0x84, bits 0-6
     pub trainer_gender: Gender,             // 0x84, bit 7
-    pub encounter_type: u8,                 // 0x85
+    pub encounter_type: EncounterType,      // 0x85
     pub performance: u8,                    // 0x87
     // Pokémon in boxes have data up until here (0x88).
 
-    pub stats: Option<StatsFeature>, // 0x90 - 0x9E
+    pub status_condition: StatusCondition, // 0x88
+    /// Current HP of the party Pokémon, e.g., 0 if it fainted. `None` means full HP: the maximum
+    /// HP, which is the HP stat. Boxed Pokémon are deserialized with `None`, as boxes heal them.
+    pub current_hp: Option<u16>,           // 0x8E - 0x8F
+    pub stats: Option<StatsFeature>,       // 0x90 - 0x9E
 
     // There are other fields that are used in game but are irrelevant for Pokémon transference,
     // including GTS services.
     // These include:
-    //  * Max HP (0x8E - 0x8F)
-    //  * Status conditions (0x88)
     //  * Capsule/seal indeces (0x8D)
     //  * [Gen 4] Seal coordinates (0xD4 - 0xEB)
     //  * Mail message + OT Name (0x9C - 0xD3)
     //  * Unknown fields and flags (0x89, 0x8A - 0x8B)
     //  * [Gen 5] More unknown fields (0xD4 - 0xDB)
+    // They are kept in `original_bytes` when deserializing, and written back when serializing.
 
     /// Meta-data storing whether this Pokémon is shiny, for printing purposes.
     is_shiny: bool,
     /// Meta-data storing whether this Pokémon is of Generation 5, for convenience reasons.
     #[get_copy = "pub"]
     is_gen5: bool,
+    /// Meta-data storing the data the Pokémon was deserialized from, if any, so that the fields
+    /// not handled by this struct are written back unchanged when serializing it.
+    #[get = "pub"]
+    original_bytes: Option<Vec<u8>>,
 }
 
 impl Pokemon {
@@ -142,6 +322,7 @@ impl Pokemon {
             pid
         );
         self.is_shiny = self.is_shiny();
+        self.recompute_stats();
     }
 
     /// Sets the nature for the Pokémon.
@@ -155,6 +336,7 @@ impl Pokemon {
         // Update PID and other features based on it:
         self.pid = self.pid.wrapping_sub(self.nature.id_and_name.id() as u32);
         self.is_shiny = self.is_shiny();
+        self.recompute_stats();
     }
 
     /// Sets the experience points of the Pokémon.
@@ -162,10 +344,82 @@ impl Pokemon {
     /// The experience points are used to calculate the Pokémon's level. Therefore, it cannot be
     /// set directly.
     ///
-    /// **This function modifies the Pokémon's level.** See `self.level()`.
+    /// **This function modifies the Pokémon's level, and its stats if it is a party Pokémon.** See
+    /// `self.level()`.
     pub fn set_experience(&mut self, experience: u32) {
         self.experience = experience;
         self.level = self.level_from_xp();
+        self.recompute_stats();
+    }
+
+    /// Sets the IVs of the Pokémon, checking that each one is within the range the games allow
+    /// (0 to 31).
+    ///
+    /// Prefer it to setting the `ivs` field directly: if the Pokémon is a party Pokémon, its stats
+    /// are regenerated from the new IVs.
+    ///
+    /// # Arguments
+    /// * `ivs` - The new IVs.
+    ///
+    /// Returns `GtsError::InvalidValue` if any IV is over 31, leaving the Pokémon unchanged.
+    pub fn set_ivs(&mut self, ivs: StatsFeature) -> Result<()> {
+        if let Some(stat) = ALL_STATS.iter().find(|stat| ivs.get(stat) > MAX_IV) {
+            return Err(GtsError::InvalidValue(format!(
+                "{} IV {} is over the maximum of {}",
+                stat,
+                ivs.get(stat),
+                MAX_IV
+            )));
+        }
+
+        self.ivs = ivs;
+        self.recompute_stats();
+        Ok(())
+    }
+
+    /// Sets the EVs of the Pokémon, checking that they are within the limits of a legitimately
+    /// trained Pokémon: 252 in each stat (the EVs past it never raise the stat), and 510 in total.
+    ///
+    /// Prefer it to setting the `evs` field directly: if the Pokémon is a party Pokémon, its stats
+    /// are regenerated from the new EVs. See also `self.clamp_evs()`.
+    ///
+    /// # Arguments
+    /// * `evs` - The new EVs.
+    ///
+    /// Returns `GtsError::InvalidValue` if the EVs of a stat, or their total, are over the maximum,
+    /// leaving the Pokémon unchanged.
+    pub fn set_evs(&mut self, evs: StatsFeature) -> Result<()> {
+        if let Some(stat) = ALL_STATS.iter().find(|stat| evs.get(stat) > MAX_STAT_EVS) {
+            return Err(GtsError::InvalidValue(format!(
+                "{} EVs {} are over the maximum of {}",
+                stat,
+                evs.get(stat),
+                MAX_STAT_EVS
+            )));
+        }
+        let total = ALL_STATS.iter().map(|stat| evs.get(stat)).sum::<u16>();
+        if total > MAX_TOTAL_EVS {
+            return Err(GtsError::InvalidValue(format!(
+                "Total EVs {} are over the maximum of {}",
+                total, MAX_TOTAL_EVS
+            )));
+        }
+
+        self.evs = evs;
+        self.recompute_stats();
+        Ok(())
+    }
+
+    /// Recomputes the stats of the Pokémon from its species, level, IVs, EVs, and nature, if it is
+    /// a party Pokémon. Boxed Pokémon have no stats to recompute. See `self.is_boxed()`.
+    ///
+    /// The setters of these features already call it, but it must be called after changing the
+    /// public `species`, `ivs` or `evs` fields of a party Pokémon directly: its stored stats are
+    /// serialized as they are.
+    pub fn recompute_stats(&mut self) {
+        if !self.is_boxed() {
+            self.stats = Some(self.generate_stats());
+        }
     }
 
     /// Calculates the level of the Pokémon from its experience points.
@@ -174,46 +428,172 @@ impl Pokemon {
     /// experience points. See `self.experience`.
     fn level_from_xp(&self) -> u8 {
         // Retrieve the species, experience type, and current experience:
-        let pkm_id = self.species.id() as usize;
-        let exp_type = should_be_some!(BASE_STATS.get(pkm_id), "Invalid species ID")[0];
+        let exp_type = self.base_stats()[0];
         let exp = self.experience;
 
-        // Iteratively check what level corresponds to the current experience:
-        for i in 1..100 {
-            let xp_needed = LEVEL_CURVES[i][exp_type as usize];
+        // Find the first level whose experience is not reached; the Pokémon is one level below.
+        // The level curves are indexed by level, so that level 1 needs `LEVEL_CURVES[1]`:
+        for level in 2..=100 {
+            let xp_needed = LEVEL_CURVES[level][exp_type as usize];
             if xp_needed > exp {
-                return i as u8;
+                return (level - 1) as u8;
             }
         }
 
         100
     }
 
+    /// Checks whether the Pokémon's level is the one its experience points correspond to, per the
+    /// experience curve of its species.
+    ///
+    /// Edited Pokémon may have only one of them changed. The games compute the level from the
+    /// experience points, which are therefore authoritative. See `self.normalize_level()`.
+    pub fn level_matches_experience(&self) -> bool {
+        self.level == self.level_from_xp()
+    }
+
+    /// Recomputes the Pokémon's level from its experience points, which are authoritative, as the
+    /// games compute the level from them. A warning is logged if the level changes.
+    ///
+    /// The stats of party Pokémon are recomputed for the new level, as the games do when a Pokémon
+    /// levels up.
+    ///
+    /// Returns whether the level changed.
+    pub fn normalize_level(&mut self) -> bool {
+        let level = self.level_from_xp();
+        if level == self.level {
+            return false;
+        }
+
+        log::warn!(
+            "{} had level {}, but its experience ({}) is of level {}; level corrected",
+            self.species.name(),
+            self.level,
+            self.experience,
+            level
+        );
+        self.level = level;
+        self.recompute_stats();
+        true
+    }
+
+    /// Gets the experience type and base stats of the Pokémon's species, as stored in the base
+    /// stats table.
+    ///
+    /// If the species is missing from the table (e.g., a species from a later generation in
+    /// corrupt data), a warning is logged and neutral values are returned instead: the Medium Fast
+    /// experience curve, and no base stats.
+    fn base_stats(&self) -> [u8; 7] {
+        BASE_STATS
+            .get(self.species.id() as usize)
+            .copied()
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "No base stats for species ID {}; using neutral ones",
+                    self.species.id()
+                );
+                FALLBACK_BASE_STATS
+            })
+    }
+
     /// Sets the name of the Pokémon.
     ///
-    /// Pokemon names have to be limited to 10 characters.
+    /// Pokémon names are limited to 10 characters, and must be representable in the character
+    /// encoding of the Pokémon's generation. See `validate_name`.
     ///
     /// # Arguments
     /// * `name` - The name to set for the Pokémon.
     ///
-    /// Returns an error of kind `InvalidData` if the name is longer than 10 characters, or if it
-    /// contains characters not representable in the Gen 4 character encoding.
+    /// Returns `GtsError::InvalidName` if the name is longer than 10 characters, or
+    /// `GtsError::Encode` if it cannot be encoded for the Pokémon's generation.
     pub fn set_name(&mut self, name: String) -> Result<()> {
-        if name.len() > 10 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "Pokémon names cannot be longer than 10 characters",
-            ));
-        }
-
-        // Assert the name does not contain invalid characters:
-        let _ = Self::encode_name_gen4(&name)?;
+        Self::validate_name(&name, MAX_NAME_CHARS, self.is_gen5, "Pokémon")?;
 
         self.name = name;
 
         Ok(())
     }
 
+    /// Sets the name of the Pokémon's original trainer.
+    ///
+    /// Trainer names are limited to 7 characters, unlike the Pokémon's names, and must be
+    /// representable in the character encoding of the Pokémon's generation. See
+    /// `validate_name`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the original trainer.
+    ///
+    /// Returns `GtsError::InvalidName` if the name is longer than 7 characters, or
+    /// `GtsError::Encode` if it cannot be encoded for the Pokémon's generation.
+    pub fn set_trainer_name(&mut self, name: &str) -> Result<()> {
+        Self::validate_name(name, MAX_TRAINER_NAME_CHARS, self.is_gen5, "Trainer")?;
+
+        self.trainer_name = name.to_string();
+
+        Ok(())
+    }
+
+    /// Checks that a name fits in the games: that it is not too long, and that it can be encoded
+    /// for the generation, with the Gen 4 custom encoding, or UTF-16 without surrogate pairs in
+    /// Gen 5.
+    ///
+    /// The length is counted in characters, not bytes, so multibyte names (e.g., Japanese ones)
+    /// have the same limit as the others.
+    ///
+    /// # Arguments
+    /// * `name` - The name to check.
+    /// * `max_chars` - The maximum number of characters of the name.
+    /// * `is_gen5` - Whether the name is for a Gen 5 game, instead of a Gen 4 one.
+    /// * `owner` - Whose name it is (e.g., "Pokémon" or "Trainer"), for the error message.
+    ///
+    /// Returns `GtsError::InvalidName` if the name is too long, or `GtsError::Encode` if it
+    /// cannot be encoded for the generation.
+    pub(crate) fn validate_name(
+        name: &str,
+        max_chars: usize,
+        is_gen5: bool,
+        owner: &str,
+    ) -> Result<()> {
+        if name.chars().count() > max_chars {
+            return Err(GtsError::InvalidName(format!(
+                "{} names cannot be longer than {} characters",
+                owner, max_chars
+            )));
+        }
+
+        if !is_gen5 {
+            let _ = Self::encode_name_gen4(name)?;
+        } else if let Some(c) = name.chars().find(|&c| c.len_utf16() > 1 || c == '\u{FFFF}') {
+            return Err(GtsError::Encode(format!(
+                "Invalid character in name: {}",
+                c
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Creates a copy of the Pokémon with a different original trainer.
+    ///
+    /// The PID (and thus the nature) is kept. Therefore, the copy will usually not be shiny under
+    /// the new trainer IDs, even if the original Pokémon was.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the new original trainer. Limited to 7 characters.
+    /// * `tid` - The Trainer ID of the new original trainer.
+    /// * `sid` - The Trainer Secret ID of the new original trainer.
+    ///
+    /// Returns the same errors as `set_trainer_name`.
+    pub fn clone_with_new_ot(&self, name: &str, tid: u16, sid: u16) -> Result<Pokemon> {
+        let mut pokemon = self.clone();
+        pokemon.set_trainer_name(name)?;
+        pokemon.trainer_id = tid;
+        pokemon.trainer_secret_id = sid;
+        pokemon.is_shiny = pokemon.is_shiny();
+
+        Ok(pokemon)
+    }
+
     /// Returns the sequence of bytes corresponding to the internal Gen 4 representation of a name,
     /// be it the Pokémon's name, or the Trainer's name.
     ///
@@ -222,16 +602,16 @@ impl Pokemon {
     /// # Arguments
     /// * `name` - The name to encode.
     ///
-    /// Returns an error of kind `InvalidData` if the name contains characters not representable in
-    /// the Gen 4 character encoding.
-    pub fn encode_name_gen4(name: &String) -> Result<Vec<u8>> {
+    /// Returns `GtsError::Encode` if the name contains characters not representable in the Gen 4
+    /// character encoding.
+    pub fn encode_name_gen4(name: &str) -> Result<Vec<u8>> {
         let mut encoded_name = Vec::with_capacity(20);
 
         // Encode each character in the name using the charmap, and add the null terminator:
         for c in name.chars() {
             let &chr = CHARMAP
                 .get_by_right(&c)
-                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid character in name"))?;
+                .ok_or_else(|| GtsError::Encode(format!("Invalid character in name: {}", c)))?;
             encoded_name.extend(chr.to_le_bytes());
         }
         encoded_name.push(0xFF);
@@ -244,6 +624,12 @@ impl Pokemon {
     /// be it the Pokémon's name, or the Trainer's name.
     ///
     /// The Gen 4 uses a custom character encoding for names.
+    ///
+    /// # Arguments
+    /// * `name` - The name to decode.
+    ///
+    /// Returns `GtsError::Decode` if the name contains characters not representable in the Gen 4
+    /// character encoding.
     pub fn decode_name_gen4(name: &[u8]) -> Result<String> {
         let mut decoded_name = String::with_capacity(10);
 
@@ -258,10 +644,10 @@ impl Pokemon {
             match CHARMAP.get_by_left(&char_code) {
                 Some(&chr) => decoded_name.push(chr),
                 None => {
-                    return Err(Error::new(
-                        ErrorKind::InvalidData,
-                        "Invalid character in name",
-                    ))
+                    return Err(GtsError::Decode(format!(
+                        "Invalid character in name: {:#06X}",
+                        char_code
+                    )))
                 }
             };
         }
@@ -276,7 +662,7 @@ impl Pokemon {
     ///
     /// # Arguments
     /// * `name` - The name to encode.
-    pub fn encode_name_gen5(name: &String) -> Vec<u8> {
+    pub fn encode_name_gen5(name: &str) -> Vec<u8> {
         // Encode characters to UTF-16:
         let mut encoded_name = name
             .encode_utf16()
@@ -292,61 +678,879 @@ impl Pokemon {
     /// Returns the String corresponding to the internal Gen 5 representation of a name,
     /// be it the Pokémon's name, or the Trainer's name.
     ///
-    /// The Gen 5 uses UTF-16 encoding for names, with 0xffff as null terminator.
+    /// The Gen 5 uses UTF-16 encoding for names, with 0xffff as null terminator. A name that fills
+    /// the whole buffer may have no terminator, in which case it ends with the buffer. Whatever
+    /// follows the terminator (usually padding) is ignored.
     ///
     /// # Arguments
     /// * `name` - The name to decode.
     ///
-    /// Returns an error of kind `InvalidData` if the name is not encodeable in UTF-16, or if it
-    /// does not end in the null terminator.
+    /// Returns `GtsError::Decode` if the name is empty, or not encodeable in UTF-16.
     pub fn decode_name_gen5(name: &[u8]) -> Result<String> {
-        // Transform bytes to UTF-16 characters and try to decode:
-        let byte_chars = name
-            .chunks(2)
+        // Transform bytes to UTF-16 characters, up to the null terminator (0xffff), if any:
+        let chars = name
+            .chunks_exact(2)
             .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .take_while(|&char_code| char_code != 0xffff)
             .collect::<Vec<u16>>();
-        let decoding_result = String::from_utf16(&byte_chars);
 
-        if let Err(e) = decoding_result {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Invalid UTF-16 encoding: {}", e),
-            ));
+        if chars.is_empty() {
+            return Err(GtsError::Decode("Gen 5 name is empty".to_string()));
         }
-        let decoded_name = should_be_ok!(decoding_result, "Invalid UTF-16 encoding");
 
-        // Assert the name is null-terminated (0xffff):
-        let split = decoded_name.split("\u{ffff}").collect::<Vec<&str>>();
-        if split.len() < 2 || split[0].is_empty() {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "Gen 5 name does not end with null terminator",
-            ));
-        }
-
-        Ok(split[0].to_string())
+        String::from_utf16(&chars)
+            .map_err(|e| GtsError::Decode(format!("Invalid UTF-16 encoding: {}", e)))
     }
 
     /// Gets whether the Pokémon is shiny.
     ///
-    /// Shininess is dependent on the Pokémon's PID, Trainer ID, and Trainer Secret ID.
+    /// Shininess is dependent on the Pokémon's PID, Trainer ID, and Trainer Secret ID: the Pokémon
+    /// is shiny if its shiny value is below 8. See `self.shiny_value()`.
     pub fn is_shiny(&self) -> bool {
-        let pid = self.pid();
-        let tid = self.trainer_id;
-        let sid = self.trainer_secret_id;
+        self.shiny_value() < SHINY_THRESHOLD
+    }
 
-        // Compute the shiny formula:
+    /// Gets the shiny value of the Pokémon: the XOR of its Trainer ID, Trainer Secret ID, and the
+    /// upper and lower halves of its PID.
+    ///
+    /// The lower the value, the "shinier" the Pokémon; it is shiny if the value is below 8.
+    pub fn shiny_value(&self) -> u16 {
+        let pid = self.pid();
         let pid_high = (pid >> 16) as u16;
         let pid_low = (pid & 0xFFFF) as u16;
-        let tid_xor = tid ^ sid;
-        let pid_xor = pid_high ^ pid_low;
-        tid_xor ^ pid_xor < 8
+
+        self.trainer_id ^ self.trainer_secret_id ^ pid_high ^ pid_low
+    }
+
+    /// Gets whether the Pokémon is a "square" shiny, i.e., its shiny value is 0.
+    ///
+    /// Square and "star" shinies (shiny values 1 to 7) look the same in Gen 4 and Gen 5 games, but
+    /// the games since Sword and Shield tell them apart, with square-shaped sparkles for the former.
+    pub fn is_square_shiny(&self) -> bool {
+        self.shiny_value() == 0
+    }
+
+    /// Gets the Pokérus strain of the Pokémon.
+    ///
+    /// The strain is stored in the upper 4 bits of the Pokérus byte. A strain of 0 means the
+    /// Pokémon has never been infected.
+    pub fn pokerus_strain(&self) -> u8 {
+        self.pokerus >> 4
+    }
+
+    /// Gets the days remaining until the Pokémon is cured from Pokérus.
+    ///
+    /// The days remaining are stored in the lower 4 bits of the Pokérus byte. They are only
+    /// meaningful if the Pokémon has ever been infected (see `self.pokerus_strain()`).
+    pub fn pokerus_days_remaining(&self) -> u8 {
+        self.pokerus & 0x0F
+    }
+
+    /// Gets whether the Pokémon is currently infected with Pokérus.
+    ///
+    /// That is, whether it has a strain and days remaining until it is cured.
+    pub fn has_pokerus(&self) -> bool {
+        self.pokerus_strain() != 0 && self.pokerus_days_remaining() != 0
+    }
+
+    /// Gets whether the Pokémon has been cured from Pokérus.
+    ///
+    /// That is, whether it has a strain but no days remaining until it is cured.
+    pub fn is_pokerus_cured(&self) -> bool {
+        self.pokerus_strain() != 0 && self.pokerus_days_remaining() == 0
+    }
+
+    /// Sets the Pokérus strain of the Pokémon, keeping the days remaining.
+    ///
+    /// # Arguments
+    /// * `strain` - The strain to set, between 0 and 15.
+    ///
+    /// Returns `GtsError::InvalidValue` if the strain does not fit in 4 bits.
+    pub fn set_pokerus_strain(&mut self, strain: u8) -> Result<()> {
+        if strain > 0x0F {
+            return Err(GtsError::InvalidValue(
+                "Pokérus strain must be between 0 and 15".to_string(),
+            ));
+        }
+
+        self.pokerus = (strain << 4) | self.pokerus_days_remaining();
+
+        Ok(())
+    }
+
+    /// Sets the days remaining until the Pokémon is cured from Pokérus, keeping the strain.
+    ///
+    /// Setting the days remaining to 0 for a Pokémon with a strain marks it as cured.
+    ///
+    /// # Arguments
+    /// * `days` - The days remaining to set, between 0 and 15.
+    ///
+    /// Returns `GtsError::InvalidValue` if the days do not fit in 4 bits.
+    pub fn set_pokerus_days_remaining(&mut self, days: u8) -> Result<()> {
+        if days > 0x0F {
+            return Err(GtsError::InvalidValue(
+                "Pokérus days remaining must be between 0 and 15".to_string(),
+            ));
+        }
+
+        self.pokerus = (self.pokerus & 0xF0) | days;
+
+        Ok(())
+    }
+
+    /// Gets the gender threshold of the Pokémon's species.
+    ///
+    /// The Pokémon is female if the lowest byte of its PID is lower than the threshold, and male
+    /// otherwise. The special values 0, 254, and 255 stand for male-only, female-only, and
+    /// genderless species, respectively.
+    pub fn gender_ratio(&self) -> u8 {
+        *should_be_some!(
+            GENDER_RATIOS.get(self.species.id() as usize),
+            "Invalid species ID: {}",
+            self.species.id()
+        )
+    }
+
+    /// Gets the gender the Pokémon must have, according to its species and PID.
+    pub fn expected_gender(&self) -> Gender {
+        match self.gender_ratio() {
+            0 => Gender::Male,
+            254 => Gender::Female,
+            255 => Gender::Genderless,
+            threshold if ((self.pid & 0xFF) as u8) < threshold => Gender::Female,
+            _ => Gender::Male,
+        }
+    }
+
+    /// Sets the gender of the Pokémon.
+    ///
+    /// The gender must be one the Pokémon can have: genderless for genderless species, the only
+    /// possible gender for single-gender species, or the gender implied by the PID otherwise.
+    /// Prefer this method over setting `gender` directly.
+    ///
+    /// # Arguments
+    /// * `gender` - The gender to set.
+    ///
+    /// Returns `GtsError::InvalidValue` if the Pokémon cannot have the given gender.
+    pub fn set_gender(&mut self, gender: Gender) -> Result<()> {
+        self.check_gender(gender)?;
+        self.gender = gender;
+
+        Ok(())
+    }
+
+    /// Checks whether the Pokémon can have the given gender, according to its species and PID.
+    ///
+    /// # Arguments
+    /// * `gender` - The gender to check.
+    ///
+    /// Returns `GtsError::InvalidValue` if the Pokémon cannot have the given gender.
+    fn check_gender(&self, gender: Gender) -> Result<()> {
+        let expected = self.expected_gender();
+        if gender == expected {
+            return Ok(());
+        }
+
+        let reason = match self.gender_ratio() {
+            0 => "the species is male-only".to_string(),
+            254 => "the species is female-only".to_string(),
+            255 => "the species is genderless".to_string(),
+            _ => format!("its PID ({:#010X}) implies {}", self.pid, expected),
+        };
+
+        Err(GtsError::InvalidValue(format!(
+            "{} cannot be {}: {}",
+            self.species.name(),
+            gender,
+            reason
+        )))
+    }
+
+    /// Checks whether the Pokémon's gender is the one implied by its PID and species' gender ratio.
+    ///
+    /// See `self.expected_gender()`.
+    pub fn pid_matches_gender(&self) -> bool {
+        self.gender == self.expected_gender()
+    }
+
+    /// Gets the regular ability slot (0 or 1) selected by the Pokémon's PID.
+    ///
+    /// Pokémon from Gen 3 and Gen 4 games select it with the bit 0 of the PID, while Pokémon from
+    /// Gen 5 games select it with the bit 16.
+    pub fn pid_ability_slot(&self) -> u8 {
+        if self.origin_generation() == 5 {
+            ((self.pid >> 16) & 1) as u8
+        } else {
+            (self.pid & 1) as u8
+        }
+    }
+
+    /// Gets the IDs of the abilities of the Pokémon's species and form: the first and second
+    /// regular abilities, and the hidden ability.
+    ///
+    /// The second and hidden IDs are 0 if the species does not have them.
+    fn species_abilities(&self) -> [u16; 3] {
+        match (self.species.id(), self.form_id) {
+            (487, 1) => [26, 0, 0], // Giratina (Origin Forme): Levitate.
+            (492, 1) => [32, 0, 0], // Shaymin (Sky Forme): Serene Grace.
+            (species_id, _) => *should_be_some!(
+                SPECIES_ABILITIES.get(species_id as usize),
+                "Invalid species ID: {}",
+                species_id
+            ),
+        }
+    }
+
+    /// Gets the regular ability selected by the Pokémon's PID, among those of its species.
+    ///
+    /// See `self.pid_ability_slot()`.
+    fn pid_ability(&self) -> u16 {
+        let [first, second, _] = self.species_abilities();
+        if second == 0 || self.pid_ability_slot() == 0 {
+            first
+        } else {
+            second
+        }
+    }
+
+    /// Checks whether the Pokémon has the hidden ability of its species.
+    ///
+    /// Species whose hidden ability is also the regular ability selected by the PID are not
+    /// considered to have it, as it cannot be told apart.
+    pub fn has_hidden_ability(&self) -> bool {
+        let [_, _, hidden] = self.species_abilities();
+        let ability = self.ability.id();
+
+        hidden != 0 && ability == hidden && ability != self.pid_ability()
+    }
+
+    /// Gets the abilities the Pokémon can legally have, according to its species, PID, and origin
+    /// game.
+    ///
+    /// * Pokémon from Gen 4 games can only have the regular ability selected by their PID.
+    /// * Pokémon from Gen 3 games can have either regular ability, as some species only had one
+    ///   in Gen 3, which is kept regardless of the PID.
+    /// * Pokémon from Gen 5 games can have the regular ability selected by their PID, or the
+    ///   hidden ability of their species.
+    pub fn legal_abilities(&self) -> Vec<IdFeature> {
+        let [first, second, hidden] = self.species_abilities();
+        let ids = match self.origin_generation() {
+            3 => vec![first, second],
+            4 => vec![self.pid_ability()],
+            _ => vec![self.pid_ability(), hidden],
+        };
+
+        ids.into_iter()
+            .filter(|&id| id != 0)
+            .map(|id| should_be_some!(IdFeature::from_ability_id(id), "Invalid ability ID: {}", id))
+            .collect()
+    }
+
+    /// Fixes the Pokémon's ability, if it is not one it can legally have.
+    ///
+    /// The ability is set to the regular ability selected by the PID. See
+    /// `self.legal_abilities()`.
+    ///
+    /// Returns whether the ability was changed.
+    pub fn fix_ability(&mut self) -> bool {
+        let ability = self.ability.id();
+        if self
+            .legal_abilities()
+            .iter()
+            .any(|legal| legal.id() == ability)
+        {
+            return false;
+        }
+
+        let pid_ability = self.pid_ability();
+        self.ability = should_be_some!(
+            IdFeature::from_ability_id(pid_ability),
+            "Invalid ability ID: {}",
+            pid_ability
+        );
+
+        true
+    }
+
+    /// Checks that the nature and ability of the Pokémon are consistent with its PID.
+    ///
+    /// * The nature is derived from the PID (PID mod 25) for Pokémon from Gen 3 and Gen 4 games.
+    ///   Gen 5 games store the nature separately, so any nature is valid for Pokémon from them.
+    /// * The ability must be one of those the Pokémon can legally have. See
+    ///   `self.legal_abilities()`.
+    ///
+    /// Returns `GtsError::InvalidValue` describing the inconsistency, if any.
+    fn check_pid_consistency(&self) -> Result<()> {
+        if self.origin_generation() != 5 && !self.nature_matches_pid() {
+            return Err(GtsError::InvalidValue(format!(
+                "{} cannot have nature {}: its PID ({:#010X}) implies {}",
+                self.species.name(),
+                self.nature.id_and_name.name(),
+                self.pid,
+                self.pid_nature().id_and_name.name()
+            )));
+        }
+
+        let ability = self.ability.id();
+        if self
+            .legal_abilities()
+            .iter()
+            .all(|legal| legal.id() != ability)
+        {
+            let [first, second, hidden] = self.species_abilities();
+            let reason = if ability == 0 {
+                "it is not an ability of the species".to_string()
+            } else if ability == first || ability == second {
+                format!(
+                    "its PID ({:#010X}) selects ability slot {}",
+                    self.pid,
+                    self.pid_ability_slot() + 1
+                )
+            } else if ability == hidden {
+                "hidden abilities are only obtainable in Gen 5 games".to_string()
+            } else {
+                "it is not an ability of the species".to_string()
+            };
+            return Err(GtsError::InvalidValue(format!(
+                "{} cannot have ability {}: {}",
+                self.species.name(),
+                self.ability.name(),
+                reason
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Gets the name of the Pokémon's species, e.g., "Turtwig".
+    pub fn species_name(&self) -> &str {
+        self.species.name()
+    }
+
+    /// Gets the name of the Pokémon's ability.
+    pub fn ability_name(&self) -> &str {
+        self.ability.name()
+    }
+
+    /// Gets the name of the Pokémon's nature.
+    pub fn nature_name(&self) -> &str {
+        self.nature.id_and_name.name()
+    }
+
+    /// Gets the nature stored in the Pokémon's data, at 0x41, by Gen 5 games.
+    ///
+    /// Returns `None` for Gen 4 Pokémon, whose nature is not stored, but derived from the PID.
+    /// See `self.pid_nature()`.
+    pub fn stored_nature(&self) -> Option<&Nature> {
+        self.is_gen5.then_some(&self.nature)
+    }
+
+    /// Gets the nature derived from the Pokémon's PID (PID mod 25), which is the actual nature of
+    /// Gen 4 Pokémon, and of Gen 3 and Gen 4 Pokémon transferred to Gen 5.
+    pub fn pid_nature(&self) -> Nature {
+        let nature_id = (self.pid % 25) as u16;
+        should_be_some!(
+            Nature::from_id(nature_id),
+            "Invalid nature ID: {}",
+            nature_id
+        )
+    }
+
+    /// Checks whether the Pokémon's nature is the one derived from its PID.
+    ///
+    /// It always is for Gen 4 Pokémon. Gen 5 games store the nature apart from the PID, so it may
+    /// differ for Pokémon from them; but not for Pokémon transferred from Gen 3 or Gen 4 games,
+    /// which makes a mismatch a legality signal for them.
+    pub fn nature_matches_pid(&self) -> bool {
+        self.nature.id_and_name.id() == self.pid_nature().id_and_name.id()
+    }
+
+    /// Gets the name of the item held by the Pokémon, "Nothing" if it holds none.
+    pub fn held_item_name(&self) -> &str {
+        self.held_item.name()
+    }
+
+    /// Builds a JSON object with the human-relevant features of the Pokémon: species, nickname,
+    /// level, nature, ability, moves, held item, EVs, IVs, shininess, original trainer, and met
+    /// info.
+    ///
+    /// Unlike `PokemonSummary`, it is not a stable schema: fields may be added to it as needed.
+    pub fn summary_json(&self) -> JsonValue {
+        let stats_json = |stats: &StatsFeature| {
+            json!({
+                "hp": stats.hp,
+                "atk": stats.atk,
+                "def": stats.def,
+                "spa": stats.spa,
+                "spd": stats.spd,
+                "spe": stats.spe,
+            })
+        };
+
+        json!({
+            "species": self.species_name(),
+            "species_id": self.species.id(),
+            "nickname": self.name(),
+            "level": self.level,
+            "nature": self.nature_name(),
+            "ability": self.ability_name(),
+            "moves": self.known_moves().iter().map(|mov| mov.name()).collect::<Vec<_>>(),
+            "held_item": self.held_item_name(),
+            "evs": stats_json(&self.evs),
+            "ivs": stats_json(&self.ivs),
+            "shiny": self.is_shiny(),
+            "ot_name": self.trainer_name(),
+            "tid": self.trainer_id,
+            "sid": self.trainer_secret_id,
+            "met": {
+                "location": self.met_location.to_string(),
+                "level": self.met_level,
+                "date": self.met_date.to_string(),
+                "game": self.origin_game.to_string(),
+            },
+            "generation": if self.is_gen5 { 5 } else { 4 },
+        })
+    }
+
+    /// Gets the number of days since the Pokémon was met, e.g., for "caught 1204 days ago".
+    ///
+    /// Returns a negative number if the met date is in the future, per the local clock.
+    pub fn days_since_met(&self) -> i64 {
+        Self::days_since(self.met_date, LocalTime::now().date_naive())
+    }
+
+    /// Gets the number of days since the Pokémon's egg was received.
+    ///
+    /// Returns `None` if the Pokémon was not hatched from an egg.
+    pub fn days_since_egg(&self) -> Option<i64> {
+        let today = LocalTime::now().date_naive();
+        self.egg_date.map(|date| Self::days_since(date, today))
+    }
+
+    /// Computes the number of days from a date to another.
+    ///
+    /// # Arguments
+    /// * `date` - The earlier date.
+    /// * `today` - The later date.
+    fn days_since(date: NaiveDate, today: NaiveDate) -> i64 {
+        today.signed_duration_since(date).num_days()
+    }
+
+    /// Gets the moves the Pokémon knows, skipping its empty move slots.
+    pub fn known_moves(&self) -> Vec<&IdFeature> {
+        self.moves.iter().filter(|mov| mov.id() != 0).collect()
+    }
+
+    /// Sets the moves of the Pokémon, by name.
+    ///
+    /// The remaining move slots are left empty. The PP and PP Ups of the moves are not modified.
+    ///
+    /// # Arguments
+    /// * `names` - The names of the moves to set, at most 4.
+    ///
+    /// Returns `GtsError::InvalidValue` if more than 4 moves are given, if any of them is not a
+    /// valid move name, or if there is an empty move slot ("None") before a move.
+    pub fn set_moves(&mut self, names: &[&str]) -> Result<()> {
+        if names.len() > 4 {
+            return Err(GtsError::InvalidValue(format!(
+                "A Pokémon cannot know more than 4 moves, got {}",
+                names.len()
+            )));
+        }
+
+        let mut moves: [IdFeature; 4] = Default::default();
+        for (slot, name) in moves.iter_mut().zip(names) {
+            *slot = IdFeature::from_move_name(name)
+                .ok_or_else(|| GtsError::InvalidValue(format!("Unknown move: {}", name)))?;
+        }
+        for slot in moves.iter_mut().skip(names.len()) {
+            *slot = should_be_some!(
+                IdFeature::from_move_id(0),
+                "Could not get `None` move from moves map"
+            );
+        }
+        Self::check_move_slots(&moves)?;
+
+        self.moves = moves;
+
+        Ok(())
+    }
+
+    /// Sets the item held by the Pokémon, by name.
+    ///
+    /// Item IDs differ between Gen 4 and Gen 5 games, so the item is looked up in the items of the
+    /// Pokémon's generation.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the item to hold, as named in the Pokémon's generation; "Nothing" to
+    ///   hold no item.
+    ///
+    /// Returns `GtsError::InvalidValue` if the item does not exist in the Pokémon's generation.
+    pub fn set_held_item(&mut self, name: &str) -> Result<()> {
+        let item = if self.is_gen5 {
+            IdFeature::from_gen5_item_name(name)
+        } else {
+            IdFeature::from_gen4_item_name(name)
+        };
+
+        let generation = if self.is_gen5 { 5 } else { 4 };
+        self.held_item = item.ok_or_else(|| {
+            GtsError::InvalidValue(format!("Unknown Gen {} item: {}", generation, name))
+        })?;
+
+        Ok(())
+    }
+
+    /// Computes the maximum PP of the move in a move slot, with the PP Ups applied to it.
+    ///
+    /// Each PP Up raises the maximum PP by a fifth of the move's base PP, up to 3 PP Ups. The base
+    /// PP are those of the Pokémon's generation, as a few moves have less PP in Gen 5.
+    ///
+    /// # Arguments
+    /// * `slot` - The move slot, from 0 to 3.
+    ///
+    /// Returns the maximum PP, 0 for an empty move slot, or `None` if the slot or the move does
+    /// not exist.
+    pub fn max_pp(&self, slot: usize) -> Option<u8> {
+        let move_id = self.moves.get(slot)?.id();
+        if move_id == 0 {
+            return Some(0);
+        }
+
+        let gen4_base_pp = GEN4_BASE_PP_CHANGES
+            .iter()
+            .find(|(id, _)| !self.is_gen5 && *id == move_id)
+            .map(|(_, pp)| *pp);
+        let base_pp = gen4_base_pp.or_else(|| MOVE_BASE_PP.get(move_id as usize).copied())?;
+        let pp_ups = self.move_pp_ups[slot].min(MAX_PP_UPS);
+
+        Some(base_pp + base_pp * pp_ups / 5)
+    }
+
+    /// Checks that there are no moves after an empty move slot, as the games keep moves compacted.
+    ///
+    /// # Arguments
+    /// * `moves` - The move slots to check.
+    ///
+    /// Returns `GtsError::InvalidValue` if there is a move after an empty move slot.
+    fn check_move_slots(moves: &[IdFeature; 4]) -> Result<()> {
+        let misplaced_move = moves
+            .iter()
+            .skip_while(|mov| mov.id() != 0)
+            .find(|mov| mov.id() != 0);
+        if let Some(mov) = misplaced_move {
+            return Err(GtsError::InvalidValue(format!(
+                "Move {} comes after an empty move slot",
+                mov.name()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Gets the ID of the last move that exists in a generation.
+    ///
+    /// # Arguments
+    /// * `is_gen5` - Whether to get the last move of Gen 5, instead of Gen 4.
+    pub fn max_move_id_for_gen(is_gen5: bool) -> u16 {
+        if is_gen5 {
+            LAST_GEN5_MOVE
+        } else {
+            LAST_GEN4_MOVE
+        }
+    }
+
+    /// Checks that the Pokémon only knows moves that exist in its generation, as the others would
+    /// be read as different moves (or none) by the games.
+    ///
+    /// Returns `GtsError::InvalidValue` if a move does not exist in the Pokémon's generation.
+    fn check_move_generation(&self) -> Result<()> {
+        let max_move_id = Self::max_move_id_for_gen(self.is_gen5);
+        if let Some(mov) = self.moves.iter().find(|mov| mov.id() > max_move_id) {
+            return Err(GtsError::InvalidValue(format!(
+                "Move {} does not exist in Gen {}",
+                mov.name(),
+                if self.is_gen5 { 5 } else { 4 }
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the games would accept the Pokémon through the GTS.
+    ///
+    /// The games reject Bad Eggs, Pokémon with a level outside of the 1-100 range, and Pokémon
+    /// whose data was corrupted (i.e., whose stored checksum does not match their data when they
+    /// were loaded).
+    ///
+    /// Returns all the issues found, if any.
+    pub fn is_valid_trade_target(&self) -> std::result::Result<(), Vec<ValidationIssue>> {
+        let mut issues = Vec::new();
+
+        if self.bad_egg_flag {
+            issues.push(ValidationIssue::BadEgg);
+        }
+        if !(1..=100).contains(&self.level) {
+            issues.push(ValidationIssue::InvalidLevel(self.level));
+        }
+        if self.original_checksum != self.computed_checksum {
+            issues.push(ValidationIssue::ChecksumMismatch {
+                stored: self.original_checksum,
+                computed: self.computed_checksum,
+            });
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Gets whether the Pokémon's ball could have been obtained in its origin game.
+    ///
+    /// For example, Dream Balls are only obtainable in Gen 5 games, and Apricorn balls only in
+    /// HeartGold and SoulSilver.
+    pub fn ball_legal_for_origin(&self) -> bool {
+        self.ball.is_obtainable_in(self.origin_game)
+    }
+
+    /// Checks whether the Pokémon is legal, as far as this library can tell.
+    ///
+    /// Currently, this checks that its gender matches its species' gender ratio and its PID, that
+    /// its nature and ability are consistent with its PID, that its moves are compacted and exist
+    /// in its generation, that its level matches its experience, and that its ball is obtainable
+    /// in its origin game. Then, the checks of the `legality` module are run: see
+    /// `check_origin_consistency`, `check_encounter_consistency`, `check_fateful_consistency`,
+    /// `check_hatch_consistency`, `check_nickname_consistency`, `check_move_pps`,
+    /// `check_hidden_ability_source` and `check_event_chain_consistency`.
+    ///
+    /// Returns `GtsError::InvalidValue` describing the first problem found, if any.
+    pub fn validate(&self) -> Result<()> {
+        self.check_gender(self.gender)?;
+        self.check_pid_consistency()?;
+        Self::check_move_slots(&self.moves)?;
+        self.check_move_generation()?;
+
+        if !self.level_matches_experience() {
+            return Err(GtsError::InvalidValue(format!(
+                "{} has level {}, but its experience ({}) is of level {}",
+                self.species.name(),
+                self.level,
+                self.experience,
+                self.level_from_xp()
+            )));
+        }
+
+        if !self.ball_legal_for_origin() {
+            return Err(GtsError::InvalidValue(format!(
+                "{} is not obtainable in {}",
+                self.ball, self.origin_game
+            )));
+        }
+
+        Self::first_legality_note(check_origin_consistency(self))?;
+        Self::first_legality_note(check_encounter_consistency(self))?;
+        Self::first_legality_note(check_fateful_consistency(self))?;
+        Self::first_legality_note(check_hatch_consistency(self))?;
+        Self::first_legality_note(check_nickname_consistency(self))?;
+        Self::first_legality_note(check_move_pps(self))?;
+        Self::first_legality_note(check_hidden_ability_source(self))?;
+        Self::first_legality_note(check_event_chain_consistency(self))?;
+
+        Ok(())
+    }
+
+    /// Turns the notes of a legality check into the result of `validate`.
+    ///
+    /// # Arguments
+    /// * `notes` - The issues found by the check.
+    ///
+    /// Returns `GtsError::InvalidValue` describing the first issue, if any.
+    fn first_legality_note(notes: Vec<LegalityNote>) -> Result<()> {
+        match notes.into_iter().next() {
+            Some(note) => Err(GtsError::InvalidValue(note.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Summarizes the signs of editing that can be seen at a glance: the EV total and the stats
+    /// over the EV cap, the number of perfect IVs, whether the checksum matched when the Pokémon
+    /// was loaded, and the first problem found by `validate`.
+    pub fn legality_summary(&self) -> LegalitySummary {
+        LegalitySummary {
+            ev_total: ALL_STATS.iter().map(|stat| self.evs.get(stat)).sum(),
+            over_cap_evs: ALL_STATS
+                .iter()
+                .map(|&stat| (stat, self.evs.get(&stat)))
+                .filter(|&(_, evs)| evs > MAX_STAT_EVS)
+                .collect(),
+            perfect_ivs: ALL_STATS
+                .iter()
+                .filter(|stat| self.ivs.get(stat) == MAX_IV)
+                .count() as u8,
+            checksum_matches: self.original_checksum == self.computed_checksum,
+            validation_error: self.validate().err().map(|e| e.to_string()),
+        }
+    }
+
+    /// Applies safe fixes to the Pokémon, so that it passes `validate`, as far as possible without
+    /// changing how it looks in the game:
+    /// * For Pokémon from Gen 3 and Gen 4 games, whose nature is derived from the PID, recomputes
+    ///   a PID consistent with the nature, keeping the gender, ability slot, and shininess.
+    /// * Clamps the EVs to 252 per stat and 510 in total.
+    /// * Fixes the ability to one the Pokémon can legally have. See `self.fix_ability()`.
+    /// * Recomputes the level from the experience points. See `self.normalize_level()`.
+    /// * Replaces a ball not obtainable in the origin game by a Poké Ball, unless the Pokémon
+    ///   comes from an event (fateful encounter), whose balls are not predictable.
+    ///
+    /// Returns a description of every change made.
+    pub fn auto_legalize(&mut self) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        let nature_id = self.nature.id_and_name.id() as u32;
+        let new_pid = (self.origin_generation() != 5 && self.pid % 25 != nature_id)
+            .then(|| self.nature_consistent_pid())
+            .flatten();
+        if let Some(pid) = new_pid {
+            changes.push(format!(
+                "PID changed from {:#010X} to {:#010X}, to match nature {}",
+                self.pid,
+                pid,
+                self.nature.id_and_name.name()
+            ));
+            self.set_pid(pid);
+        }
+
+        let evs = self.evs;
+        self.clamp_evs();
+        let clamped = [
+            Stat::Hp,
+            Stat::Atk,
+            Stat::Def,
+            Stat::Spe,
+            Stat::SpA,
+            Stat::SpD,
+        ]
+        .iter()
+        .filter(|stat| evs.get(stat) != self.evs.get(stat))
+        .map(|stat| format!("{} {} -> {}", stat, evs.get(stat), self.evs.get(stat)))
+        .collect::<Vec<String>>();
+        if !clamped.is_empty() {
+            changes.push(format!("EVs clamped: {}", clamped.join(", ")));
+        }
+
+        let ability = self.ability.name().clone();
+        if self.fix_ability() {
+            changes.push(format!(
+                "Ability changed from {} to {}",
+                ability,
+                self.ability.name()
+            ));
+        }
+
+        let level = self.level;
+        if self.normalize_level() {
+            changes.push(format!(
+                "Level changed from {} to {}, to match the experience",
+                level, self.level
+            ));
+        }
+
+        if !self.fateful && !self.ball_legal_for_origin() {
+            changes.push(format!(
+                "Ball changed from {} to {}, as the former is not obtainable in {}",
+                self.ball,
+                Pokeball::PokeBall,
+                self.origin_game
+            ));
+            self.ball = Pokeball::PokeBall;
+        }
+
+        changes
+    }
+
+    /// Finds a PID consistent with the Pokémon's nature, keeping the gender, ability slot, and
+    /// shininess implied by its current PID.
+    ///
+    /// The lowest byte of the PID, which sets the gender and the ability slot, is kept. For each
+    /// value of the other byte of the lower half, the upper half is solved so that the XOR of both
+    /// halves only changes in its lowest 3 bits, which keeps the shiny value on the same side of
+    /// the shiny threshold. These 2048 candidates cover every nature, for any PID and trainer.
+    ///
+    /// Returns `None` if no such PID exists.
+    fn nature_consistent_pid(&self) -> Option<u32> {
+        let nature_id = self.nature.id_and_name.id() as u32;
+        let low_byte = self.pid & 0xFF;
+        let halves_xor = (self.pid >> 16) ^ (self.pid & 0xFFFF);
+
+        (0..=0xFF)
+            .flat_map(|middle_byte| {
+                let low = (middle_byte << 8) | low_byte;
+                (0..SHINY_THRESHOLD as u32).map(move |bits| ((halves_xor ^ bits ^ low) << 16) | low)
+            })
+            .find(|pid| pid % 25 == nature_id)
+    }
+
+    /// Clamps the Pokémon's EVs to the maximum of each stat, and the maximum in total.
+    ///
+    /// If the total is exceeded, the EVs of the last stats (in the games' internal order: HP,
+    /// Attack, Defense, Speed, Special Attack, Special Defense) are reduced first. The stats of
+    /// party Pokémon are recomputed.
+    pub fn clamp_evs(&mut self) {
+        let mut remaining = MAX_TOTAL_EVS;
+        for ev in [
+            &mut self.evs.hp,
+            &mut self.evs.atk,
+            &mut self.evs.def,
+            &mut self.evs.spe,
+            &mut self.evs.spa,
+            &mut self.evs.spd,
+        ] {
+            *ev = (*ev).min(MAX_STAT_EVS).min(remaining);
+            remaining -= *ev;
+        }
+        self.recompute_stats();
     }
 
     /// Gets the hidden power type and power of the Pokémon.
     ///
-    /// The hidden power is determined by the Pokémon's IVs.
+    /// The hidden power is determined by the Pokémon's IVs. See `hidden_power_type` to get the
+    /// type as a `Type`.
     pub fn get_hidden_power(&self) -> (String, u8) {
+        let (type_index, power) = self.hidden_power_index_and_power();
+
+        // Get the hidden power type String, and return result tuple:
+        (
+            should_be_some!(
+                HIDDEN_POWERS.get(type_index as usize),
+                "Invalid hidden power index: {}",
+                type_index
+            )
+            .clone(),
+            power,
+        )
+    }
+
+    /// Gets the type of the Pokémon's hidden power.
+    ///
+    /// The hidden power is determined by the Pokémon's IVs. It can be of any type but Normal.
+    pub fn hidden_power_type(&self) -> Type {
+        let (type_index, _) = self.hidden_power_index_and_power();
+
+        // The hidden power types are all but Normal, in the same order:
+        should_be_ok!(
+            Type::try_from(type_index + 1),
+            "Invalid hidden power index: {}",
+            type_index
+        )
+    }
+
+    /// Computes the hidden power of the Pokémon from its IVs.
+    ///
+    /// Returns the index of the type in the hidden power types (0 to 15, from Fighting to Dark),
+    /// and the power (30 to 70).
+    fn hidden_power_index_and_power(&self) -> (u8, u8) {
         let mut type_ = 0;
         let mut power = 0;
 
@@ -371,16 +1575,63 @@ impl Pokemon {
         type_ = type_ * 15 / 63; // Scale to 0-15.
         power = power * 40 / 63 + 30; // Scale to 30-70.
 
-        // Get the hidden power type String, and return result tuple:
-        (
-            should_be_some!(
-                HIDDEN_POWERS.get(type_ as usize),
-                "Invalid hidden power index: {}",
-                type_
-            )
-            .clone(),
-            power as u8,
-        )
+        (type_ as u8, power as u8)
+    }
+
+    /// Gets the generation of the game the Pokémon originates from: 3, 4, or 5.
+    ///
+    /// This is independent of the storage format: a Pokémon caught in Ruby and migrated to
+    /// Black is stored as a Gen 5 Pokémon, but its origin generation is still 3.
+    pub fn origin_generation(&self) -> u8 {
+        self.origin_game.generation()
+    }
+
+    /// Gets the name the games give by default to the Pokémon's species, in the Pokémon's
+    /// language.
+    ///
+    /// Returns `None` if the default names of the Pokémon's language are not known: only the
+    /// English ones are, for now.
+    pub fn default_name(&self) -> Option<&'static str> {
+        Self::default_name_in(self.species.id(), self.language)
+    }
+
+    /// Returns whether the Pokémon's name is the default name of its species, i.e., whether it is
+    /// not nicknamed, regardless of the `is_nicknamed` flag.
+    ///
+    /// Only the English default names are known for now. The names of Pokémon from other
+    /// languages are compared against the English ones, so they will usually be considered
+    /// nicknames.
+    pub fn nickname_is_default(&self) -> bool {
+        self.default_name()
+            .or_else(|| Self::default_name_in(self.species.id(), Language::English))
+            .is_some_and(|default_name| self.name == default_name)
+    }
+
+    /// Gets the default name of a species in a language, if known.
+    ///
+    /// # Arguments
+    /// * `species_id` - The ID of the species.
+    /// * `language` - The language of the name.
+    fn default_name_in(species_id: u16, language: Language) -> Option<&'static str> {
+        SPECIES_DEFAULT_NAMES
+            .get(&language.to_string())?
+            .get(species_id as usize)
+            .map(String::as_str)
+    }
+
+    /// Returns whether the Pokémon was obtained as an egg, whether it has hatched or not.
+    ///
+    /// That is equal to checking whether the `egg_date` field is set.
+    pub fn met_as_egg(&self) -> bool {
+        self.egg_date.is_some()
+    }
+
+    /// Returns whether the Pokémon hatched from an egg.
+    ///
+    /// Hatched Pokémon have both an egg location, where the egg was obtained (e.g., from the
+    /// Day-Care Couple), and a met location, where the egg hatched.
+    pub fn was_hatched(&self) -> bool {
+        self.met_as_egg() && !self.is_egg
     }
 
     /// Returns whether this Pokémon's data corresponds to that of a Pokémon stored in a PC box (a
@@ -406,67 +1657,74 @@ impl Pokemon {
     ///
     /// This is useful for converting boxed Pokémon into party Pokémon.
     fn generate_stats(&self) -> StatsFeature {
-        // Get the features used to determine the stats:
-        let id = self.species.id() as usize;
-        let base_stats = should_be_some!(
-            BASE_STATS.get(id as usize),
-            "Invalid species ID: {}",
-            self.species.id()
-        );
-        let lv = self.level;
-        let ivs = self.ivs;
-        let evs = self.evs;
+        // Get the features used to determine the stats. The first "base stat" is the experience
+        // type, so the actual base stats start at index 1:
+        let base_stats = self.base_stats();
+        let level = self.level as u32;
+        let nature = self.nature();
 
-        // Calculate the stats (disregarding the nature for now):
-        let hp = (ivs.hp + 2 * (base_stats[0] as u16) + evs.hp / 4) * (lv as u16) / 100 + 10;
-        let atk = (ivs.atk + 2 * (base_stats[1] as u16) + evs.atk) / 4 * (lv as u16) / 100 + 5;
-        let def = (ivs.def + 2 * (base_stats[2] as u16) + evs.def) / 4 * (lv as u16) / 100 + 5;
-        let spa = (ivs.spa + 2 * (base_stats[3] as u16) + evs.spa) / 4 * (lv as u16) / 100 + 5;
-        let spd = (ivs.spd + 2 * (base_stats[4] as u16) + evs.spd) / 4 * (lv as u16) / 100 + 5;
-        let spe = (ivs.spe + 2 * (base_stats[5] as u16) + evs.spe) / 4 * (lv as u16) / 100 + 5;
-        let mut stats = StatsFeature {
-            hp,
-            atk,
-            def,
-            spa,
-            spd,
-            spe,
-        };
+        let stat_value = |stat: Stat, index: usize| -> u16 {
+            let base = base_stats[index] as u32;
+            let iv = self.ivs.get(&stat) as u32;
+            let ev = self.evs.get(&stat) as u32;
+            let value = (2 * base + iv + ev / 4) * level / 100;
 
-        // Add the nature modifiers:
-        let nature = self.nature();
-        match nature.increased_stat {
-            Stat::Atk => stats.atk = (stats.atk as f32 * 1.1) as u16,
-            Stat::Def => stats.def = (stats.def as f32 * 1.1) as u16,
-            Stat::SpA => stats.spa = (stats.spa as f32 * 1.1) as u16,
-            Stat::SpD => stats.spd = (stats.spd as f32 * 1.1) as u16,
-            Stat::Spe => stats.spe = (stats.spe as f32 * 1.1) as u16,
-            Stat::Hp => {
-                should_not_happen!("HP stat cannot be increased by nature");
-            }
-        }
-        match nature.decreased_stat {
-            Stat::Atk => stats.atk = (stats.atk as f32 * 0.9) as u16,
-            Stat::Def => stats.def = (stats.def as f32 * 0.9) as u16,
-            Stat::SpA => stats.spa = (stats.spa as f32 * 0.9) as u16,
-            Stat::SpD => stats.spd = (stats.spd as f32 * 0.9) as u16,
-            Stat::Spe => stats.spe = (stats.spe as f32 * 0.9) as u16,
-            Stat::Hp => {
-                should_not_happen!("HP stat cannot be decreased by nature");
+            if let Stat::Hp = stat {
+                return if self.species.id() == SHEDINJA {
+                    1
+                } else {
+                    (value + level + 10) as u16
+                };
             }
-        }
 
-        stats
+            // Apply the nature modifier, unless the nature is neutral:
+            let value = value + 5;
+            (match (nature.increased_stat == stat, nature.decreased_stat == stat) {
+                (true, false) => value * 110 / 100,
+                (false, true) => value * 90 / 100,
+                _ => value,
+            }) as u16
+        };
+
+        StatsFeature {
+            hp: stat_value(Stat::Hp, 1),
+            atk: stat_value(Stat::Atk, 2),
+            def: stat_value(Stat::Def, 3),
+            spa: stat_value(Stat::SpA, 4),
+            spd: stat_value(Stat::SpD, 5),
+            spe: stat_value(Stat::Spe, 6),
+        }
     }
 
     /// Serializes the Pokémon into a vector of bytes, complying with the internal format used in
     /// the games.
+    ///
+    /// If the Pokémon was deserialized, the fields not handled by this struct (e.g., mail, seals,
+    /// and unknown bytes) are written back as they were in the original data,
+    /// so that a deserialization and serialization round-trip is lossless. Otherwise, or after
+    /// `self.discard_original_bytes()`, they are zeroed. These are, exactly:
+    /// * Unused bytes within blocks A to D (e.g., 0x42 - 0x43 and 0x64 - 0x67).
+    /// * 0x89 - 0x8B: Unknown fields and flags.
+    /// * 0x8D: Capsule/seal index (i.e., no Ball Capsule).
+    /// * 0x9C - 0xD3: Mail message and its OT name (i.e., no mail).
+    /// * [Gen 4] 0xD4 - 0xEB: Seal coordinates.
+    /// * [Gen 5] 0xD4 - 0xDB: Unknown fields.
+    ///
+    /// The output is always a full party Pokémon: `GEN4_PKM_LEN` (236) or `GEN5_PKM_LEN` (220)
+    /// bytes long, with its level (0x8C), current HP (0x8E - 0x8F) and stats (0x90 - 0x9B) set,
+    /// generating the latter if the Pokémon is boxed. The current HP is the maximum HP, unless
+    /// `self.current_hp` is set to a lower one.
     pub fn serialize(&self) -> Vec<u8> {
         let mut bytes = if !self.is_gen5 {
             vec![0x00; GEN4_PKM_LEN]
         } else {
             vec![0x00; GEN5_PKM_LEN]
         };
+        // Start from the original data, if any; the known fields are overwritten below:
+        if let Some(original) = &self.original_bytes {
+            let len = original.len().min(bytes.len());
+            bytes[..len].copy_from_slice(&original[..len]);
+        }
 
         // Fill the bytes vector with the Pokémon's data:
         // Block A: 0x00 - 0x28
@@ -490,14 +1748,7 @@ impl Pokemon {
             self.evs.spd as u8,
             self.evs.spe as u8,
         ]);
-        bytes[0x1E..0x24].copy_from_slice(&[
-            self.contest_stats.cool,
-            self.contest_stats.beauty,
-            self.contest_stats.cute,
-            self.contest_stats.smart,
-            self.contest_stats.tough,
-            self.contest_stats.sheen,
-        ]);
+        bytes[0x1E..0x24].copy_from_slice(&ContestStat::ALL.map(|s| self.contest_stats.get(&s)));
         bytes[0x24..0x28].copy_from_slice(&self.sinnoh_ribbons[0..4]);
         // Block B: 0x28 - 0x48
         bytes[0x28..0x30].copy_from_slice(
@@ -548,10 +1799,10 @@ impl Pokemon {
             bytes[0x41] = self.nature.id_and_name.id() as u8;
         }
         if !self.is_gen5 {
-            let egg_location: u16 = self.egg_location.into();
-            bytes[0x44..0x46].copy_from_slice(&egg_location.to_le_bytes());
-            let met_location: u16 = self.met_location.into();
-            bytes[0x46..0x48].copy_from_slice(&met_location.to_le_bytes());
+            let (pt_hgss_egg_location, _) = self.gen4_location_pair(self.egg_location);
+            bytes[0x44..0x46].copy_from_slice(&pt_hgss_egg_location.to_le_bytes());
+            let (pt_hgss_met_location, _) = self.gen4_location_pair(self.met_location);
+            bytes[0x46..0x48].copy_from_slice(&pt_hgss_met_location.to_le_bytes());
         }
         // Block C: 0x48 - 0x68
         if !self.is_gen5 {
@@ -584,13 +1835,15 @@ impl Pokemon {
             encoded_name.resize(0x78 - 0x68, 0);
             bytes[0x68..0x78].copy_from_slice(&encoded_name);
         }
-        if let Some(egg_date) = self.egg_date {
-            bytes[0x78..0x7B].copy_from_slice(&[
+        // Always written, so that clearing the egg date also clears it in the original data:
+        bytes[0x78..0x7B].copy_from_slice(&match self.egg_date {
+            Some(egg_date) => [
                 (egg_date.year() - 2000) as u8,
                 egg_date.month() as u8,
                 egg_date.day() as u8,
-            ]);
-        }
+            ],
+            None => [0; 3],
+        });
         bytes[0x7B..0x7E].copy_from_slice(&[
             (self.met_date.year() - 2000) as u8,
             self.met_date.month() as u8,
@@ -598,36 +1851,10 @@ impl Pokemon {
         ]);
         if !self.is_gen5 {
             // Handle location particularities of Diamond and Pearl:
-            // TODO: Check in-game representation of corner cases, e.g. Distortion World, Battle
-            // Frontier, and HGSS locations.
-
-            let egg_location = match self.egg_location {
-                Location::Gen4(loc) => loc,
-                _ => should_not_happen!(
-                    "Egg location for Gen 4 Pokémon is not a Gen 4 location: {:?}",
-                    self.egg_location
-                ),
-            };
-            let met_location = match self.met_location {
-                Location::Gen4(loc) => loc,
-                _ => should_not_happen!(
-                    "Met location for Gen 4 Pokémon is not a Gen 4 location: {:?}",
-                    self.met_location
-                ),
-            };
-            let dp_egg_location = if egg_location <= Gen4Location::DP_LAST_LOCATION {
-                egg_location
-            } else {
-                Gen4Location::FarawayPlace
-            };
-            let dp_met_location = if met_location <= Gen4Location::DP_LAST_LOCATION {
-                met_location
-            } else {
-                Gen4Location::FarawayPlace
-            };
-
-            bytes[0x7E..0x80].copy_from_slice(&(dp_egg_location as u16).to_le_bytes());
-            bytes[0x80..0x82].copy_from_slice(&(dp_met_location as u16).to_le_bytes());
+            let (_, dp_egg_location) = self.gen4_location_pair(self.egg_location);
+            bytes[0x7E..0x80].copy_from_slice(&dp_egg_location.to_le_bytes());
+            let (_, dp_met_location) = self.gen4_location_pair(self.met_location);
+            bytes[0x80..0x82].copy_from_slice(&dp_met_location.to_le_bytes());
         } else {
             let egg_location: u16 = self.egg_location.into();
             bytes[0x7E..0x80].copy_from_slice(&egg_location.to_le_bytes());
@@ -642,19 +1869,21 @@ impl Pokemon {
             self.ball as u8
         };
         bytes[0x84] = self.met_level | (self.trainer_gender as u8) << 7;
-        bytes[0x85] = self.encounter_type;
+        bytes[0x85] = self.encounter_type.into();
         bytes[0x86] = if !self.is_gen5 { self.ball as u8 } else { 0 };
         bytes[0x87] = self.performance;
         // 0x88 - End of "boxed" Pokémon data.
 
+        bytes[0x88] = self.status_condition.into();
         bytes[0x8C] = self.level;
         // Check if the Pokémon has stats:
         let stats = match self.stats {
             Some(stats) => stats,
             None => self.generate_stats(),
         };
-        // Set the current HP from the maximum HP:
-        bytes[0x8E..0x90].copy_from_slice(&stats.hp.to_le_bytes());
+        // Set the current HP, which cannot exceed the maximum HP (e.g., after lowering the stats):
+        let current_hp = self.current_hp.map_or(stats.hp, |hp| hp.min(stats.hp));
+        bytes[0x8E..0x90].copy_from_slice(&current_hp.to_le_bytes());
         // Copy the stats.
         bytes[0x90..0x9C].copy_from_slice(
             &[
@@ -670,30 +1899,144 @@ impl Pokemon {
         // We don't care about the other data after the stats.
 
         // Compute and store the correct checksum:
-        let checksum = bytes[0x08..BOXED_PKM_LEN]
+        let checksum = Self::compute_checksum(&bytes);
+        bytes[0x06..0x08].copy_from_slice(&checksum.to_le_bytes());
+
+        bytes
+    }
+
+    /// Serializes the Pokémon like `self.serialize()`, but as a healthy party Pokémon, for
+    /// interoperability with tools like PKHeX, which check the party data region.
+    ///
+    /// The current HP is set to the maximum HP and the status conditions (0x88) are cleared, even
+    /// if the original data had a fainted or poisoned Pokémon. Every other byte is the same as in
+    /// `self.serialize()`; see it for the fields that are zeroed.
+    ///
+    /// Returns the decrypted data, `GEN4_PKM_LEN` (236) or `GEN5_PKM_LEN` (220) bytes long.
+    pub fn to_pkhex_compatible_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.serialize();
+        // The party data is not covered by the checksum, so there is no need to recompute it:
+        bytes[0x88] = 0;
+        let max_hp = [bytes[0x90], bytes[0x91]];
+        bytes[0x8E..0x90].copy_from_slice(&max_hp);
+
+        bytes
+    }
+
+    /// Discards the data the Pokémon was deserialized from, so that `self.serialize()` zeroes the
+    /// fields not handled by this struct, instead of writing them back unchanged.
+    pub fn discard_original_bytes(&mut self) {
+        self.original_bytes = None;
+    }
+
+    /// Gets the values of a Gen 4 location to store in the Platinum/HeartGold/SoulSilver offsets
+    /// (0x44 for the egg location, 0x46 for the met location) and in the Diamond/Pearl offsets
+    /// (0x7E and 0x80), as the games do.
+    ///
+    /// Diamond and Pearl only read their offsets, so locations that do not exist in them are
+    /// stored there as Faraway Place, and in full in the Platinum/HeartGold/SoulSilver offsets.
+    /// Locations that do exist in Diamond and Pearl are stored in both offsets for Pokémon from
+    /// Platinum, HeartGold and SoulSilver, and only in the Diamond/Pearl ones for the rest.
+    ///
+    /// # Arguments
+    /// * `location` - The egg or met location of the Pokémon, which must be a Gen 4 location.
+    ///
+    /// Returns the (Platinum/HeartGold/SoulSilver, Diamond/Pearl) pair of values.
+    fn gen4_location_pair(&self, location: Location) -> (u16, u16) {
+        let location = match location {
+            Location::Gen4(location) => location,
+            _ => should_not_happen!(
+                "Location of a Gen 4 Pokémon is not a Gen 4 location: {:?}",
+                location
+            ),
+        };
+
+        if !location.is_in_dp() {
+            (location as u16, Gen4Location::FarawayPlace as u16)
+        } else if matches!(
+            self.origin_game,
+            Game::Platinum | Game::HeartGold | Game::SoulSilver
+        ) {
+            (location as u16, location as u16)
+        } else {
+            (0, location as u16)
+        }
+    }
+
+    /// Computes the checksum of the serialized Pokémon data.
+    ///
+    /// The checksum is the sum of the 16-bit words of the boxed data, after the checksum itself.
+    ///
+    /// # Arguments
+    /// * `bytes` - The decrypted serialized Pokémon data, at least `BOXED_PKM_LEN` bytes long.
+    fn compute_checksum(bytes: &[u8]) -> u16 {
+        bytes[0x08..BOXED_PKM_LEN]
             .chunks(2)
             .fold(0u16, |acc, chunk| {
                 acc.wrapping_add(u16::from_le_bytes([chunk[0], chunk[1]]))
-            });
-        bytes[0x06..0x08].copy_from_slice(&checksum.to_le_bytes());
+            })
+    }
 
-        bytes
+    /// Deserializes a Pokémon from a byte slice, complying with the internal format used in the
+    /// games.
+    ///
+    /// Panics if the data is not a valid Pokémon. See `try_deserialize` for a non-panicking
+    /// alternative.
+    pub fn deserialize(bytes: &[u8]) -> Pokemon {
+        Self::try_deserialize(bytes)
+            .unwrap_or_else(|e| panic!("Failed to deserialize Pokémon: {}", e))
     }
 
     /// Deserializes a Pokémon from a byte slice, complying with the internal format used in the
     /// games.
-    pub fn deserialize(bytes: &[u8]) -> Pokemon {
-        assert!(
-            bytes.len() == BOXED_PKM_LEN
-                || bytes.len() == GEN4_PKM_LEN
-                || bytes.len() == GEN5_PKM_LEN,
-            "Invalid Pokémon data size",
-        );
+    ///
+    /// # Arguments
+    /// * `bytes` - The decrypted serialized Pokémon data, either boxed or with battle stats.
+    ///
+    /// Returns `GtsError::InvalidValue` if the data has an invalid length,
+    /// `GtsError::Unsupported` if its species is not in the 1-649 range (Gen 1 to Gen 5), or other
+    /// `GtsError` variants if any of its fields cannot be decoded.
+    pub fn try_deserialize(bytes: &[u8]) -> Result<Pokemon> {
+        Self::try_deserialize_with_generation(bytes, bytes.len() == GEN5_PKM_LEN)
+    }
+
+    /// Deserializes a Pokémon from a byte slice, knowing which generation it belongs to.
+    ///
+    /// Boxed Pokémon data has the same length in Gen 4 and Gen 5, so its generation can only be
+    /// told apart by its context (e.g., the save file it comes from).
+    ///
+    /// # Arguments
+    /// * `bytes` - The decrypted serialized Pokémon data, either boxed or with battle stats.
+    /// * `is_gen5` - Whether the Pokémon is a Gen 5 Pokémon. Pokémon introduced in Gen 5 are
+    ///   always considered Gen 5 Pokémon.
+    ///
+    /// Returns the same errors as `try_deserialize`.
+    pub(crate) fn try_deserialize_with_generation(bytes: &[u8], is_gen5: bool) -> Result<Pokemon> {
+        if bytes.len() != BOXED_PKM_LEN
+            && bytes.len() != GEN4_PKM_LEN
+            && bytes.len() != GEN5_PKM_LEN
+        {
+            return Err(GtsError::InvalidValue(format!(
+                "Invalid Pokémon data size: {}",
+                bytes.len()
+            )));
+        }
+
+        // Every field read below is within these regions; the boxed data only has the first one:
+        debug_assert!(bytes.len() >= BLOCKS_END);
+        let has_battle_stats = bytes.len() >= BATTLE_STATS_END;
 
         let mut pkm = Pokemon::default();
 
+        // Species from later generations would be out of range of the data tables:
         let species_id = u16::from_le_bytes([bytes[0x08], bytes[0x09]]);
-        pkm.is_gen5 = bytes.len() == GEN5_PKM_LEN || species_id > LAST_GEN4_POKEMON;
+        if !(1..=LAST_GEN5_POKEMON).contains(&species_id) {
+            return Err(GtsError::Unsupported(format!(
+                "Unsupported species ID: {} (only species 1 to {} are supported)",
+                species_id, LAST_GEN5_POKEMON
+            )));
+        }
+        pkm.is_gen5 = is_gen5 || species_id > LAST_GEN4_POKEMON;
         // Block A: 0x00 - 0x28
         pkm.set_pid(u32::from_le_bytes([
             bytes[0x00],
@@ -704,40 +2047,26 @@ impl Pokemon {
         pkm.encryption_bypass = bytes[0x04] & 0x03 != 0;
         pkm.bad_egg_flag = (bytes[0x04] & 0x02) != 0;
         pkm.original_checksum = u16::from_le_bytes([bytes[0x06], bytes[0x07]]);
-        pkm.species = should_be_some!(
-            IdFeature::from_species_id(species_id),
-            "Invalid species ID: {}",
-            species_id
-        );
+        pkm.computed_checksum = Self::compute_checksum(bytes);
+        pkm.species =
+            IdFeature::from_species_id(species_id).ok_or(GtsError::InvalidSpecies(species_id))?;
         let item_id = u16::from_le_bytes([bytes[0x0A], bytes[0x0B]]);
         if pkm.is_gen5 {
-            pkm.held_item = should_be_some!(
-                IdFeature::from_gen5_item_id(item_id),
-                "Invalid item ID: {}",
-                item_id
-            );
+            pkm.held_item = IdFeature::from_gen5_item_id(item_id)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid item ID: {}", item_id)))?;
         } else {
-            pkm.held_item = should_be_some!(
-                IdFeature::from_gen4_item_id(item_id),
-                "Invalid item ID: {}",
-                item_id
-            );
+            pkm.held_item = IdFeature::from_gen4_item_id(item_id)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid item ID: {}", item_id)))?;
         }
         pkm.trainer_id = u16::from_le_bytes([bytes[0x0C], bytes[0x0D]]);
         pkm.trainer_secret_id = u16::from_le_bytes([bytes[0x0E], bytes[0x0F]]);
         pkm.experience = u32::from_le_bytes([bytes[0x10], bytes[0x11], bytes[0x12], bytes[0x13]]);
         pkm.friendship = bytes[0x14];
-        pkm.ability = should_be_some!(
-            IdFeature::from_ability_id(bytes[0x15] as u16),
-            "Invalid ability ID: {}",
-            bytes[0x15]
-        );
+        pkm.ability = IdFeature::from_ability_id(bytes[0x15] as u16)
+            .ok_or_else(|| GtsError::Decode(format!("Invalid ability ID: {}", bytes[0x15])))?;
         pkm.markings = bytes[0x16];
-        pkm.language = should_be_ok!(
-            Language::try_from(bytes[0x17]),
-            "Invalid language ID: {}",
-            bytes[0x17]
-        );
+        pkm.language = Language::try_from(bytes[0x17])
+            .map_err(|_| GtsError::Decode(format!("Invalid language ID: {}", bytes[0x17])))?;
         pkm.evs = StatsFeature {
             hp: bytes[0x18] as u16,
             atk: bytes[0x19] as u16,
@@ -746,14 +2075,9 @@ impl Pokemon {
             spd: bytes[0x1C] as u16,
             spe: bytes[0x1D] as u16,
         };
-        pkm.contest_stats = ContestStatsFeature {
-            cool: bytes[0x1E],
-            beauty: bytes[0x1F],
-            cute: bytes[0x20],
-            smart: bytes[0x21],
-            tough: bytes[0x22],
-            sheen: bytes[0x23],
-        };
+        for (i, stat) in ContestStat::ALL.iter().enumerate() {
+            pkm.contest_stats.set(stat, bytes[0x1E + i]);
+        }
         pkm.sinnoh_ribbons = [
             bytes[0x24],
             bytes[0x25],
@@ -770,26 +2094,14 @@ impl Pokemon {
         let move3_id = u16::from_le_bytes([bytes[0x2C], bytes[0x2D]]);
         let move4_id = u16::from_le_bytes([bytes[0x2E], bytes[0x2F]]);
         pkm.moves = [
-            should_be_some!(
-                IdFeature::from_move_id(move1_id),
-                "Invalid move ID: {}",
-                move1_id
-            ),
-            should_be_some!(
-                IdFeature::from_move_id(move2_id),
-                "Invalid move ID: {}",
-                move2_id
-            ),
-            should_be_some!(
-                IdFeature::from_move_id(move3_id),
-                "Invalid move ID: {}",
-                move3_id
-            ),
-            should_be_some!(
-                IdFeature::from_move_id(move4_id),
-                "Invalid move ID: {}",
-                move4_id
-            ),
+            IdFeature::from_move_id(move1_id)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid move ID: {}", move1_id)))?,
+            IdFeature::from_move_id(move2_id)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid move ID: {}", move2_id)))?,
+            IdFeature::from_move_id(move3_id)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid move ID: {}", move3_id)))?,
+            IdFeature::from_move_id(move4_id)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid move ID: {}", move4_id)))?,
         ];
         pkm.move_pps = [bytes[0x30], bytes[0x31], bytes[0x32], bytes[0x33]];
         pkm.move_pp_ups = [bytes[0x34], bytes[0x35], bytes[0x36], bytes[0x37]];
@@ -806,11 +2118,8 @@ impl Pokemon {
         pkm.is_nicknamed = (bytes[0x3B] & 0x80) != 0;
         pkm.hoenn_ribbons = [bytes[0x3C] & 0x3F, bytes[0x3D], bytes[0x3E], bytes[0x3f]];
         pkm.fateful = (bytes[0x40] & 0x01) != 0;
-        pkm.gender = should_be_ok!(
-            Gender::try_from((bytes[0x40] >> 1) & 0x03),
-            "Invalid gender ID: {}",
-            bytes[0x40] >> 1
-        );
+        pkm.gender = Gender::try_from((bytes[0x40] >> 1) & 0x03)
+            .map_err(|_| GtsError::Decode(format!("Invalid gender ID: {}", bytes[0x40] >> 1)))?;
         pkm.form_id = bytes[0x40] >> 3;
         if !pkm.is_gen5 {
             // Gen 4 stores shiny leaves in 0x41:
@@ -829,11 +2138,8 @@ impl Pokemon {
             );
         } else {
             // Gen 5 stores nature ID in 0x41:
-            pkm.nature = should_be_some!(
-                Nature::from_id(bytes[0x41] as u16),
-                "Invalid nature ID: {}",
-                bytes[0x41]
-            );
+            pkm.nature = Nature::from_id(bytes[0x41] as u16)
+                .ok_or_else(|| GtsError::Decode(format!("Invalid nature ID: {}", bytes[0x41])))?;
         }
         // Transform egg location to correct enum type:
         let egg_loc_plathgss = u16::from_le_bytes([bytes[0x44], bytes[0x45]]);
@@ -841,25 +2147,19 @@ impl Pokemon {
 
         pkm.egg_location = if egg_loc_plathgss != 0 {
             // If the Plat/HG/SS egg loc offset is non-zero, this is always a Gen 4 location.
-            Location::Gen4(should_be_ok!(
-                Gen4Location::try_from(egg_loc_plathgss),
-                "Invalid egg location ID: {}",
-                egg_loc_plathgss
-            ))
+            Location::Gen4(Gen4Location::try_from(egg_loc_plathgss).map_err(|_| {
+                GtsError::Decode(format!("Invalid egg location ID: {}", egg_loc_plathgss))
+            })?)
         } else {
             // If the Plat/HG/SS egg location offset is zero, this can be a Gen 4 or Gen 5 location.
             if !pkm.is_gen5 {
-                Location::Gen4(should_be_ok!(
-                    Gen4Location::try_from(egg_loc_others),
-                    "Invalid egg location ID: {}",
-                    egg_loc_others
-                ))
+                Location::Gen4(Gen4Location::try_from(egg_loc_others).map_err(|_| {
+                    GtsError::Decode(format!("Invalid egg location ID: {}", egg_loc_others))
+                })?)
             } else {
-                Location::Gen5(should_be_ok!(
-                    Gen5Location::try_from(egg_loc_others),
-                    "Invalid egg location ID: {}",
-                    egg_loc_others
-                ))
+                Location::Gen5(Gen5Location::try_from(egg_loc_others).map_err(|_| {
+                    GtsError::Decode(format!("Invalid egg location ID: {}", egg_loc_others))
+                })?)
             }
         };
         // Transform met location to correct enum type:
@@ -868,96 +2168,71 @@ impl Pokemon {
 
         pkm.met_location = if met_loc_plathgss != 0 {
             // If the Plat/HG/SS met location offset is non-zero, this is always a Gen 4 location.
-            Location::Gen4(should_be_ok!(
-                Gen4Location::try_from(met_loc_plathgss),
-                "Invalid met location ID: {}",
-                met_loc_plathgss
-            ))
+            Location::Gen4(Gen4Location::try_from(met_loc_plathgss).map_err(|_| {
+                GtsError::Decode(format!("Invalid met location ID: {}", met_loc_plathgss))
+            })?)
         } else {
             // If the Plat/HG/SS met location offset is zero, this can be a Gen 4 or Gen 5 location.
             if !pkm.is_gen5 {
-                Location::Gen4(should_be_ok!(
-                    Gen4Location::try_from(met_loc_others),
-                    "Invalid met location ID: {}",
-                    met_loc_others
-                ))
+                Location::Gen4(Gen4Location::try_from(met_loc_others).map_err(|_| {
+                    GtsError::Decode(format!("Invalid met location ID: {}", met_loc_others))
+                })?)
             } else {
-                Location::Gen5(should_be_ok!(
-                    Gen5Location::try_from(met_loc_others),
-                    "Invalid met location ID: {}",
-                    met_loc_others
-                ))
+                Location::Gen5(Gen5Location::try_from(met_loc_others).map_err(|_| {
+                    GtsError::Decode(format!("Invalid met location ID: {}", met_loc_others))
+                })?)
             }
         };
         // Block C: 0x48 - 0x68
         if !pkm.is_gen5 {
-            pkm.name = should_be_ok!(
-                Self::decode_name_gen4(&bytes[0x48..0x5E]),
-                "Invalid Pokémon name"
-            );
+            pkm.name = Self::decode_name_gen4(&bytes[0x48..0x5E])?;
         } else {
-            pkm.name = should_be_ok!(
-                Self::decode_name_gen5(&bytes[0x48..0x5E]),
-                "Invalid Pokémon name"
-            );
+            pkm.name = Self::decode_name_gen5(&bytes[0x48..0x5E])?;
         }
-        pkm.origin_game = should_be_ok!(
-            bytes[0x5F].try_into(),
-            "Invalid origin game ID: {}",
-            bytes[0x5F]
-        );
+        pkm.origin_game = bytes[0x5F]
+            .try_into()
+            .map_err(|_| GtsError::Decode(format!("Invalid origin game ID: {}", bytes[0x5F])))?;
         pkm.sinnoh_ribbons[4..8].copy_from_slice(&bytes[0x60..0x64]);
         // Block D: 0x68 - 0x82
         if !pkm.is_gen5 {
-            pkm.trainer_name = should_be_ok!(
-                Self::decode_name_gen4(&bytes[0x68..0x78]),
-                "Invalid Trainer name"
-            );
+            pkm.trainer_name = Self::decode_name_gen4(&bytes[0x68..0x78])?;
         } else {
-            pkm.trainer_name = should_be_ok!(
-                Self::decode_name_gen5(&bytes[0x68..0x78]),
-                "Invalid Trainer name"
-            );
+            pkm.trainer_name = Self::decode_name_gen5(&bytes[0x68..0x78])?;
         }
         pkm.egg_date = NaiveDate::from_ymd_opt(
             bytes[0x78] as i32 + 2000,
             bytes[0x79] as u32,
             bytes[0x7A] as u32,
         );
-        pkm.met_date = should_be_some!(
-            NaiveDate::from_ymd_opt(
-                bytes[0x7B] as i32 + 2000,
-                bytes[0x7C] as u32,
-                bytes[0x7D] as u32
-            ),
-            "Invalid met date"
-        );
+        pkm.met_date = NaiveDate::from_ymd_opt(
+            bytes[0x7B] as i32 + 2000,
+            bytes[0x7C] as u32,
+            bytes[0x7D] as u32,
+        )
+        .ok_or_else(|| GtsError::Decode("Invalid met date".to_string()))?;
         pkm.pokerus = bytes[0x82];
         // Handle HGSS ball particularities:
         let ball = bytes[0x83];
         let hgss_ball = bytes[0x86];
         pkm.ball = if !pkm.is_gen5 && hgss_ball != 0 {
-            should_be_ok!(
-                Pokeball::try_from(hgss_ball),
-                "Invalid Pokéball ID: {}",
-                hgss_ball
-            )
+            Pokeball::try_from(hgss_ball)
+                .map_err(|_| GtsError::Decode(format!("Invalid Pokéball ID: {}", hgss_ball)))?
         } else {
-            should_be_ok!(Pokeball::try_from(ball), "Invalid Pokéball ID: {}", ball)
+            Pokeball::try_from(ball)
+                .map_err(|_| GtsError::Decode(format!("Invalid Pokéball ID: {}", ball)))?
         };
         pkm.met_level = bytes[0x84] & 0x7F;
-        pkm.trainer_gender = should_be_ok!(
-            Gender::try_from((bytes[0x84] >> 7) & 0x01),
-            "Invalid trainer gender ID"
-        );
-        pkm.encounter_type = bytes[0x85];
+        pkm.trainer_gender = Gender::try_from((bytes[0x84] >> 7) & 0x01)
+            .map_err(|_| GtsError::Decode("Invalid trainer gender ID".to_string()))?;
+        pkm.encounter_type = EncounterType::from(bytes[0x85]);
         pkm.performance = bytes[0x87];
         // 0x88 - End of "boxed" Pokémon data.
 
-        pkm.level = bytes[0x8C];
-        // Check and add if the Pokémon has stats:
-        if bytes.len() == GEN4_PKM_LEN || bytes.len() == GEN5_PKM_LEN {
-            // Ignore current HP.
+        // Check and add if the Pokémon has a level and stats:
+        if has_battle_stats {
+            pkm.status_condition = StatusCondition::from(bytes[0x88]);
+            pkm.level = bytes[0x8C];
+            pkm.current_hp = Some(u16::from_le_bytes([bytes[0x8E], bytes[0x8F]]));
             pkm.stats = Some(StatsFeature {
                 hp: u16::from_le_bytes([bytes[0x90], bytes[0x91]]),
                 atk: u16::from_le_bytes([bytes[0x92], bytes[0x93]]),
@@ -967,10 +2242,16 @@ impl Pokemon {
                 spd: u16::from_le_bytes([bytes[0x9A], bytes[0x9B]]),
             });
         } else {
+            pkm.level = pkm.level_from_xp();
+            pkm.status_condition = StatusCondition::None;
+            pkm.current_hp = None;
             pkm.stats = None;
         }
 
-        pkm
+        // Keep the data, to write back the fields not handled here when serializing:
+        pkm.original_bytes = Some(bytes.to_vec());
+
+        Ok(pkm)
     }
 
     // Raw data processing methods:
@@ -1069,7 +2350,7 @@ impl Pokemon {
     /// * `pokemon_data` - The serialized Pokémon data to encrypt or decrypt.
     /// * `pid` - The Pokémon's PID, used as a seed for the encryption algorithm.
     /// * `checksum` - The Pokémon's checksum, used as a seed for the encryption algorithm.
-    fn crypt_data(pokemon_data: &mut Vec<u8>, pid: u32, checksum: u16) {
+    fn crypt_data(pokemon_data: &mut [u8], pid: u32, checksum: u16) {
         Self::encryption_step(&mut pokemon_data[0x8..BOXED_PKM_LEN], checksum as u32);
         if pokemon_data.len() > BOXED_PKM_LEN {
             Self::encryption_step(&mut pokemon_data[BOXED_PKM_LEN..], pid);
@@ -1092,27 +2373,14 @@ impl Pokemon {
     ///   bytes.
     /// * `seed` - The seed used for the encryption algorithm.
     fn encryption_step(data: &mut [u8], seed: u32) {
-        // Unpack the byte array into u16 values:
-        let mut data_as_u16 = data
-            .chunks(2)
-            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-            .collect::<Vec<u16>>();
-
-        // Perform the encryption, using a XOR operation with a linear congruential generator:
+        // Perform the encryption in place, XOR-ing each little-endian u16 value with the output of
+        // a linear congruential generator:
         let mut state = seed;
-        for i in 0..data_as_u16.len() {
-            let byte = &mut data_as_u16[i];
-            state = (state.wrapping_mul(0x41C64E6D) + 0x6073) & 0xFFFFFFFF;
-            *byte ^= (state >> 16) as u16;
+        for chunk in data.chunks_exact_mut(2) {
+            state = state.wrapping_mul(0x41C64E6D).wrapping_add(0x6073);
+            let value = u16::from_le_bytes([chunk[0], chunk[1]]) ^ (state >> 16) as u16;
+            chunk.copy_from_slice(&value.to_le_bytes());
         }
-
-        // Copy back the data to the original buffer:
-        data.copy_from_slice(
-            &data_as_u16
-                .iter()
-                .flat_map(|&x| x.to_le_bytes())
-                .collect::<Vec<u8>>(),
-        );
     }
 
     /// Shuffles the "blocks"" of the serialized Pokémon data, as to comply with the game's
@@ -1138,7 +2406,7 @@ impl Pokemon {
     /// # Arguments
     /// * `pokemon_data` - The serialized Pokémon data to shuffle, as a mutable vector of bytes.
     /// * `pid` - The Pokémon's PID, used to determine the shuffle order.
-    fn shuffle_blocks(pokemon_data: &mut Vec<u8>, pid: u32) {
+    fn shuffle_blocks(pokemon_data: &mut [u8], pid: u32) {
         let shuffle_data = &mut pokemon_data[0x08..BOXED_PKM_LEN];
         let shuffle_order = Self::determine_shuffle_block_order(pid);
 
@@ -1163,7 +2431,7 @@ impl Pokemon {
     /// # Arguments
     /// * `pokemon_data` - The serialized Pokémon data to unshuffle, as a mutable vector of bytes.
     /// * `pid` - The Pokémon's PID, used to determine the unshuffle order.
-    fn unshuffle_blocks(pokemon_data: &mut Vec<u8>, pid: u32) {
+    fn unshuffle_blocks(pokemon_data: &mut [u8], pid: u32) {
         let shuffle_data = &mut pokemon_data[0x08..BOXED_PKM_LEN];
         let shuffle_order = Self::determine_shuffle_block_order(pid);
 
@@ -1221,7 +2489,9 @@ impl Pokemon {
 
     /// Saves the Pokémon to binary a file in the specified directory, with the specified extension.
     ///
-    /// The resulting file will contain the Pokémon's serialized data. See `serialize`.
+    /// The resulting file will contain the Pokémon's serialized data. See `serialize`. It is named
+    /// after the Pokémon and the current time, with a counter appended if a file with that name
+    /// already exists, so that concurrent saves never overwrite each other.
     ///
     /// # Arguments
     /// * `dir_path` - The directory where the file will be saved. If `None`, defaults to
@@ -1231,24 +2501,45 @@ impl Pokemon {
     ///
     /// # Returns
     /// `Ok(true)` if the pokémon was saved successfully, `Ok(false)` if the pokémon was not
-    /// saved due to the resulting file already existing, or the corresponding error if there was
-    /// an error during saving.
+    /// saved due to an identical file already existing in the directory, or the corresponding
+    /// error if there was an error during saving.
     pub fn save(&self, dir_path: Option<&Path>, extension: Option<String>) -> Result<bool> {
-        // Handle optional args:
+        let extension =
+            extension.unwrap_or_else(|| SaveFormat::Pk.extension(self.is_gen5).to_string());
+
+        self.save_data(dir_path, extension, self.serialize())
+    }
+
+    /// Saves the Pokémon to a file in the specified directory, in the specified format.
+    ///
+    /// # Arguments
+    /// * `dir_path` - The directory where the file will be saved. If `None`, defaults to
+    ///   "pokemon".
+    /// * `format` - The format of the file, which determines its extension. See `SaveFormat`.
+    ///
+    /// # Returns
+    /// The same as `self.save()`.
+    pub fn save_with_format(&self, dir_path: Option<&Path>, format: SaveFormat) -> Result<bool> {
+        self.save_data(
+            dir_path,
+            format.extension(self.is_gen5).to_string(),
+            format.encode(self),
+        )
+    }
+
+    /// Saves the given data of the Pokémon to a file in the specified directory, with the
+    /// specified extension, unless an identical file of the Pokémon already exists.
+    ///
+    /// # Arguments
+    /// * `dir_path` - The directory where the file will be saved. If `None`, defaults to
+    ///   "pokemon".
+    /// * `extension` - The file extension to use.
+    /// * `data` - The contents of the file.
+    fn save_data(&self, dir_path: Option<&Path>, extension: String, data: Vec<u8>) -> Result<bool> {
         let dir_path = match dir_path {
             Some(p) => p.to_path_buf(),
             None => Path::new("pokemon").to_path_buf(),
         };
-        let extension = match extension {
-            Some(ext) => ext,
-            None => {
-                if self.is_gen5 {
-                    "pk5".to_string()
-                } else {
-                    "pk4".to_string()
-                }
-            }
-        };
 
         // Create the save directory:
         if !dir_path.exists() {
@@ -1256,6 +2547,7 @@ impl Pokemon {
             // Make all users own the directory:
             // (The gts-rs app must be run as superuser, which messes with permissions.)
             let mut dir_permissions = fs::metadata(&dir_path)?.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
             dir_permissions.set_readonly(false);
             fs::set_permissions(&dir_path, dir_permissions)?;
         }
@@ -1265,71 +2557,92 @@ impl Pokemon {
         let shiny_mark = if self.is_shiny() { "!" } else { "" };
         let base_name = format!("{}_{}{}", self.species.name(), self.name, shiny_mark);
 
-        // Data to save:
-        let data = self.serialize();
-
-        // Check if the resulting file already exists, not saving self if it does:
-        let (file_exists, _maybe_file_path) =
-            Self::save_file_exists(&data, &dir_path, &base_name, &extension)?;
-        if file_exists {
+        // Check if the same data was already saved to the directory, not saving self if it was.
+        // The index is locked until the file is created, so that concurrent saves of the same
+        // Pokémon do not both save it:
+        let mut index = SAVED_FILES_INDEX
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let saved_files = match index.entry(dir_path.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Self::saved_files(&dir_path)?),
+        };
+        let key = (extension, data);
+        if saved_files.contains(&key) {
             return Ok(false);
         }
-
-        // Save to disk:
-        let file_path = dir_path.join(format!("{}_{}.{}", base_name, current_time_str, extension));
-        let mut file = File::create(&file_path)?;
-        file.write_all(&data)?;
+        let (extension, data) = &key;
+
+        // Save to disk, adding a counter to the file name if a file with the same name exists
+        // (e.g., a different Pokémon of the same species and name, saved in the same second):
+        let mut counter = 1;
+        let (file_path, mut file) = loop {
+            let file_name = match counter {
+                1 => format!("{}_{}.{}", base_name, current_time_str, extension),
+                _ => format!(
+                    "{}_{}_{}.{}",
+                    base_name, current_time_str, counter, extension
+                ),
+            };
+            let file_path = dir_path.join(file_name);
+            match File::create_new(&file_path) {
+                Ok(file) => break (file_path, file),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => counter += 1,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        file.write_all(data)?;
         // Let all users own the saved file:
         // (The gts-rs app must be run as superuser, which messes with permissions.)
         let mut file_permissions = fs::metadata(&file_path)?.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
         file_permissions.set_readonly(false);
         fs::set_permissions(&file_path, file_permissions)?;
+        saved_files.insert(key);
 
         Ok(true)
     }
 
-    /// Checks if a file with the same Pokémon data already exists in the specified path.
+    /// Reads the files in a save directory, to index them by their extension and contents.
+    ///
+    /// The directory is only read on the first save to it; the files saved afterwards are added to
+    /// the index as they are saved. Files added to the directory by other means meanwhile are not
+    /// indexed.
     ///
     /// # Arguments
-    /// * `data` - The serialized Pokémon data as a vector of bytes.
     /// * `path` - The path of the directory where the Pokémon data files are stored.
-    /// * `base_name` - The base name of the Pokémon file, without extension.
-    /// * `extension` - The file extension to check for.
     ///
     /// # Returns
-    /// * `Ok((true, Some(file_path)))` if a file with the same Pokémon data exists,
-    ///   where `file_path` is the path to the existing file.
-    /// * `Ok((false, None))` if no such file exists.
-    /// * `Err(error)` if there was an error reading the directory or file.
-    fn save_file_exists(
-        data: &Vec<u8>,
-        path: &Path,
-        base_name: &String,
-        extension: &String,
-    ) -> Result<(bool, Option<PathBuf>)> {
-        // Check if there is any file in the provided path that begins with the base name and ends
-        // in the extension.
-        for file in fs::read_dir(path)? {
-            if let Ok(file) = file {
-                let file_name = file.file_name().to_string_lossy().into_owned();
-                if file_name.starts_with(base_name) && file_name.ends_with(extension) {
-                    let file_path = file.path();
-                    let other = fs::read(&file_path)?;
-                    if *data == other {
-                        // The file exists and is identical to the current Pokémon data.
-                        return Ok((true, Some(file_path)));
-                    }
-                }
+    /// The set of (extension, contents) of the files in the directory, or the corresponding error
+    /// if the directory or a file could not be read.
+    fn saved_files(path: &Path) -> Result<SavedFiles> {
+        let mut saved_files = HashSet::new();
+        for file in fs::read_dir(path)?.flatten() {
+            let file_path = file.path();
+            let Some(extension) = file_path.extension() else {
+                continue;
+            };
+            if file_path.is_file() {
+                saved_files.insert((
+                    extension.to_string_lossy().into_owned(),
+                    fs::read(&file_path)?,
+                ));
             }
         }
 
-        Ok((false, None))
+        Ok(saved_files)
     }
 
-    /// Loads a Pokémon from a binary file at the specified path.
+    /// Loads a Pokémon from a file at the specified path, in any of the formats the Pokémon are
+    /// saved in (see `SaveFormat`).
     ///
-    /// The file must be in the game's internal format, either Gen 4 or Gen 5, and representing
-    /// either boxed or party Pokémon.
+    /// The file must hold the data in the game's internal format, either Gen 4 or Gen 5, and
+    /// representing either boxed or party Pokémon, or be a JSON file with that data in base64.
+    /// Its extension is not checked: the format is told by the contents alone.
+    ///
+    /// The data is usually stored decrypted, but some tools (e.g., emulator dumps) store it
+    /// encrypted. If the stored checksum does not match the data, but it does once the data is
+    /// decrypted, the data is taken as encrypted and decrypted.
     ///
     /// # Arguments
     /// * `file_path` - The path to the file containing the Pokémon data.
@@ -1339,39 +2652,166 @@ impl Pokemon {
     /// `Ok(pokemon)`, where `pokemon` is the loaded Pokémon, if the pokémon was loaded
     /// successfully, or the corresponding error if there was an error during loading.
     pub fn load(file_path: &Path) -> Result<Pokemon> {
-        let file_path_str = file_path.to_string_lossy();
-        if !file_path_str.ends_with(".pkm")
-            && !file_path_str.ends_with(".pk4")
-            && !file_path_str.ends_with(".pk5")
-        {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "File must be a .pkm, .pk4, or .pk5 file",
-            ));
+        let mut data = fs::read(file_path)?;
+        // Binary Pokémon data is never valid JSON, so JSON files are told apart by parsing them:
+        if let Ok(json) = serde_json::from_slice::<PokemonJsonData>(&data) {
+            data = STANDARD_B64.decode(json.data).map_err(|e| {
+                GtsError::InvalidFile(format!("Invalid Pokémon data in the JSON file: {}", e))
+            })?;
         }
 
-        let data = fs::read(file_path)?;
+        if data.len() != BOXED_PKM_LEN && data.len() != GEN4_PKM_LEN && data.len() != GEN5_PKM_LEN {
+            return Err(GtsError::InvalidFile(format!(
+                "Invalid Pokémon file size: {}",
+                data.len()
+            )));
+        }
 
-        assert!(
-            data.len() == BOXED_PKM_LEN || data.len() == GEN4_PKM_LEN || data.len() == GEN5_PKM_LEN,
-            "Invalid Pokémon file size: {}",
-            data.len(),
-        );
+        // Detect encrypted data by its checksum, which is stored unencrypted:
+        let stored_checksum = u16::from_le_bytes([data[0x06], data[0x07]]);
+        if Self::compute_checksum(&data) != stored_checksum {
+            let decrypted = Self::to_decrypted_data(&data);
+            if Self::compute_checksum(&decrypted) == stored_checksum {
+                log::info!(
+                    "{} holds encrypted Pokémon data; decrypting it",
+                    file_path.display()
+                );
+                return Pokemon::try_deserialize(&decrypted);
+            }
+        }
+        log::debug!("{} holds decrypted Pokémon data", file_path.display());
 
-        let pokemon = Pokemon::deserialize(&data);
+        Pokemon::try_deserialize(&data)
+    }
 
-        Ok(pokemon)
+    /// Rewrites the met data of the Pokémon so that it appears to have been received through a
+    /// link trade.
+    ///
+    /// The met location is set to the link trade location of the Pokémon's generation, and the
+    /// met date is set to the current date.
+    ///
+    /// **This changes the Pokémon's apparent history**: the original met location and date are
+    /// lost.
+    pub fn set_link_trade_origin(&mut self) {
+        self.met_location = if !self.is_gen5 {
+            Location::Gen4(Gen4Location::LinkTradeMet)
+        } else {
+            Location::Gen5(Gen5Location::LinkTradeInGame)
+        };
+        self.met_date = LocalTime::now().date_naive();
+    }
+
+    /// Evolves the Pokémon as trading it would, if its species evolves by trade.
+    ///
+    /// Eggs, and Pokémon holding an Everstone, do not evolve. The item required to evolve, if
+    /// any, is consumed. The Pokémon keeps the same ability slot, and its name is updated to the
+    /// new species' one unless it has a nickname (or its default names are unknown; see
+    /// `self.default_name()`).
+    ///
+    /// Species that only exist in Gen 5 are not evolved into when the Pokémon is a Gen 4 one. The
+    /// stats of party Pokémon are recomputed for the new species.
+    ///
+    /// Returns whether the Pokémon evolved.
+    pub fn evolve_by_trade(&mut self) -> bool {
+        if self.is_egg || self.held_item.id() == EVERSTONE {
+            return false;
+        }
+
+        let last_species = if !self.is_gen5 {
+            LAST_GEN4_POKEMON
+        } else {
+            LAST_GEN5_POKEMON
+        };
+        let Some(&[_, item, evolution]) = TRADE_EVOLUTIONS.iter().find(|&&[species, item, _]| {
+            species == self.species.id() && (item == 0 || item == self.held_item.id())
+        }) else {
+            return false;
+        };
+        if evolution > last_species {
+            return false;
+        }
+
+        // Keep the ability slot, and the default name if not nicknamed:
+        let ability_slot = self
+            .species_abilities()
+            .iter()
+            .position(|&ability| ability == self.ability.id());
+        let had_default_name = self.nickname_is_default();
+
+        self.species = should_be_some!(
+            IdFeature::from_species_id(evolution),
+            "Invalid species ID: {}",
+            evolution
+        );
+        let abilities = self.species_abilities();
+        let ability = match ability_slot.map(|slot| abilities[slot]) {
+            Some(ability) if ability != 0 => ability,
+            _ => self.pid_ability(),
+        };
+        self.ability = should_be_some!(
+            IdFeature::from_ability_id(ability),
+            "Invalid ability ID: {}",
+            ability
+        );
+        if let (true, Some(name)) = (had_default_name, self.default_name()) {
+            self.name = name.to_string();
+        }
+        if item != 0 {
+            let no_item = if !self.is_gen5 {
+                IdFeature::from_gen4_item_id(0)
+            } else {
+                IdFeature::from_gen5_item_id(0)
+            };
+            self.held_item = should_be_some!(no_item, "Could not get `None` item from items map");
+        }
+        self.recompute_stats();
+
+        true
     }
 
-    /// Converts a Pokémon from Gen 4 to Gen 5.
+    /// Converts a Pokémon from Gen 4 to Gen 5, as Poké Transfer does.
     ///
-    /// This makes it possible to send a Pokémon received from a Gen 4 game to Gen 4 game.
+    /// Panics if the Pokémon cannot be converted. See `try_convert_to_gen5` for a non-panicking
+    /// alternative.
     pub fn convert_to_gen5(&mut self) {
+        self.try_convert_to_gen5()
+            .unwrap_or_else(|e| panic!("Failed to convert Pokémon to Gen 5: {}", e))
+    }
+
+    /// Attempts to convert a Pokémon from Gen 4 to Gen 5, as Poké Transfer does.
+    ///
+    /// This makes it possible to send a Pokémon received from a Gen 4 game to a Gen 5 game. The
+    /// nature, derived from the PID in Gen 4, is stored in its own field in Gen 5; the held item
+    /// is remapped to its Gen 5 ID; and the locations are set to the Poké Transfer ones.
+    ///
+    /// # Returns
+    /// `Ok(())` if the conversion was successful, or `GtsError::Unsupported` if the species or the
+    /// held item of the Pokémon does not exist in Gen 5 (e.g., Gen 4 mail). Every Gen 4 move
+    /// exists in Gen 5.
+    pub fn try_convert_to_gen5(&mut self) -> Result<()> {
         if self.is_gen5 {
-            return;
+            return Ok(());
         }
 
+        if self.species.id() == 0 || self.species.id() > LAST_GEN5_POKEMON {
+            return Err(GtsError::Unsupported(format!(
+                "Species {} does not exist in Gen 5",
+                self.species
+            )));
+        }
+        let held_item = item_gen4_to_gen5(self.held_item.id())
+            .and_then(IdFeature::from_gen5_item_id)
+            .ok_or_else(|| {
+                GtsError::Unsupported(format!(
+                    "Held item {} does not exist in Gen 5",
+                    self.held_item
+                ))
+            })?;
         self.is_gen5 = true;
+        // The original data is laid out for the other generation:
+        self.original_bytes = None;
+
+        self.held_item = held_item;
 
         // Set locations to the one used for Poké Transfers from previous gens:
         self.met_location = Location::Gen5(Gen5Location::OtherRegionDistantLand);
@@ -1380,6 +2820,8 @@ impl Pokemon {
         } else {
             self.egg_location = Location::Gen5(Gen5Location::OtherRegionDistantLand);
         }
+
+        Ok(())
     }
 
     /// Attempts to convert a Pokémon from Gen 5 to Gen 4.
@@ -1395,13 +2837,14 @@ impl Pokemon {
         }
 
         if self.species.id() > LAST_GEN4_POKEMON {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "The Pokémon was introduced in Gen 5",
+            return Err(GtsError::Unsupported(
+                "The Pokémon was introduced in Gen 5".to_string(),
             ));
         }
 
         self.is_gen5 = false;
+        // The original data is laid out for the other generation:
+        self.original_bytes = None;
 
         // Set locations to the default for unknown locations:
         self.met_location = Location::Gen4(Gen4Location::FarawayPlace);
@@ -1415,17 +2858,20 @@ impl Pokemon {
             self.ball = Pokeball::default();
         }
 
-        if self.held_item.id() > LAST_GEN4_ITEM {
-            self.held_item = should_be_some!(
-                IdFeature::from_gen4_item_id(0),
-                "Could not get Gen 4 `None` item from items map"
-            );
-        }
+        // Remove held items unavailable in Gen 4:
+        self.held_item = item_gen5_to_gen4(self.held_item.id())
+            .and_then(IdFeature::from_gen4_item_id)
+            .unwrap_or_else(|| {
+                should_be_some!(
+                    IdFeature::from_gen4_item_id(0),
+                    "Could not get Gen 4 `None` item from items map"
+                )
+            });
 
         // Delete moves unavailable in Gen 4; set Struggle as only move if all 4 are erased:
         let mut erased_moves = 0;
         for mov in self.moves.iter_mut() {
-            if mov.id() > LAST_GEN4_MOVE {
+            if mov.id() > Self::max_move_id_for_gen(false) {
                 *mov = should_be_some!(
                     IdFeature::from_move_id(0),
                     "Could not get Gen 4 `None` move from moves map"
@@ -1444,3 +2890,739 @@ impl Pokemon {
         Ok(())
     }
 }
+
+impl PartialEq for Pokemon {
+    /// Compares two Pokémon by their serialized data, their canonical representation.
+    ///
+    /// Comparing field by field could tell apart Pokémon with the same data, e.g., because of the
+    /// cached shininess, or of the generated stats of boxed Pokémon.
+    fn eq(&self, other: &Self) -> bool {
+        self.serialize() == other.serialize()
+    }
+}
+
+impl Eq for Pokemon {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::Fixture;
+
+    #[test]
+    fn deserialize_rejects_species_after_gen5() {
+        let mut bytes = vec![0x00; GEN5_PKM_LEN];
+        bytes[0x08..0x0A].copy_from_slice(&700u16.to_le_bytes());
+
+        let result = Pokemon::try_deserialize(&bytes);
+        assert!(matches!(result, Err(GtsError::Unsupported(_))));
+    }
+
+    #[test]
+    fn deserialize_handles_every_data_length() {
+        let party = Fixture::Gen4Party.bytes();
+
+        let boxed = Pokemon::try_deserialize(&party[..BOXED_PKM_LEN]).unwrap();
+        assert!(boxed.is_boxed());
+        assert!(!Pokemon::try_deserialize(party).unwrap().is_boxed());
+        for len in [0, BLOCKS_END - 1, BLOCKS_END + 1, BATTLE_STATS_END] {
+            let result = Pokemon::try_deserialize(&party[..len]);
+            assert!(matches!(result, Err(GtsError::InvalidValue(_))));
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_unhandled_bytes() {
+        let mut bytes = Fixture::Gen4Party.bytes().to_vec();
+        // Unused byte, status condition (poisoned), and mail:
+        bytes[0x42] = 0x5A;
+        bytes[0x88] = 0x08;
+        bytes[0x9C..0xA0].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        let checksum = Pokemon::compute_checksum(&bytes);
+        bytes[0x06..0x08].copy_from_slice(&checksum.to_le_bytes());
+
+        let mut pokemon = Pokemon::try_deserialize(&bytes).unwrap();
+        assert_eq!(pokemon.serialize(), bytes);
+
+        pokemon.discard_original_bytes();
+        assert_ne!(pokemon, Pokemon::try_deserialize(&bytes).unwrap());
+        let serialized = pokemon.serialize();
+        assert_eq!(serialized[0x42], 0x00);
+        assert_eq!(serialized[0x9C..0xA0], [0x00; 4]);
+        // The status condition is a field of its own:
+        assert_eq!(pokemon.status_condition, StatusCondition::Poison);
+        assert_eq!(serialized[0x88], 0x08);
+    }
+
+    #[test]
+    fn pkhex_compatible_bytes_are_a_healthy_party_pokemon() {
+        let mut bytes = Fixture::Gen4Party.bytes().to_vec();
+        // Fainted and poisoned:
+        bytes[0x88] = 0x08;
+        bytes[0x8E..0x90].copy_from_slice(&[0x00, 0x00]);
+        let pokemon = Pokemon::try_deserialize(&bytes).unwrap();
+
+        let pkhex_bytes = pokemon.to_pkhex_compatible_bytes();
+        assert_eq!(pkhex_bytes.len(), GEN4_PKM_LEN);
+        assert_eq!(pkhex_bytes[0x88], 0x00);
+        assert_eq!(pkhex_bytes[0x8E..0x90], pkhex_bytes[0x90..0x92]);
+        assert_ne!(pkhex_bytes[0x8E..0x90], [0x00, 0x00]);
+        assert_eq!(pkhex_bytes[..BOXED_PKM_LEN], bytes[..BOXED_PKM_LEN]);
+        // Unlike the regular serialization, which keeps it fainted:
+        assert_eq!(pokemon.current_hp, Some(0));
+        assert_eq!(pokemon.serialize()[0x8E..0x90], [0x00, 0x00]);
+
+        let boxed = Pokemon::try_deserialize(&bytes[..BOXED_PKM_LEN]).unwrap();
+        let pkhex_bytes = boxed.to_pkhex_compatible_bytes();
+        assert_eq!(pkhex_bytes.len(), GEN4_PKM_LEN);
+        assert_eq!(pkhex_bytes[0x8C], boxed.level);
+        assert_ne!(pkhex_bytes[0x8E..0x90], [0x00, 0x00]);
+
+        // The current HP can be set, but not over the maximum HP:
+        let mut pokemon = boxed;
+        pokemon.current_hp = Some(1);
+        assert_eq!(pokemon.serialize()[0x8E..0x90], [0x01, 0x00]);
+        pokemon.current_hp = Some(u16::MAX);
+        let serialized = pokemon.serialize();
+        assert_eq!(serialized[0x8E..0x90], serialized[0x90..0x92]);
+    }
+
+    #[test]
+    fn days_since_counts_whole_days() {
+        let date = NaiveDate::from_ymd_opt(2008, 2, 28).unwrap();
+
+        assert_eq!(Pokemon::days_since(date, date), 0);
+        assert_eq!(
+            Pokemon::days_since(date, NaiveDate::from_ymd_opt(2008, 3, 1).unwrap()),
+            2
+        );
+        assert_eq!(
+            Pokemon::days_since(NaiveDate::from_ymd_opt(2008, 3, 1).unwrap(), date),
+            -2
+        );
+
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.met_date = LocalTime::now().date_naive();
+        assert_eq!(pokemon.days_since_met(), 0);
+        pokemon.egg_date = None;
+        assert_eq!(pokemon.days_since_egg(), None);
+    }
+
+    #[test]
+    fn unknown_encounter_types_load_and_are_reported() {
+        let mut bytes = Fixture::Gen4Party.bytes().to_vec();
+        bytes[0x85] = 0x42;
+        let checksum = Pokemon::compute_checksum(&bytes);
+        bytes[0x06..0x08].copy_from_slice(&checksum.to_le_bytes());
+
+        let pokemon = Pokemon::try_deserialize(&bytes).unwrap();
+        assert_eq!(pokemon.encounter_type, EncounterType::Unknown(0x42));
+        assert_eq!(pokemon.serialize()[0x85], 0x42);
+        assert_eq!(check_encounter_consistency(&pokemon).len(), 1);
+        assert!(matches!(pokemon.validate(), Err(GtsError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn gen5_stored_nature_can_differ_from_pid_nature() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+        assert!(pokemon.stored_nature().is_some());
+
+        let mut mismatched = Fixture::Gen5Party.bytes().to_vec();
+        let pid_nature = pokemon.pid() % 25;
+        mismatched[0x41] = ((pid_nature + 1) % 25) as u8;
+        let checksum = Pokemon::compute_checksum(&mismatched);
+        mismatched[0x06..0x08].copy_from_slice(&checksum.to_le_bytes());
+        let mismatched = Pokemon::try_deserialize(&mismatched).unwrap();
+
+        assert_eq!(
+            mismatched.stored_nature().unwrap().id_and_name.id() as u32,
+            (pid_nature + 1) % 25
+        );
+        assert_eq!(mismatched.pid_nature().id_and_name.id() as u32, pid_nature);
+        assert!(!mismatched.nature_matches_pid());
+
+        let gen4 = Fixture::Gen4Party.pokemon();
+        assert!(gen4.stored_nature().is_none());
+        assert!(gen4.nature_matches_pid());
+    }
+
+    #[test]
+    fn eq_compares_serialized_data() {
+        let pokemon = Fixture::Gen4Boxed.pokemon();
+
+        let mut other = pokemon.clone();
+        other.set_pid(pokemon.pid());
+        assert_eq!(pokemon, other);
+
+        other.friendship = other.friendship.wrapping_add(1);
+        assert_ne!(pokemon, other);
+    }
+
+    #[test]
+    fn convert_to_gen5_remaps_held_item() {
+        let pokemon = Fixture::Gen4Party.pokemon();
+
+        let mut with_ball = pokemon.clone();
+        with_ball.held_item = IdFeature::from_gen4_item_name("Poke Ball").unwrap();
+        with_ball.convert_to_gen5();
+        assert!(with_ball.is_gen5());
+        assert_eq!(with_ball.origin_generation(), 4);
+        assert_eq!(with_ball.held_item.name(), "Poké Ball");
+        assert_eq!(
+            with_ball.nature().id_and_name.id(),
+            (pokemon.pid() % 25) as u16
+        );
+
+        let mut with_mail = pokemon.clone();
+        with_mail.held_item = IdFeature::from_gen4_item_name("Grass Mail").unwrap();
+        let result = with_mail.try_convert_to_gen5();
+        assert!(matches!(result, Err(GtsError::Unsupported(_))));
+        assert!(!with_mail.is_gen5());
+    }
+
+    #[test]
+    fn set_trainer_name_validates_length_and_encoding() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+
+        pokemon.set_trainer_name("DAWN").unwrap();
+        assert_eq!(pokemon.trainer_name(), "DAWN");
+        let result = pokemon.set_trainer_name("BARRY123");
+        assert!(matches!(result, Err(GtsError::InvalidName(_))));
+        let result = pokemon.set_trainer_name("🦀");
+        assert!(matches!(result, Err(GtsError::Encode(_))));
+        assert_eq!(pokemon.trainer_name(), "DAWN");
+
+        pokemon.try_convert_to_gen5().unwrap();
+        pokemon.set_trainer_name("Cheren").unwrap();
+        let result = pokemon.set_trainer_name("🦀");
+        assert!(matches!(result, Err(GtsError::Encode(_))));
+    }
+
+    #[test]
+    fn name_limits_count_characters() {
+        let mut pokemon = Fixture::Gen5Party.pokemon();
+
+        // 30 bytes in UTF-8, but 10 characters:
+        pokemon
+            .set_name("ポケモンポケモンポケ".to_string())
+            .unwrap();
+        assert_eq!(pokemon.name(), "ポケモンポケモンポケ");
+        let result = pokemon.set_name("ポケモンポケモンポケモ".to_string());
+        assert!(matches!(result, Err(GtsError::InvalidName(_))));
+
+        pokemon.set_trainer_name("サトシサトシサ").unwrap();
+        let result = pokemon.set_trainer_name("サトシサトシサト");
+        assert!(matches!(result, Err(GtsError::InvalidName(_))));
+    }
+
+    #[test]
+    fn ivs_and_evs_are_validated_and_regenerate_stats() {
+        let mut pokemon = Fixture::Gen5Party.pokemon();
+        assert!(!pokemon.is_boxed());
+
+        let all = |value| StatsFeature {
+            hp: value,
+            atk: value,
+            def: value,
+            spa: value,
+            spd: value,
+            spe: value,
+        };
+        assert!(matches!(
+            pokemon.set_ivs(all(32)),
+            Err(GtsError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            pokemon.set_evs(StatsFeature {
+                atk: 253,
+                ..StatsFeature::default()
+            }),
+            Err(GtsError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            pokemon.set_evs(all(100)),
+            Err(GtsError::InvalidValue(_))
+        ));
+        let maxed = StatsFeature {
+            atk: 252,
+            spe: 252,
+            hp: 6,
+            ..StatsFeature::default()
+        };
+        pokemon.set_evs(maxed).unwrap();
+        assert_eq!((pokemon.evs.atk, pokemon.evs.spe), (252, 252));
+
+        // Clamping keeps the EVs within the same limits:
+        pokemon.evs = StatsFeature {
+            hp: 255,
+            atk: 255,
+            ..StatsFeature::default()
+        };
+        pokemon.clamp_evs();
+        assert_eq!((pokemon.evs.hp, pokemon.evs.atk), (252, 252));
+
+        // The usual example of the stat formulas: a level 78 Adamant Garchomp.
+        pokemon.species = IdFeature::from_species_id(445).unwrap();
+        pokemon.level = 78;
+        pokemon.nature = Nature::from_id(3).unwrap();
+        pokemon
+            .set_ivs(StatsFeature {
+                hp: 24,
+                atk: 12,
+                def: 30,
+                spa: 16,
+                spd: 23,
+                spe: 5,
+            })
+            .unwrap();
+        pokemon
+            .set_evs(StatsFeature {
+                hp: 74,
+                atk: 190,
+                def: 91,
+                spa: 48,
+                spd: 84,
+                spe: 23,
+            })
+            .unwrap();
+        let stats = pokemon.stats.unwrap();
+        assert_eq!(
+            [stats.hp, stats.atk, stats.def, stats.spa, stats.spd, stats.spe],
+            [289, 278, 193, 135, 171, 171]
+        );
+        let bytes = pokemon.serialize();
+        assert_eq!(u16::from_le_bytes([bytes[0x90], bytes[0x91]]), 289);
+    }
+
+    #[test]
+    fn party_stats_are_recomputed_after_edits() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.set_experience(1000);
+        let stored_hp = |pokemon: &Pokemon| {
+            let bytes = pokemon.serialize();
+            u16::from_le_bytes([bytes[0x90], bytes[0x91]])
+        };
+        let hp = stored_hp(&pokemon);
+        assert_eq!(hp, pokemon.generate_stats().hp);
+
+        // Editing the EVs directly leaves the stats stale, until they are recomputed:
+        pokemon.evs.hp = 252;
+        assert_eq!(stored_hp(&pokemon), hp);
+        pokemon.recompute_stats();
+        assert!(stored_hp(&pokemon) > hp);
+
+        // The setters recompute them:
+        let hp = stored_hp(&pokemon);
+        pokemon.set_experience(100_000);
+        assert!(stored_hp(&pokemon) > hp);
+        assert_eq!(stored_hp(&pokemon), pokemon.generate_stats().hp);
+    }
+
+    #[test]
+    fn gen5_names_filling_the_buffer_need_no_terminator() {
+        let name = "Kosmemosas";
+        let encoded = Pokemon::encode_name_gen5(name);
+        assert_eq!(encoded.len(), 22);
+
+        // All 10 characters, without the terminator:
+        assert_eq!(Pokemon::decode_name_gen5(&encoded[..20]).unwrap(), name);
+        // With the terminator, and padding that is not valid UTF-16 after it:
+        let mut padded = encoded.clone();
+        padded.extend([0x00, 0xD8]);
+        assert_eq!(Pokemon::decode_name_gen5(&padded).unwrap(), name);
+
+        assert!(Pokemon::decode_name_gen5(&[0xff, 0xff, 0x41, 0x00]).is_err());
+    }
+
+    #[test]
+    fn set_held_item_uses_the_generation_items() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+
+        pokemon.set_held_item("Metal Coat").unwrap();
+        assert_eq!(pokemon.held_item.id(), 233);
+        // Gen 5 items do not exist in Gen 4:
+        let result = pokemon.set_held_item("Dream Ball");
+        assert!(matches!(result, Err(GtsError::InvalidValue(_))));
+        assert_eq!(pokemon.held_item_name(), "Metal Coat");
+
+        pokemon.try_convert_to_gen5().unwrap();
+        pokemon.set_held_item("Dream Ball").unwrap();
+        assert_eq!(pokemon.held_item.id(), 576);
+        pokemon.set_held_item("Nothing").unwrap();
+        assert_eq!(pokemon.held_item.id(), 0);
+    }
+
+    #[test]
+    fn save_skips_duplicates_and_never_overwrites() {
+        let dir = std::env::temp_dir().join(format!("gts-rs-save-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let pokemon = Fixture::Gen4Party.pokemon();
+        let mut other = pokemon.clone();
+        other.friendship = other.friendship.wrapping_add(1);
+
+        assert!(pokemon.save(Some(&dir), None).unwrap());
+        assert!(!pokemon.save(Some(&dir), None).unwrap());
+        // Same species and name, likely saved in the same second:
+        assert!(other.save(Some(&dir), None).unwrap());
+        assert!(pokemon.save(Some(&dir), Some("pkm".to_string())).unwrap());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn save_formats_encode_the_same_pokemon() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+
+        assert_eq!(SaveFormat::Pk.encode(&pokemon), pokemon.serialize());
+        assert_eq!(SaveFormat::Ek.extension(pokemon.is_gen5()), "ek5");
+        let encrypted = SaveFormat::Ek.encode(&pokemon);
+        assert_eq!(Pokemon::to_decrypted_data(&encrypted), pokemon.serialize());
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&SaveFormat::Json.encode(&pokemon)).unwrap();
+        assert_eq!(json["species"], "Snivy");
+        assert_eq!(json["generation"], 5);
+        assert_eq!(
+            STANDARD_B64.decode(json["data"].as_str().unwrap()).unwrap(),
+            pokemon.serialize()
+        );
+        let mut empty_handed = pokemon.clone();
+        empty_handed.set_held_item("Nothing").unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&SaveFormat::Json.encode(&empty_handed)).unwrap();
+        assert_eq!(json["held_item"], "Nothing");
+
+        assert_eq!("pk4".parse::<SaveFormat>().unwrap(), SaveFormat::Pk);
+        assert_eq!("ek".parse::<SaveFormat>().unwrap(), SaveFormat::Ek);
+        assert!("pkx".parse::<SaveFormat>().is_err());
+    }
+
+    #[test]
+    fn every_save_format_loads_back() {
+        for fixture in [Fixture::Gen4Party, Fixture::Gen5Party] {
+            let pokemon = fixture.pokemon();
+            for format in [SaveFormat::Pk, SaveFormat::Ek, SaveFormat::Json] {
+                let dir = std::env::temp_dir().join(format!(
+                    "gts-rs-load-test-{}-{:?}",
+                    std::process::id(),
+                    format
+                ));
+                let _ = fs::remove_dir_all(&dir);
+                assert!(pokemon.save_with_format(Some(&dir), format).unwrap());
+
+                let file = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+                let extension = format.extension(pokemon.is_gen5());
+                assert!(SaveFormat::FILE_EXTENSIONS.contains(&extension));
+                assert_eq!(Pokemon::load(&file).unwrap(), pokemon, "{:?}", format);
+                fs::remove_dir_all(&dir).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn summary_json_has_the_relevant_features() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+
+        let json = pokemon.summary_json();
+        assert_eq!(json["species"], "Snivy");
+        assert_eq!(json["nickname"], pokemon.name().as_str());
+        assert_eq!(json["level"], pokemon.level());
+        assert_eq!(json["ivs"]["spe"], pokemon.ivs.spe);
+        assert_eq!(json["tid"], pokemon.trainer_id);
+        assert_eq!(json["sid"], pokemon.trainer_secret_id);
+        assert_eq!(
+            json["moves"].as_array().unwrap().len(),
+            pokemon.known_moves().len()
+        );
+        assert_eq!(json["met"]["date"], pokemon.met_date.to_string());
+    }
+
+    #[test]
+    fn shiny_value_is_the_xor_of_ids_and_pid() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.trainer_id = 0x1234;
+        pokemon.trainer_secret_id = 0x5678;
+
+        // 0x1234 ^ 0x5678 = 0x444C:
+        pokemon.pid = 0x444C_0000;
+        assert_eq!(pokemon.shiny_value(), 0);
+        assert!(pokemon.is_shiny() && pokemon.is_square_shiny());
+        pokemon.pid = 0x444C_0007;
+        assert_eq!(pokemon.shiny_value(), 7);
+        assert!(pokemon.is_shiny() && !pokemon.is_square_shiny());
+        pokemon.pid = 0x444C_0008;
+        assert_eq!(pokemon.shiny_value(), 8);
+        assert!(!pokemon.is_shiny());
+    }
+
+    #[test]
+    fn level_is_normalized_from_experience() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+
+        // Turtwig has the Medium Slow curve: 973 points are level 12, and 1261 are level 13.
+        pokemon.set_experience(1000);
+        assert_eq!(pokemon.level, 12);
+        assert!(pokemon.level_matches_experience());
+        pokemon.set_experience(1261);
+        assert_eq!(pokemon.level, 13);
+        pokemon.set_experience(0);
+        assert_eq!(pokemon.level, 1);
+
+        pokemon.level = 50;
+        assert!(!pokemon.level_matches_experience());
+        assert!(pokemon.normalize_level());
+        assert_eq!(pokemon.level, 1);
+        assert!(!pokemon.normalize_level());
+    }
+
+    #[test]
+    fn validate_rejects_moves_from_later_generations() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.set_gender(pokemon.expected_gender()).unwrap();
+        pokemon.fix_ability();
+        pokemon.normalize_level();
+        // A Pokémon caught in the wild, instead of the fixture's event egg:
+        pokemon.egg_date = None;
+        pokemon.egg_location = Location::Gen4(Gen4Location::NO_EGG_LOCATION);
+        pokemon.met_location = Location::Gen4(Gen4Location::Route201);
+        pokemon.fateful = false;
+        pokemon.is_nicknamed = !pokemon.nickname_is_default();
+        pokemon.set_moves(&["Tackle", "Shadow Force"]).unwrap();
+        assert_eq!(Pokemon::max_move_id_for_gen(false), 467);
+        assert!(pokemon.validate().is_ok());
+
+        pokemon.set_moves(&["Tackle", "Scald"]).unwrap();
+        assert!(matches!(pokemon.validate(), Err(GtsError::InvalidValue(_))));
+
+        pokemon.try_convert_to_gen5().unwrap();
+        pokemon.set_moves(&["Tackle", "V-create"]).unwrap();
+        assert_eq!(Pokemon::max_move_id_for_gen(true), 559);
+        // V-create does not exist in the Gen 4 origin game, but it does in a Gen 5 one:
+        assert!(matches!(pokemon.validate(), Err(GtsError::InvalidValue(_))));
+        pokemon.origin_game = Game::Black;
+        assert!(pokemon.validate().is_ok());
+    }
+
+    #[test]
+    fn max_pp_applies_pp_ups() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.set_moves(&["Outrage", "Tackle"]).unwrap();
+        pokemon.move_pps = [24, 35, 0, 0];
+        pokemon.move_pp_ups = [3, 0, 0, 0];
+
+        assert_eq!(pokemon.max_pp(0), Some(24));
+        assert_eq!(pokemon.max_pp(1), Some(35));
+        assert_eq!(pokemon.max_pp(2), Some(0));
+        assert_eq!(pokemon.max_pp(4), None);
+
+        // Outrage has less PP in Gen 5:
+        pokemon.is_gen5 = true;
+        assert_eq!(pokemon.max_pp(0), Some(16));
+    }
+
+    #[test]
+    fn hidden_power_type_matches_its_name() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+
+        let all = |iv| StatsFeature {
+            hp: iv,
+            atk: iv,
+            def: iv,
+            spa: iv,
+            spd: iv,
+            spe: iv,
+        };
+        pokemon.ivs = all(31);
+        assert_eq!(pokemon.hidden_power_type(), Type::Dark);
+        assert_eq!(pokemon.get_hidden_power(), ("Dark".to_string(), 70));
+        pokemon.ivs = all(30);
+        assert_eq!(pokemon.hidden_power_type(), Type::Fighting);
+        assert_eq!(
+            pokemon.hidden_power_type().to_string(),
+            pokemon.get_hidden_power().0
+        );
+    }
+
+    #[test]
+    fn evolve_by_trade_consumes_the_required_item() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+
+        // Turtwig does not evolve by trade:
+        assert!(!pokemon.evolve_by_trade());
+
+        pokemon.species = IdFeature::from_species_id(95).unwrap();
+        pokemon.held_item = IdFeature::from_gen4_item_name("Everstone").unwrap();
+        assert!(!pokemon.evolve_by_trade());
+        pokemon.held_item = IdFeature::from_gen4_item_name("Metal Coat").unwrap();
+        assert!(pokemon.evolve_by_trade());
+        assert_eq!(pokemon.species.id(), 208);
+        assert_eq!(pokemon.held_item.id(), 0);
+        assert!(pokemon
+            .legal_abilities()
+            .iter()
+            .any(|ability| ability.id() == pokemon.ability.id()));
+
+        // Boldore only evolves into Gigalith in Gen 5:
+        pokemon.species = IdFeature::from_species_id(525).unwrap();
+        assert!(!pokemon.evolve_by_trade());
+        pokemon.is_gen5 = true;
+        assert!(pokemon.evolve_by_trade());
+        assert_eq!(pokemon.species.id(), 526);
+
+        // Feebas evolves into Milotic holding a Prism Scale, a Gen 5 item:
+        pokemon.species = IdFeature::from_species_id(349).unwrap();
+        pokemon.held_item = IdFeature::from_gen5_item_name("Prism Scale").unwrap();
+        assert!(pokemon.evolve_by_trade());
+        assert_eq!(pokemon.species.id(), 350);
+        assert_eq!(pokemon.held_item.id(), 0);
+    }
+
+    #[test]
+    fn encryption_step_xors_lcg_output() {
+        let mut data = [0u8; 8];
+        Pokemon::encryption_step(&mut data, 0x12345678);
+        assert_eq!(data, [0x71, 0x0B, 0xEA, 0x84, 0x8A, 0xD9, 0xE0, 0xF4]);
+
+        Pokemon::encryption_step(&mut data, 0x12345678);
+        assert_eq!(data, [0; 8]);
+    }
+
+    #[test]
+    fn encryption_round_trip() {
+        let decrypted = Fixture::Gen4Party.bytes();
+
+        let encrypted = Pokemon::to_encrypted_data(decrypted);
+        assert_ne!(encrypted[0x08..], decrypted[0x08..]);
+        assert_eq!(Pokemon::to_decrypted_data(&encrypted), decrypted);
+    }
+
+    #[test]
+    fn load_detects_encrypted_data() {
+        let decrypted = Fixture::Gen4Party.bytes();
+        // The extension plays no part in the detection:
+        for extension in ["pk4", "ek4", "bin"] {
+            let path = std::env::temp_dir().join(format!(
+                "gts-rs-test-{}.{}",
+                std::process::id(),
+                extension
+            ));
+            fs::write(&path, Pokemon::to_encrypted_data(decrypted)).unwrap();
+
+            let loaded = Pokemon::load(&path);
+            fs::remove_file(&path).unwrap();
+            assert_eq!(
+                loaded.unwrap(),
+                Pokemon::try_deserialize(decrypted).unwrap(),
+                "{}",
+                extension
+            );
+        }
+    }
+
+    #[test]
+    fn legality_summary_flags_over_cap_evs() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.evs = StatsFeature::default();
+        pokemon.evs.atk = 253;
+        pokemon.evs.spe = 252;
+
+        let summary = pokemon.legality_summary();
+        assert_eq!(summary.ev_total, 505);
+        assert_eq!(summary.over_cap_evs, vec![(Stat::Atk, 253)]);
+        assert!(!summary.is_clean());
+        assert!(summary
+            .to_string()
+            .starts_with("EVs 505/510 (over cap: Atk 253), "));
+    }
+
+    #[test]
+    fn gen4_locations_use_the_offsets_of_the_origin_game() {
+        let bytes = Fixture::Gen4Boxed.bytes();
+        let read =
+            |bytes: &[u8], offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let faraway_place = Gen4Location::FarawayPlace as u16;
+
+        for (game, met, egg, expected_offsets) in [
+            // Platinum-only location: only readable by Platinum and HGSS.
+            (
+                Game::Platinum,
+                Gen4Location::DistortionWorld,
+                Gen4Location::NO_EGG_LOCATION,
+                [0, 117, 0, faraway_place],
+            ),
+            // HGSS-only location, and HGSS-only special egg location.
+            (
+                Game::HeartGold,
+                Gen4Location::Route29,
+                Gen4Location::MrPokemon,
+                [2013, 177, faraway_place, faraway_place],
+            ),
+            // Location in every Gen 4 game, from Platinum: stored in both offsets.
+            (
+                Game::Platinum,
+                Gen4Location::Route201,
+                Gen4Location::DayCareCouple,
+                [2000, 16, 2000, 16],
+            ),
+            // Diamond and Pearl only use their own offsets.
+            (
+                Game::Pearl,
+                Gen4Location::Route201,
+                Gen4Location::DayCareCouple,
+                [0, 0, 2000, 16],
+            ),
+        ] {
+            let mut pokemon = Pokemon::try_deserialize(bytes).unwrap();
+            pokemon.origin_game = game;
+            pokemon.met_location = Location::Gen4(met);
+            pokemon.egg_location = Location::Gen4(egg);
+
+            let serialized = pokemon.serialize();
+            let offsets = [0x44, 0x46, 0x7E, 0x80].map(|offset| read(&serialized, offset));
+            assert_eq!(offsets, expected_offsets, "{} {} {}", game, met, egg);
+
+            let deserialized = Pokemon::try_deserialize(&serialized).unwrap();
+            assert_eq!(deserialized.met_location, Location::Gen4(met));
+            assert_eq!(deserialized.egg_location, Location::Gen4(egg));
+        }
+    }
+
+    #[test]
+    fn nature_consistent_pids_keep_gender_ability_and_shininess() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        let pid = pokemon.pid;
+        let square_shiny_sid = pokemon.trainer_id ^ (pid >> 16) as u16 ^ pid as u16;
+        for trainer_secret_id in [square_shiny_sid, square_shiny_sid ^ 0x100] {
+            pokemon.trainer_secret_id = trainer_secret_id;
+            for nature_id in 0..25 {
+                pokemon.pid = pid;
+                pokemon.nature = Nature::from_id(nature_id).unwrap();
+                let gender = pokemon.expected_gender();
+                let is_shiny = pokemon.is_shiny();
+
+                let new_pid = pokemon.nature_consistent_pid().unwrap();
+                pokemon.pid = new_pid;
+                assert_eq!(new_pid % 25, nature_id as u32);
+                assert_eq!(new_pid & 0xFF, pid & 0xFF);
+                assert_eq!(pokemon.expected_gender(), gender);
+                assert_eq!(pokemon.is_shiny(), is_shiny);
+            }
+        }
+    }
+
+    #[test]
+    fn cleared_egg_dates_are_not_kept_from_the_original_data() {
+        let mut pokemon = Fixture::Gen4Party.pokemon();
+        pokemon.egg_date = NaiveDate::from_ymd_opt(2009, 1, 2);
+        let mut reloaded = Pokemon::try_deserialize(&pokemon.serialize()).unwrap();
+        assert_eq!(reloaded.egg_date, NaiveDate::from_ymd_opt(2009, 1, 2));
+
+        reloaded.egg_date = None;
+        let serialized = reloaded.serialize();
+        assert_eq!(serialized[0x78..0x7B], [0, 0, 0]);
+        assert_eq!(
+            Pokemon::try_deserialize(&serialized).unwrap().egg_date,
+            None
+        );
+    }
+}
@@ -0,0 +1,699 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Reading and writing of the Pokémon stored in Gen 4 and Gen 5 save files.
+//!
+//! The save file layouts follow the ones documented by PKHeX (https://github.com/kwsch/PKHeX).
+//! Only the party and the PC boxes are supported.
+use getset::CopyGetters;
+use std::{fs, path::Path};
+
+use crate::{
+    error::{GtsError, Result},
+    pokemon::{Pokemon, BOXED_PKM_LEN, GEN4_PKM_LEN, GEN5_PKM_LEN},
+};
+
+/// Size of the save data in Gen 4 and Gen 5 save files.
+///
+/// Some emulators append extra data to save files, which is ignored.
+const SAVE_DATA_LEN: usize = 0x80000;
+/// Offset of the second copy of the save data in Gen 4 save files.
+const GEN4_BACKUP_OFFSET: usize = 0x40000;
+/// Number of Pokémon slots in each PC box.
+pub const BOX_SLOTS: usize = 30;
+/// Maximum number of Pokémon in the party.
+pub const PARTY_SLOTS: usize = 6;
+
+/// Enum that identifies the different save file layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveLayout {
+    /// Pokémon Diamond and Pearl.
+    DiamondPearl,
+    /// Pokémon Platinum.
+    Platinum,
+    /// Pokémon HeartGold and SoulSilver.
+    HeartGoldSoulSilver,
+    /// Pokémon Black and White.
+    BlackWhite,
+    /// Pokémon Black 2 and White 2.
+    Black2White2,
+}
+
+/// Struct describing the blocks of a Gen 4 save file layout.
+///
+/// Gen 4 save files hold two copies of the save data, each made of a general block (which holds
+/// the party) and a storage block (which holds the PC boxes). Each block ends in a footer, whose
+/// last 0x10 bytes are the save counter, the block size, a magic number, the block ID, and the
+/// CRC16-CCITT checksum of the data before the footer.
+struct Gen4Blocks {
+    /// The layout described.
+    layout: SaveLayout,
+    /// Offset and size (including the footer) of the general block.
+    general: (usize, usize),
+    /// Offset and size (including the footer) of the storage block.
+    storage: (usize, usize),
+    /// Size of the block footers.
+    footer_len: usize,
+    /// Offset of the party within the general block.
+    party: usize,
+    /// Offset of the first PC box within the storage block.
+    boxes: usize,
+    /// Distance between the starts of two consecutive PC boxes.
+    box_stride: usize,
+}
+
+/// Blocks of the different Gen 4 save file layouts.
+const GEN4_BLOCKS: [Gen4Blocks; 3] = [
+    Gen4Blocks {
+        layout: SaveLayout::DiamondPearl,
+        general: (0x0000, 0xC100),
+        storage: (0xC100, 0x121E0),
+        footer_len: 0x14,
+        party: 0x94,
+        boxes: 0x04,
+        box_stride: BOX_SLOTS * BOXED_PKM_LEN,
+    },
+    Gen4Blocks {
+        layout: SaveLayout::Platinum,
+        general: (0x0000, 0xCF2C),
+        storage: (0xCF2C, 0x121E4),
+        footer_len: 0x14,
+        party: 0x9C,
+        boxes: 0x04,
+        box_stride: BOX_SLOTS * BOXED_PKM_LEN,
+    },
+    Gen4Blocks {
+        layout: SaveLayout::HeartGoldSoulSilver,
+        general: (0x0000, 0xF628),
+        storage: (0xF700, 0x12310),
+        footer_len: 0x10,
+        party: 0x94,
+        boxes: 0x00,
+        box_stride: 0x1000,
+    },
+];
+/// Number of PC boxes in Gen 4 games.
+const GEN4_BOX_COUNT: usize = 18;
+
+/// Offset and size of the checksum table of Black and White save files.
+///
+/// Gen 5 save files hold a single copy of the save data, split into blocks. The CRC16-CCITT
+/// checksum of each block is stored 2 bytes after its end, and mirrored in the checksum table,
+/// which is in turn followed by its own checksum.
+const BW_CHECKSUM_TABLE: (usize, usize) = (0x23F00, 0x8C);
+/// Offset and size of the checksum table of Black 2 and White 2 save files.
+const B2W2_CHECKSUM_TABLE: (usize, usize) = (0x25F00, 0x94);
+/// Offset of the party in Gen 5 save files.
+const GEN5_PARTY: usize = 0x18E04;
+/// Offset of the first PC box in Gen 5 save files.
+const GEN5_BOXES: usize = 0x400;
+/// Distance between the starts of two consecutive PC boxes in Gen 5 save files.
+const GEN5_BOX_STRIDE: usize = 0x1000;
+/// Number of PC boxes in Gen 5 games.
+const GEN5_BOX_COUNT: usize = 24;
+
+/// Enum that identifies where the checksums covering the PC boxes are stored.
+#[derive(Clone, Copy, Debug)]
+enum BoxChecksums {
+    /// Gen 4: in the footer of the storage block in use.
+    Gen4 {
+        /// Offset of the storage block in use.
+        storage: usize,
+        /// Size of the storage block, including the footer.
+        size: usize,
+        /// Size of the storage block footer.
+        footer_len: usize,
+    },
+    /// Gen 5: after each box block, and in the checksum table.
+    Gen5 {
+        /// Offset and size of the checksum table.
+        table: (usize, usize),
+    },
+}
+
+/// Struct representing a Gen 4 or Gen 5 save file, giving access to the Pokémon stored in it.
+#[derive(Debug, CopyGetters)]
+pub struct SaveFile {
+    /// The raw save file data.
+    data: Vec<u8>,
+    /// The layout of the save file.
+    #[get_copy = "pub"]
+    layout: SaveLayout,
+    /// Offset of the party: a 32-bit Pokémon count, followed by the Pokémon.
+    party_offset: usize,
+    /// Offset of the first PC box.
+    boxes_offset: usize,
+    /// Distance between the starts of two consecutive PC boxes.
+    box_stride: usize,
+    /// Number of PC boxes in the save file.
+    #[get_copy = "pub"]
+    box_count: usize,
+    /// Location of the checksums covering the PC boxes.
+    box_checksums: BoxChecksums,
+}
+
+impl SaveFile {
+    /// Loads a save file from disk.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the save file.
+    ///
+    /// Returns `GtsError::Io` if the file could not be read, or `GtsError::InvalidFile` if it is
+    /// not a Gen 4 or Gen 5 save file.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// Creates a save file from its raw data.
+    ///
+    /// The layout of the save file is detected from its block checksums. For Gen 4 save files, the
+    /// most recent valid copy of each block is used.
+    ///
+    /// # Arguments
+    /// * `data` - The raw save file data.
+    ///
+    /// Returns `GtsError::InvalidFile` if the data is not a Gen 4 or Gen 5 save file.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        if data.len() < SAVE_DATA_LEN {
+            return Err(GtsError::InvalidFile(format!(
+                "Save file too small: {} bytes, expected {}",
+                data.len(),
+                SAVE_DATA_LEN
+            )));
+        }
+
+        // Try the Gen 4 layouts first, using the most recent valid copy of each block:
+        for blocks in GEN4_BLOCKS.iter() {
+            let general = latest_gen4_block(&data, blocks.general, blocks.footer_len);
+            let storage = latest_gen4_block(&data, blocks.storage, blocks.footer_len);
+            if let (Some(general), Some(storage)) = (general, storage) {
+                return Ok(Self {
+                    data,
+                    layout: blocks.layout,
+                    party_offset: general + blocks.party,
+                    boxes_offset: storage + blocks.boxes,
+                    box_stride: blocks.box_stride,
+                    box_count: GEN4_BOX_COUNT,
+                    box_checksums: BoxChecksums::Gen4 {
+                        storage,
+                        size: blocks.storage.1,
+                        footer_len: blocks.footer_len,
+                    },
+                });
+            }
+        }
+
+        // Then, the Gen 5 layouts, which can be told apart by their checksum tables:
+        let (layout, table) = if is_valid_gen5_block(&data, BW_CHECKSUM_TABLE) {
+            (SaveLayout::BlackWhite, BW_CHECKSUM_TABLE)
+        } else if is_valid_gen5_block(&data, B2W2_CHECKSUM_TABLE) {
+            (SaveLayout::Black2White2, B2W2_CHECKSUM_TABLE)
+        } else {
+            return Err(GtsError::InvalidFile(
+                "Not a valid Gen 4 or Gen 5 save file".to_string(),
+            ));
+        };
+
+        Ok(Self {
+            data,
+            layout,
+            party_offset: GEN5_PARTY,
+            boxes_offset: GEN5_BOXES,
+            box_stride: GEN5_BOX_STRIDE,
+            box_count: GEN5_BOX_COUNT,
+            box_checksums: BoxChecksums::Gen5 { table },
+        })
+    }
+
+    /// Gets whether the save file belongs to a Gen 5 game.
+    pub fn is_gen5(&self) -> bool {
+        matches!(
+            self.layout,
+            SaveLayout::BlackWhite | SaveLayout::Black2White2
+        )
+    }
+
+    /// Gets the Pokémon in the party.
+    ///
+    /// Returns `GtsError::InvalidFile` if the party size is invalid, or the corresponding error if
+    /// any of the Pokémon could not be deserialized.
+    pub fn party(&self) -> Result<Vec<Pokemon>> {
+        let count = read_u32(&self.data, self.party_offset) as usize;
+        if count > PARTY_SLOTS {
+            return Err(GtsError::InvalidFile(format!(
+                "Invalid party size: {}",
+                count
+            )));
+        }
+
+        let pkm_len = if !self.is_gen5() {
+            GEN4_PKM_LEN
+        } else {
+            GEN5_PKM_LEN
+        };
+        (0..count)
+            .map(|i| {
+                let start = self.party_offset + 0x04 + i * pkm_len;
+                let decrypted = Pokemon::to_decrypted_data(&self.data[start..start + pkm_len]);
+                Pokemon::try_deserialize_with_generation(&decrypted, self.is_gen5())
+            })
+            .collect()
+    }
+
+    /// Gets the Pokémon in a PC box slot.
+    ///
+    /// # Arguments
+    /// * `box_index` - The index of the PC box, starting at 0.
+    /// * `slot` - The index of the slot within the box, starting at 0.
+    ///
+    /// Returns `None` if the slot is empty, `GtsError::InvalidValue` if the box or slot does not
+    /// exist, or the corresponding error if the Pokémon could not be deserialized.
+    pub fn box_slot(&self, box_index: usize, slot: usize) -> Result<Option<Pokemon>> {
+        let start = self.box_slot_offset(box_index, slot)?;
+        let encrypted = &self.data[start..start + BOXED_PKM_LEN];
+        if encrypted.iter().all(|&byte| byte == 0) {
+            return Ok(None);
+        }
+
+        // Slots whose species is "None" are empty too:
+        let decrypted = Pokemon::to_decrypted_data(encrypted);
+        if decrypted[0x08..0x0A] == [0, 0] {
+            return Ok(None);
+        }
+
+        Pokemon::try_deserialize_with_generation(&decrypted, self.is_gen5()).map(Some)
+    }
+
+    /// Gets the contents of each slot of a PC box.
+    ///
+    /// # Arguments
+    /// * `box_index` - The index of the PC box, starting at 0.
+    ///
+    /// Returns the Pokémon in each slot (`None` for empty slots), `GtsError::InvalidValue` if the
+    /// box does not exist, or the corresponding error if any of the Pokémon could not be
+    /// deserialized.
+    pub fn box_pokemon(&self, box_index: usize) -> Result<Vec<Option<Pokemon>>> {
+        (0..BOX_SLOTS)
+            .map(|slot| self.box_slot(box_index, slot))
+            .collect()
+    }
+
+    /// Gets all the Pokémon in the PC boxes, skipping the empty slots.
+    ///
+    /// Returns the corresponding error if any of the Pokémon could not be deserialized.
+    pub fn all_box_pokemon(&self) -> Result<Vec<Pokemon>> {
+        let mut pokemon = Vec::new();
+        for box_index in 0..self.box_count {
+            pokemon.extend(self.box_pokemon(box_index)?.into_iter().flatten());
+        }
+
+        Ok(pokemon)
+    }
+
+    /// Writes a Pokémon into a PC box slot, replacing its previous contents.
+    ///
+    /// The Pokémon is converted to the generation of the save file if needed, and the checksums
+    /// covering the box are updated so that the game accepts the modified save file. For Gen 4
+    /// save files, the copy of the storage block in use is modified in place, so its save counter
+    /// is kept.
+    ///
+    /// # Arguments
+    /// * `box_index` - The index of the PC box, starting at 0.
+    /// * `slot` - The index of the slot within the box, starting at 0.
+    /// * `pokemon` - The Pokémon to write.
+    ///
+    /// Returns `GtsError::InvalidValue` if the box or slot does not exist, or
+    /// `GtsError::Unsupported` if the Pokémon cannot be converted to the save file's generation.
+    pub fn set_box_slot(&mut self, box_index: usize, slot: usize, pokemon: &Pokemon) -> Result<()> {
+        let start = self.box_slot_offset(box_index, slot)?;
+
+        let mut pokemon = pokemon.clone();
+        if !pokemon.is_gen5() && self.is_gen5() {
+            pokemon.try_convert_to_gen5()?;
+        } else if pokemon.is_gen5() && !self.is_gen5() {
+            pokemon.try_convert_to_gen4()?;
+        }
+
+        let encrypted = Pokemon::to_encrypted_data(&pokemon.serialize()[..BOXED_PKM_LEN]);
+        self.data[start..start + BOXED_PKM_LEN].copy_from_slice(&encrypted);
+        self.update_box_checksums(box_index);
+
+        Ok(())
+    }
+
+    /// Gets the raw save file data, including any changes made to it.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Saves the save file to disk, including any changes made to it.
+    ///
+    /// # Arguments
+    /// * `path` - The path to save the save file to.
+    ///
+    /// Returns `GtsError::Io` if the file could not be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, &self.data)?;
+
+        Ok(())
+    }
+
+    /// Recomputes the checksums covering a PC box, after modifying it.
+    ///
+    /// # Arguments
+    /// * `box_index` - The index of the modified PC box, starting at 0.
+    fn update_box_checksums(&mut self, box_index: usize) {
+        match self.box_checksums {
+            BoxChecksums::Gen4 {
+                storage,
+                size,
+                footer_len,
+            } => {
+                let end = storage + size;
+                let checksum = crc16_ccitt(&self.data[storage..end - footer_len]);
+                write_u16(&mut self.data, end - 0x02, checksum);
+            }
+            BoxChecksums::Gen5 {
+                table: (table, table_len),
+            } => {
+                // Box blocks come right after the box names block in the checksum table:
+                let start = self.boxes_offset + box_index * self.box_stride;
+                let box_len = BOX_SLOTS * BOXED_PKM_LEN;
+                let checksum = crc16_ccitt(&self.data[start..start + box_len]);
+                write_u16(&mut self.data, start + box_len + 0x02, checksum);
+                write_u16(&mut self.data, table + (box_index + 1) * 0x02, checksum);
+
+                let table_checksum = crc16_ccitt(&self.data[table..table + table_len]);
+                write_u16(&mut self.data, table + table_len + 0x02, table_checksum);
+            }
+        }
+    }
+
+    /// Gets the offset of a PC box slot in the save file data.
+    ///
+    /// # Arguments
+    /// * `box_index` - The index of the PC box, starting at 0.
+    /// * `slot` - The index of the slot within the box, starting at 0.
+    ///
+    /// Returns `GtsError::InvalidValue` if the box or slot does not exist.
+    fn box_slot_offset(&self, box_index: usize, slot: usize) -> Result<usize> {
+        if box_index >= self.box_count || slot >= BOX_SLOTS {
+            return Err(GtsError::InvalidValue(format!(
+                "Invalid PC box slot: box {}, slot {}",
+                box_index, slot
+            )));
+        }
+
+        Ok(self.boxes_offset + box_index * self.box_stride + slot * BOXED_PKM_LEN)
+    }
+}
+
+/// Gets the offset of the most recent valid copy of a Gen 4 block.
+///
+/// # Arguments
+/// * `data` - The save file data.
+/// * `(offset, size)` - The offset and size (including the footer) of the block in the first copy
+///   of the save data.
+/// * `footer_len` - The size of the block footer.
+///
+/// Returns `None` if no copy of the block is valid.
+fn latest_gen4_block(
+    data: &[u8],
+    (offset, size): (usize, usize),
+    footer_len: usize,
+) -> Option<usize> {
+    [offset, GEN4_BACKUP_OFFSET + offset]
+        .into_iter()
+        .filter(|&start| {
+            let end = start + size;
+            read_u32(data, end - 0x0C) as usize == size
+                && crc16_ccitt(&data[start..end - footer_len]) == read_u16(data, end - 0x02)
+        })
+        .max_by_key(|&start| read_u32(data, start + size - 0x10))
+}
+
+/// Gets whether a Gen 5 block matches the checksum stored 2 bytes after its end.
+///
+/// # Arguments
+/// * `data` - The save file data.
+/// * `(offset, size)` - The offset and size of the block.
+fn is_valid_gen5_block(data: &[u8], (offset, size): (usize, usize)) -> bool {
+    crc16_ccitt(&data[offset..offset + size]) == read_u16(data, offset + size + 0x02)
+}
+
+/// Computes the CRC16-CCITT checksum of some data, as used in the save file blocks.
+///
+/// # Arguments
+/// * `data` - The data to compute the checksum of.
+fn crc16_ccitt(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Reads a little-endian 16-bit value from the save file data.
+///
+/// # Arguments
+/// * `data` - The save file data.
+/// * `offset` - The offset of the value.
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Writes a little-endian 16-bit value into the save file data.
+///
+/// # Arguments
+/// * `data` - The save file data.
+/// * `offset` - The offset of the value.
+/// * `value` - The value to write.
+fn write_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Reads a little-endian 32-bit value from the save file data.
+///
+/// # Arguments
+/// * `data` - The save file data.
+/// * `offset` - The offset of the value.
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::Fixture;
+
+    /// Builds an empty Gen 4 save file, whose first copy of the blocks is valid.
+    ///
+    /// # Arguments
+    /// * `blocks` - The blocks of the save file layout.
+    fn gen4_save(blocks: &Gen4Blocks) -> Vec<u8> {
+        let mut data = vec![0; SAVE_DATA_LEN];
+        for (start, size) in [blocks.general, blocks.storage] {
+            let end = start + size;
+            data[end - 0x0C..end - 0x08].copy_from_slice(&(size as u32).to_le_bytes());
+            let checksum = crc16_ccitt(&data[start..end - blocks.footer_len]);
+            write_u16(&mut data, end - 0x02, checksum);
+        }
+
+        data
+    }
+
+    /// Builds an empty Gen 5 save file, whose checksum table is valid.
+    ///
+    /// # Arguments
+    /// * `(table, table_len)` - The offset and size of the checksum table of the layout.
+    fn gen5_save((table, table_len): (usize, usize)) -> Vec<u8> {
+        let mut data = vec![0; SAVE_DATA_LEN];
+        let checksum = crc16_ccitt(&data[table..table + table_len]);
+        write_u16(&mut data, table + table_len + 0x02, checksum);
+
+        data
+    }
+
+    /// Gets the data of the boxed part of a Pokémon.
+    ///
+    /// # Arguments
+    /// * `pokemon` - The Pokémon.
+    fn boxed_data(pokemon: &Pokemon) -> Vec<u8> {
+        pokemon.serialize()[..BOXED_PKM_LEN].to_vec()
+    }
+
+    #[test]
+    fn crc16_matches_the_ccitt_check_value() {
+        assert_eq!(crc16_ccitt(b"123456789"), 0x29B1);
+        assert_eq!(crc16_ccitt(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn gen4_box_slots_round_trip_and_update_the_storage_checksum() {
+        let pokemon = Fixture::Gen4Boxed.pokemon();
+        for blocks in GEN4_BLOCKS.iter() {
+            let mut save = SaveFile::from_bytes(gen4_save(blocks)).unwrap();
+            assert_eq!(save.layout(), blocks.layout);
+
+            let (storage, size) = blocks.storage;
+            let stored_checksum = read_u16(save.as_bytes(), storage + size - 0x02);
+            save.set_box_slot(3, 7, &pokemon).unwrap();
+            assert_eq!(
+                boxed_data(&save.box_slot(3, 7).unwrap().unwrap()),
+                boxed_data(&pokemon)
+            );
+
+            let checksum = read_u16(save.as_bytes(), storage + size - 0x02);
+            assert_ne!(checksum, stored_checksum);
+            assert_eq!(
+                checksum,
+                crc16_ccitt(&save.as_bytes()[storage..storage + size - blocks.footer_len])
+            );
+
+            // The modified save file is still accepted, with the Pokémon in place:
+            let reloaded = SaveFile::from_bytes(save.as_bytes().to_vec()).unwrap();
+            assert_eq!(reloaded.layout(), blocks.layout);
+            assert_eq!(reloaded.all_box_pokemon().unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn gen5_box_slots_round_trip_and_update_the_box_checksums() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+        for (layout, table) in [
+            (SaveLayout::BlackWhite, BW_CHECKSUM_TABLE),
+            (SaveLayout::Black2White2, B2W2_CHECKSUM_TABLE),
+        ] {
+            let mut save = SaveFile::from_bytes(gen5_save(table)).unwrap();
+            assert_eq!(save.layout(), layout);
+
+            save.set_box_slot(2, 5, &pokemon).unwrap();
+            assert_eq!(
+                boxed_data(&save.box_slot(2, 5).unwrap().unwrap()),
+                boxed_data(&pokemon)
+            );
+
+            let reloaded = SaveFile::from_bytes(save.as_bytes().to_vec()).unwrap();
+            assert_eq!(reloaded.layout(), layout);
+            assert_eq!(reloaded.all_box_pokemon().unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn gen5_party_is_read_from_its_offset() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+        let mut data = gen5_save(BW_CHECKSUM_TABLE);
+        data[0x18E04..0x18E08].copy_from_slice(&1u32.to_le_bytes());
+        data[0x18E08..0x18E08 + GEN5_PKM_LEN]
+            .copy_from_slice(&Pokemon::to_encrypted_data(&pokemon.serialize()));
+
+        let save = SaveFile::from_bytes(data).unwrap();
+        assert_eq!(save.party().unwrap(), vec![pokemon]);
+    }
+
+    #[test]
+    fn gen5_boxes_are_written_to_their_offsets() {
+        let pokemon = Fixture::Gen5Party.pokemon();
+        let encrypted = Pokemon::to_encrypted_data(&boxed_data(&pokemon));
+        for (layout, (table, table_len)) in [
+            (SaveLayout::BlackWhite, (0x23F00, 0x8C)),
+            (SaveLayout::Black2White2, (0x25F00, 0x94)),
+        ] {
+            let mut save = SaveFile::from_bytes(gen5_save((table, table_len))).unwrap();
+            assert_eq!(save.layout(), layout);
+            assert_eq!(save.box_count(), 24);
+
+            save.set_box_slot(2, 3, &pokemon).unwrap();
+            let data = save.as_bytes();
+            let box_start = 0x400 + 2 * 0x1000;
+            let slot_start = box_start + 3 * BOXED_PKM_LEN;
+            assert_eq!(data[slot_start..slot_start + BOXED_PKM_LEN], encrypted[..]);
+
+            // The box checksum follows the box, and is mirrored in the checksum table:
+            let box_end = box_start + BOX_SLOTS * BOXED_PKM_LEN;
+            let checksum = crc16_ccitt(&data[box_start..box_end]);
+            assert_eq!(read_u16(data, box_end + 0x02), checksum);
+            assert_eq!(read_u16(data, table + 3 * 0x02), checksum);
+            assert_eq!(
+                read_u16(data, table + table_len + 0x02),
+                crc16_ccitt(&data[table..table + table_len])
+            );
+        }
+    }
+
+    #[test]
+    fn gen4_pokemon_are_converted_for_gen5_saves() {
+        let mut save = SaveFile::from_bytes(gen5_save(BW_CHECKSUM_TABLE)).unwrap();
+        save.set_box_slot(0, 0, &Fixture::Gen4Boxed.pokemon())
+            .unwrap();
+
+        assert!(save.box_slot(0, 0).unwrap().unwrap().is_gen5());
+    }
+
+    #[test]
+    fn empty_box_slots_are_none() {
+        let save = SaveFile::from_bytes(gen4_save(&GEN4_BLOCKS[0])).unwrap();
+        assert!(save.box_slot(0, 0).unwrap().is_none());
+        assert!(save.all_box_pokemon().unwrap().is_empty());
+
+        // A slot holding encrypted data whose species is "None" is empty too:
+        let mut save = SaveFile::from_bytes(gen5_save(BW_CHECKSUM_TABLE)).unwrap();
+        let mut pokemon_data = boxed_data(&Fixture::Gen5Party.pokemon());
+        pokemon_data[0x08..0x0A].copy_from_slice(&[0, 0]);
+        let encrypted = Pokemon::to_encrypted_data(&pokemon_data);
+        let start = save.box_slot_offset(0, 0).unwrap();
+        save.data[start..start + BOXED_PKM_LEN].copy_from_slice(&encrypted);
+        assert!(save.box_slot(0, 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_box_slots_are_rejected() {
+        let mut save = SaveFile::from_bytes(gen4_save(&GEN4_BLOCKS[0])).unwrap();
+        let pokemon = Fixture::Gen4Boxed.pokemon();
+
+        assert!(matches!(
+            save.box_slot(GEN4_BOX_COUNT, 0),
+            Err(GtsError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            save.set_box_slot(0, BOX_SLOTS, &pokemon),
+            Err(GtsError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_save_files_are_rejected() {
+        assert!(matches!(
+            SaveFile::from_bytes(vec![0; SAVE_DATA_LEN]),
+            Err(GtsError::InvalidFile(_))
+        ));
+        assert!(matches!(
+            SaveFile::from_bytes(vec![0; SAVE_DATA_LEN - 1]),
+            Err(GtsError::InvalidFile(_))
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Lightweight summaries of Pokémon, for listings and APIs.
+use serde::Serialize;
+
+use crate::pokemon::Pokemon;
+
+/// Struct summarizing the most relevant features of a Pokémon.
+///
+/// Unlike `Pokemon`, it does not expose raw data or internal flags, and can be serialized (e.g.,
+/// to JSON).
+#[derive(Clone, Debug, Serialize)]
+pub struct PokemonSummary {
+    /// The name of the Pokémon's species.
+    pub species: String,
+    /// The name of the Pokémon, which is the species name if it is not nicknamed.
+    pub nickname: String,
+    /// The level of the Pokémon.
+    pub level: u8,
+    /// The name of the Pokémon's nature.
+    pub nature: String,
+    /// Whether the Pokémon is shiny.
+    pub shiny: bool,
+    /// The gender of the Pokémon.
+    pub gender: String,
+    /// The name of the Pokémon's original trainer.
+    pub ot_name: String,
+    /// The Trainer ID of the Pokémon's original trainer.
+    pub tid: u16,
+    /// The names of the Pokémon's moves. Empty move slots are named "None".
+    pub moves: [String; 4],
+    /// The name of the item held by the Pokémon, or "Nothing" if it holds no item.
+    pub held_item: String,
+}
+
+impl From<&Pokemon> for PokemonSummary {
+    /// Summarizes the given Pokémon.
+    fn from(pokemon: &Pokemon) -> Self {
+        Self {
+            species: pokemon.species_name().to_string(),
+            nickname: pokemon.name().clone(),
+            level: pokemon.level(),
+            nature: pokemon.nature_name().to_string(),
+            shiny: pokemon.is_shiny(),
+            gender: pokemon.gender.to_string(),
+            ot_name: pokemon.trainer_name().clone(),
+            tid: pokemon.trainer_id,
+            moves: pokemon.moves.clone().map(|mov| mov.name().clone()),
+            held_item: pokemon.held_item_name().to_string(),
+        }
+    }
+}
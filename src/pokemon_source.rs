@@ -0,0 +1,566 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Non-interactive sources of Pokémon to send to the games.
+use pkm_utils::pokemon::SaveFormat;
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+/// Name of the subdirectory of a dispensing source that the sent Pokémon files are moved to.
+const DISPENSED_DIR: &str = "dispensed";
+
+/// Enum that identifies the orders the Pokémon files of a directory source are sent in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceOrder {
+    /// In alphabetical order, starting over after the last file.
+    #[default]
+    Sequential,
+    /// A random file each time, which can be the same as the previous one.
+    Random,
+    /// Every file once, in a random order, shuffled again after the last file.
+    Shuffle,
+}
+
+impl FromStr for SourceOrder {
+    type Err = Error;
+
+    /// Parses the name of an order: `sequential`, `random` or `shuffle`.
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "sequential" => Ok(SourceOrder::Sequential),
+            "random" => Ok(SourceOrder::Random),
+            "shuffle" => Ok(SourceOrder::Shuffle),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unknown order: {} (expected sequential, random or shuffle)",
+                    name
+                ),
+            )),
+        }
+    }
+}
+
+/// Progress of a source through its Pokémon files.
+#[derive(Debug, Default)]
+struct SourceState {
+    /// Index of the next file to send, in the sequential order.
+    next_index: usize,
+    /// Files left to send in the current round, in the shuffle order; the next one is the last.
+    shuffled: Vec<PathBuf>,
+    /// Files sent by a dispensing source whose reception was not confirmed yet, by the profile ID
+    /// of the game they were sent to. They are not sent to other games meanwhile.
+    reserved: HashMap<Option<u32>, PathBuf>,
+}
+
+/// Source of the Pokémon to send to the games of one generation, instead of prompting for them.
+///
+/// The source is either a single Pokémon file, which is sent on every request, or a directory,
+/// whose Pokémon files are sent one per request, in the source's order (alphabetical by default).
+///
+/// A directory source can also dispense its Pokémon, for distribution events: each file sent is
+/// moved to the `dispensed` subdirectory once the game confirms its reception, so that it is only
+/// given out once.
+#[derive(Debug)]
+pub struct PokemonSource {
+    /// The path of the Pokémon file or directory.
+    path: PathBuf,
+    /// The order the files are sent in, if the source is a directory.
+    order: SourceOrder,
+    /// Whether each file sent is moved to the dispensed subdirectory, and whether the dispensed
+    /// files are moved back once all of them were sent, if the source dispenses its Pokémon.
+    dispensing: Option<bool>,
+    /// Progress through the files, if the source is a directory.
+    state: Mutex<SourceState>,
+}
+
+impl PokemonSource {
+    /// Creates a new Pokémon source from a file or directory path.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the Pokémon file, or of the directory containing Pokémon files.
+    ///
+    /// Returns an error of kind `NotFound` if the path does not exist.
+    pub fn new(path: PathBuf) -> Result<Self> {
+        if !path.exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Pokémon source not found: {}", path.display()),
+            ));
+        }
+
+        Ok(Self {
+            path,
+            order: SourceOrder::default(),
+            dispensing: None,
+            state: Mutex::new(SourceState::default()),
+        })
+    }
+
+    /// Sets the order the Pokémon files are sent in, if the source is a directory.
+    ///
+    /// # Arguments
+    /// * `order` - The order to send the files in.
+    pub fn with_order(mut self, order: SourceOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Makes the source dispense its Pokémon: each file sent is moved to the `dispensed`
+    /// subdirectory of the source once the game confirms its reception, so that it is only given
+    /// out once. See `self.confirm_reception()`.
+    ///
+    /// # Arguments
+    /// * `replenish` - Whether to move the dispensed files back once all of them were sent,
+    ///   instead of running out of Pokémon.
+    ///
+    /// Returns an error of kind `InvalidInput` if the source is not a directory, or the
+    /// corresponding error if the dispensed subdirectory could not be created.
+    pub fn with_dispensing(mut self, replenish: bool) -> Result<Self> {
+        if !self.path.is_dir() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Only directories can dispense Pokémon: {}",
+                    self.path.display()
+                ),
+            ));
+        }
+        fs::create_dir_all(self.dispensed_dir())?;
+
+        self.dispensing = Some(replenish);
+        Ok(self)
+    }
+
+    /// Gets the path of the source.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the path of the subdirectory the dispensed Pokémon files are moved to.
+    fn dispensed_dir(&self) -> PathBuf {
+        self.path.join(DISPENSED_DIR)
+    }
+
+    /// Gets the paths of all the Pokémon files of the source, in alphabetical order.
+    ///
+    /// Returns an error of kind `NotFound` if the source is a directory without Pokémon files, or
+    /// the corresponding error if the directory could not be read.
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        if !self.path.is_dir() {
+            return Ok(vec![self.path.clone()]);
+        }
+
+        let files = pokemon_files(&self.path)?;
+        if files.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No Pokémon files found in {}", self.path.display()),
+            ));
+        }
+
+        Ok(files)
+    }
+
+    /// Gets the path of the next Pokémon file to send.
+    ///
+    /// If the source dispenses its Pokémon, the file is reserved for the profile it is sent to
+    /// until the game confirms its reception (see `self.confirm_reception()`), and not sent to other
+    /// games meanwhile. A file still reserved for the profile was not received (e.g., the trade was
+    /// cancelled), so it is released first. Once all the files were dispensed, they are moved back
+    /// if the source replenishes itself.
+    ///
+    /// # Arguments
+    /// * `profile_id` - The profile ID of the game the file is sent to, if known.
+    ///
+    /// Returns an error of kind `NotFound` if the source is a directory without Pokémon files (left
+    /// to dispense), or the corresponding error if the directory could not be read, or the
+    /// dispensed files could not be moved back.
+    pub fn next_file(&self, profile_id: Option<u32>) -> Result<PathBuf> {
+        // Keep the state locked while listing the files, so that concurrent requests do not
+        // reserve or replenish the same files:
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(file) = state.reserved.remove(&profile_id) {
+            log::info!(
+                "The reception of {} was not confirmed; it can be sent again.",
+                file.display()
+            );
+        }
+
+        let files = match (self.unreserved_files(&state), self.dispensing) {
+            (Err(e), Some(true)) if e.kind() == ErrorKind::NotFound => {
+                self.replenish()?;
+                self.unreserved_files(&state)?
+            }
+            (files, _) => files?,
+        };
+
+        let file = match self.order {
+            // Dispensed files are gone, so the first file left is always the next one:
+            SourceOrder::Sequential if self.dispensing.is_some() => files[0].clone(),
+            SourceOrder::Sequential => {
+                let file = files[state.next_index % files.len()].clone();
+                state.next_index = state.next_index.wrapping_add(1);
+                file
+            }
+            SourceOrder::Random => {
+                let mut rng = SmallRng::from_rng(&mut rand::rng());
+                files[rng.random_range(0..files.len())].clone()
+            }
+            SourceOrder::Shuffle => {
+                // Skip the files removed or reserved since the round was shuffled:
+                state.shuffled.retain(|file| files.contains(file));
+                if state.shuffled.is_empty() {
+                    let mut rng = SmallRng::from_rng(&mut rand::rng());
+                    state.shuffled = files;
+                    state.shuffled.shuffle(&mut rng);
+                }
+                state
+                    .shuffled
+                    .pop()
+                    .expect("The shuffled files should not be empty after a reshuffle")
+            }
+        };
+
+        if self.dispensing.is_some() {
+            state.reserved.insert(profile_id, file.clone());
+        }
+
+        Ok(file)
+    }
+
+    /// Dispenses the Pokémon file sent to a game, once the game confirms its reception: the file is
+    /// moved to the dispensed subdirectory.
+    ///
+    /// Does nothing if the source does not dispense its Pokémon, or no file is reserved for the
+    /// profile.
+    ///
+    /// # Arguments
+    /// * `profile_id` - The profile ID of the game that confirmed the reception, if known.
+    ///
+    /// Returns the corresponding error if the file could not be moved.
+    pub fn confirm_reception(&self, profile_id: Option<u32>) -> Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(file) = state.reserved.remove(&profile_id) else {
+            return Ok(());
+        };
+
+        let dispensed = self
+            .dispensed_dir()
+            .join(file.file_name().unwrap_or_default());
+        fs::rename(&file, &dispensed)?;
+        let files_left = pokemon_files(&self.path)?.len();
+        log::info!("Dispensed {}; {} Pokémon left.", file.display(), files_left);
+
+        Ok(())
+    }
+
+    /// Gets the paths of the Pokémon files of the source not reserved for a game, in alphabetical
+    /// order.
+    ///
+    /// # Arguments
+    /// * `state` - The progress of the source, with the reserved files.
+    ///
+    /// Returns an error of kind `NotFound` if every Pokémon file is reserved, or the errors of
+    /// `self.files()`.
+    fn unreserved_files(&self, state: &SourceState) -> Result<Vec<PathBuf>> {
+        let mut files = self.files()?;
+        files.retain(|file| !state.reserved.values().any(|reserved| reserved == file));
+        if files.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "Every Pokémon file in {} is waiting for its reception to be confirmed",
+                    self.path.display()
+                ),
+            ));
+        }
+
+        Ok(files)
+    }
+
+    /// Moves all the dispensed Pokémon files back to the source directory.
+    ///
+    /// Returns an error of kind `NotFound` if no Pokémon file was dispensed, or the corresponding
+    /// error if the files could not be moved.
+    fn replenish(&self) -> Result<()> {
+        let dispensed = pokemon_files(&self.dispensed_dir())?;
+        if dispensed.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No Pokémon files found in {}", self.path.display()),
+            ));
+        }
+
+        for file in &dispensed {
+            fs::rename(file, self.path.join(file.file_name().unwrap_or_default()))?;
+        }
+        log::info!(
+            "All Pokémon in {} were dispensed; replenished {} Pokémon.",
+            self.path.display(),
+            dispensed.len()
+        );
+
+        Ok(())
+    }
+}
+
+/// Strips the quotes surrounding a path, as added by some terminals when dragging a file into them,
+/// or by copying the path of a file.
+///
+/// # Arguments
+/// * `path` - The path, possibly surrounded by single or double quotes.
+///
+/// Returns the path without the surrounding quotes, or as is if there are none.
+pub fn unquote_path(path: &str) -> &str {
+    ['\'', '"']
+        .iter()
+        .find_map(|&quote| {
+            path.strip_prefix(quote)
+                .and_then(|path| path.strip_suffix(quote))
+        })
+        .unwrap_or(path)
+}
+
+/// Reads a playlist of Pokémon files: a text file with the path of a Pokémon file on each line.
+///
+/// Blank lines, and lines starting with `#`, are skipped. Relative paths are relative to the
+/// directory of the playlist.
+///
+/// # Arguments
+/// * `path` - The path of the playlist.
+///
+/// Returns the paths of the Pokémon files, in the order they are listed, or the corresponding
+/// error if the playlist could not be read.
+pub fn read_playlist(path: &Path) -> Result<Vec<PathBuf>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("Could not read playlist {}: {}", path.display(), e),
+        )
+    })?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base_dir.join(unquote_path(line)))
+        .collect())
+}
+
+/// Gets the paths of the Pokémon files in a directory (those with the extension of any save format,
+/// see `SaveFormat::FILE_EXTENSIONS`), in alphabetical order.
+///
+/// # Arguments
+/// * `dir` - The directory to list the Pokémon files of.
+///
+/// Returns the corresponding error if the directory could not be read.
+fn pokemon_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|extension| {
+                    SaveFormat::FILE_EXTENSIONS.contains(&extension.to_string_lossy().as_ref())
+                })
+        })
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty directory for a test, with the given Pokémon files (and a file that is not
+    /// a Pokémon file).
+    ///
+    /// # Arguments
+    /// * `name` - The name of the test, to keep the directories of concurrent tests apart.
+    /// * `files` - The names of the Pokémon files to create.
+    fn source_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gts-rs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files.iter().chain(&["notes.txt"]) {
+            fs::write(dir.join(file), file).unwrap();
+        }
+
+        dir
+    }
+
+    /// Gets the names of the files of a source, in order.
+    ///
+    /// # Arguments
+    /// * `files` - The paths of the files.
+    fn names(files: &[PathBuf]) -> Vec<&str> {
+        files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sequential_sources_cycle_through_their_files() {
+        let dir = source_dir("sequential", &["b.pk5", "a.pk4"]);
+        let source = PokemonSource::new(dir.clone()).unwrap();
+
+        assert_eq!(names(&source.files().unwrap()), ["a.pk4", "b.pk5"]);
+        let sent = (0..3)
+            .map(|_| source.next_file(Some(1)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names(&sent), ["a.pk4", "b.pk5", "a.pk4"]);
+
+        // Nothing is dispensed:
+        source.confirm_reception(Some(1)).unwrap();
+        assert_eq!(source.files().unwrap().len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sources_list_the_files_of_every_save_format() {
+        let dir = source_dir("formats", &["a.pkm", "b.ek4", "c.ek5", "d.json"]);
+        let source = PokemonSource::new(dir.clone()).unwrap();
+
+        assert_eq!(
+            names(&source.files().unwrap()),
+            ["a.pkm", "b.ek4", "c.ek5", "d.json"]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn files_are_only_dispensed_once_received() {
+        let dir = source_dir("dispense", &["a.pk4", "b.pk4", "c.pk4"]);
+        let source = PokemonSource::new(dir.clone())
+            .unwrap()
+            .with_dispensing(false)
+            .unwrap();
+
+        // A cancelled trade leaves the file in the source, to be sent again:
+        assert_eq!(names(&[source.next_file(Some(1)).unwrap()]), ["a.pk4"]);
+        assert!(dir.join("a.pk4").exists());
+        assert_eq!(names(&[source.next_file(Some(1)).unwrap()]), ["a.pk4"]);
+
+        // Other games are not sent the file waiting for its confirmation:
+        assert_eq!(names(&[source.next_file(Some(2)).unwrap()]), ["b.pk4"]);
+
+        source.confirm_reception(Some(1)).unwrap();
+        assert!(!dir.join("a.pk4").exists());
+        assert!(dir.join(DISPENSED_DIR).join("a.pk4").exists());
+        assert_eq!(names(&source.files().unwrap()), ["b.pk4", "c.pk4"]);
+
+        // Confirming again, or for a profile without a file, does nothing:
+        source.confirm_reception(Some(1)).unwrap();
+        source.confirm_reception(Some(3)).unwrap();
+        assert_eq!(source.files().unwrap().len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dispensing_sources_run_out_unless_replenished() {
+        for replenish in [false, true] {
+            let dir = source_dir(&format!("replenish-{}", replenish), &["a.pk4"]);
+            let source = PokemonSource::new(dir.clone())
+                .unwrap()
+                .with_dispensing(replenish)
+                .unwrap();
+
+            source.next_file(Some(1)).unwrap();
+            // The only file is waiting for its confirmation:
+            let result = source.next_file(Some(2));
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::NotFound);
+            source.confirm_reception(Some(1)).unwrap();
+
+            let result = source.next_file(Some(1));
+            if replenish {
+                assert_eq!(names(&[result.unwrap()]), ["a.pk4"]);
+                assert!(dir.join("a.pk4").exists());
+            } else {
+                assert_eq!(result.unwrap_err().kind(), ErrorKind::NotFound);
+            }
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn shuffled_sources_send_every_file_once_per_round() {
+        let dir = source_dir("shuffle", &["a.pk4", "b.pk4", "c.pk4"]);
+        let source = PokemonSource::new(dir.clone())
+            .unwrap()
+            .with_order(SourceOrder::Shuffle);
+
+        for _ in 0..2 {
+            let mut round = (0..3)
+                .map(|_| source.next_file(None).unwrap())
+                .collect::<Vec<_>>();
+            round.sort();
+            assert_eq!(names(&round), ["a.pk4", "b.pk4", "c.pk4"]);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_directories_dispense_pokemon() {
+        let dir = source_dir("single-file", &["a.pk4"]);
+        let source = PokemonSource::new(dir.join("a.pk4")).unwrap();
+        let result = source.with_dispensing(false);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let result = PokemonSource::new(dir.join("missing.pk4"));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::NotFound);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn playlists_skip_comments_and_unquote_paths() {
+        let dir = source_dir("playlist", &[]);
+        let playlist = dir.join("playlist.txt");
+        fs::write(&playlist, "# Event\n\na.pk4\n  \"b c.pk5\"  \n/abs/d.pk4\n").unwrap();
+
+        assert_eq!(
+            read_playlist(&playlist).unwrap(),
+            [
+                dir.join("a.pk4"),
+                dir.join("b c.pk5"),
+                PathBuf::from("/abs/d.pk4")
+            ]
+        );
+        assert_eq!(unquote_path("'a.pk4'"), "a.pk4");
+        assert_eq!(unquote_path("\"a.pk4'"), "\"a.pk4'");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+/*
+ * GTS-RS - Rust tool for downloading/uploading Pokémon to Gen IV/V games via the in-game GTS.
+ * (Rust re-implementation of IR-GTS-MG: https://github.com/ScottehMax/IR-GTS-MG/tree/gen-5)
+ * Copyright (C) 2025  Bolu <bolu@tuta.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Recording and replaying of GTS sessions, for debugging purposes.
+//!
+//! A session log is a text file with one JSON-encoded `SessionEntry` per line.
+use actix_http::Request;
+use actix_service::{IntoServiceFactory, Service, ServiceFactory};
+use actix_web::{
+    body::{self, MessageBody},
+    dev::AppConfig,
+};
+use chrono::Local as LocalTime;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Error, ErrorKind, Result, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::http_server::{gts_app, is_token_request, set_dry_run, verify_gen5_footer, Generations};
+
+/// File the session is being recorded to, if session recording is enabled.
+static SESSION_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Enum representing an event of a GTS session.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SessionEntry {
+    /// A HTTP request received by the GTS server.
+    Http {
+        /// The path of the request.
+        path: String,
+        /// The query string of the request, without the leading `?`.
+        query: String,
+    },
+    /// A DNS query received by the DNS server.
+    Dns {
+        /// The DNS query, as displayed by the DNS client.
+        query: String,
+    },
+}
+
+/// Line of the session log: a session entry with the time it was recorded at.
+#[derive(Serialize, Deserialize)]
+struct SessionLine {
+    /// Time the entry was recorded at, as a UNIX timestamp in milliseconds.
+    timestamp: i64,
+    #[serde(flatten)]
+    entry: SessionEntry,
+}
+
+/// Starts recording the session to a file.
+///
+/// The entries are appended to the file if it already exists.
+///
+/// # Arguments
+/// * `path` - The path of the file to record the session to.
+///
+/// Returns an error of kind `AlreadyExists` if the session is already being recorded.
+pub fn start_recording(path: &Path) -> Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    SESSION_FILE.set(Mutex::new(file)).map_err(|_| {
+        Error::new(
+            ErrorKind::AlreadyExists,
+            "Session is already being recorded",
+        )
+    })?;
+    log::info!("Recording session to {}", path.display());
+
+    Ok(())
+}
+
+/// Records an entry into the session log.
+///
+/// Does nothing if session recording is not enabled. See `start_recording`.
+///
+/// # Arguments
+/// * `entry` - The session entry to record.
+pub fn record(entry: SessionEntry) {
+    let Some(file) = SESSION_FILE.get() else {
+        return;
+    };
+
+    let line = SessionLine {
+        timestamp: LocalTime::now().timestamp_millis(),
+        entry,
+    };
+    let Ok(json) = serde_json::to_string(&line) else {
+        log::warn!("Failed to serialize session entry: {:?}", line.entry);
+        return;
+    };
+
+    let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Err(e) = writeln!(file, "{}", json) {
+        log::warn!("Failed to record session entry: {}", e);
+    }
+}
+
+/// Replays a recorded session, feeding the recorded HTTP requests through the GTS endpoints.
+///
+/// No servers are started, and no console is needed. DNS entries cannot be replayed without the
+/// real DNS server, so they are only logged for context. The footers of the Gen 5 responses are
+/// verified (see `crate::http_server::verify_gen5_footer`).
+///
+/// The endpoints are run in dry-run mode, so replaying has no side effects: deposited Pokémon are
+/// not saved, and no Pokémon is sent. See `crate::http_server::set_dry_run`.
+///
+/// # Arguments
+/// * `path` - The path of the session log to replay.
+/// * `generations` - The generations whose endpoints the requests are replayed through.
+pub async fn replay(path: &Path, generations: Generations) -> Result<()> {
+    set_dry_run(true);
+    let app = gts_app(generations)
+        .into_factory()
+        .new_service(AppConfig::default())
+        .await
+        .map_err(|_| Error::other("Failed to initialize the GTS endpoints"))?;
+
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line: SessionLine = serde_json::from_str(line).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid session entry at line {}: {}", i + 1, e),
+            )
+        })?;
+
+        match line.entry {
+            SessionEntry::Http { path, query } => {
+                let is_gen5 = path.starts_with("/syachi2ds/");
+                let uri = if query.is_empty() {
+                    path
+                } else {
+                    format!("{}?{}", path, query)
+                };
+                log::info!("Replaying request: {}", uri);
+
+                let mut request = Request::new();
+                request.head_mut().uri = uri.parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid request URI at line {}: {}", i + 1, e),
+                    )
+                })?;
+                let response = app.call(request).await.map_err(|e| {
+                    Error::other(format!(
+                        "Failed to replay the request at line {}: {}",
+                        i + 1,
+                        e
+                    ))
+                })?;
+                log::info!("Response status: {}", response.status());
+
+                // Check the footer appended to the Gen 5 responses:
+                let body = body::to_bytes(response.into_body().boxed())
+                    .await
+                    .map_err(|e| {
+                        Error::other(format!(
+                            "Failed to read the response at line {}: {}",
+                            i + 1,
+                            e
+                        ))
+                    })?;
+                if is_gen5 && !body.is_empty() && !is_token_request(&query) {
+                    if verify_gen5_footer(&body) {
+                        log::info!("Response footer is valid");
+                    } else {
+                        log::warn!("Response footer does not match its body");
+                    }
+                }
+            }
+            SessionEntry::Dns { query } => {
+                log::info!("Recorded DNS query (not replayed): {}", query);
+            }
+        }
+    }
+
+    Ok(())
+}